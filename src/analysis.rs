@@ -0,0 +1,579 @@
+/*
+
+ADA Staking CLI Tool (⚠️ Not Offical or Investment Advice ⚠️)
+Copyright (C) 2022  zulrah93 <1s16slrse@mozmail.com>
+
+This program is free software; you can redistribute it and/or
+modify it under the terms of the GNU General Public License
+as published by the Free Software Foundation; either version 2
+of the License, or (at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program; if not, write to the Free Software
+Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301, USA.
+
+
+*/
+
+//! Analyses that run a pool more than once (target horizon, solver, Monte Carlo, sensitivity, break-even, tax, scenario metrics and the bench)
+//! They only return numbers main.rs formats and prints them
+
+use crate::{
+    calculate_closed_form, calculate_staked_pool, calculate_staked_pool_sampled, calculate_staked_pool_with, PriceEvent, PriceEventKind, SimulationOptions,
+    StakedCardanoPool, StakedCardanoPoolResult, Target,
+};
+use chrono::NaiveDate;
+use rand::Rng;
+use serde::Serialize;
+use std::time::Instant;
+
+/// Guard rails for solve_initial_ada the total is within half a cent of the target or the search gives up
+pub const SOLVE_MAX_ITERATIONS: u32 = 200;
+pub const SOLVE_TOLERANCE_USD: f64 = 0.005;
+
+/// Horizons timed by bench and how many runs each timing is averaged over
+pub const BENCH_YEARS: [f64; 4] = [1.0, 10.0, 50.0, 100.0];
+pub const BENCH_RUNS: u32 = 20;
+
+/// Largest relative difference allowed between the loop and the closed form before bench fails
+pub const BENCH_EPSILON: f64 = 1e-9;
+
+/// Finds the first day the target is reached within max_years and makes that the horizon so every output covers just those days
+/// None when it is not reached the pool is then left running the whole max_years
+pub fn apply_target(pool: &mut StakedCardanoPool, target: Target, max_years: f64, options: &SimulationOptions) -> Option<u64> {
+    pool.years_holding = max_years;
+    let options = SimulationOptions {
+        record_history: false,
+        record_payouts: false,
+        target: Some(target),
+        ..*options
+    };
+    let day = calculate_staked_pool(pool, &options).target_day;
+    if let Some(day) = day {
+        pool.years_holding = pool.years_for_days(day);
+    }
+    day
+}
+
+/// Starting ADA found by solve_initial_ada with the steps the search took and how far the total ended from the target in USD
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InitialAdaSolution {
+    pub ada: f64,
+    pub iterations: u32,
+    pub residual: f64,
+}
+
+/// Why solve_initial_ada has no answer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolveError {
+    /// The target is not a number above 0
+    InvalidTarget(f64),
+    /// The total with this much ADA is not a number
+    NotANumber(f64),
+    /// The ADA was doubled up to this amount without reaching the target
+    Unreachable(f64),
+}
+
+/// Bisection over the starting ADA the total grows with it so the answer is between 0 and the first doubling that overshoots
+/// The search runs keep no history the caller runs the solved pool again for its output
+pub fn solve_initial_ada(pool: &StakedCardanoPool, target: f64, options: &SimulationOptions) -> Result<InitialAdaSolution, SolveError> {
+    if !target.is_finite() || target <= 0.0 {
+        return Err(SolveError::InvalidTarget(target));
+    }
+    let options = SimulationOptions {
+        record_history: false,
+        record_payouts: false,
+        ..*options
+    };
+    let total_with = |ada: f64| -> Result<f64, SolveError> {
+        let total = calculate_staked_pool_with(&StakedCardanoPool { ada, ..pool.clone() }, &options, |_| {}).total();
+        if total.is_finite() {
+            Ok(total)
+        } else {
+            Err(SolveError::NotANumber(ada))
+        }
+    };
+    let mut iterations = 0;
+    let (mut low, mut high) = (0.0, pool.ada.max(1.0));
+    while total_with(high)? < target {
+        iterations += 1;
+        if iterations > SOLVE_MAX_ITERATIONS {
+            return Err(SolveError::Unreachable(high));
+        }
+        low = high;
+        high *= 2.0;
+    }
+    let mut ada = high;
+    let mut residual = total_with(high)? - target;
+    while residual.abs() > SOLVE_TOLERANCE_USD && iterations < SOLVE_MAX_ITERATIONS {
+        iterations += 1;
+        ada = (low + high) / 2.0;
+        residual = total_with(ada)? - target;
+        if residual < 0.0 {
+            low = ada;
+        } else {
+            high = ada;
+        }
+    }
+    Ok(InitialAdaSolution { ada, iterations, residual })
+}
+
+/// Value at p percent of an ascending list interpolating between the two nearest runs
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64)
+}
+
+/// Percentiles reported and drawn for Monte Carlo the outer pair holds confidence percent of the runs between them
+/// and the quartiles are kept when they fall inside it
+pub fn monte_carlo_percentiles(confidence: f64) -> Vec<f64> {
+    let low = (100.0 - confidence) / 2.0;
+    let mut percentiles = vec![low, 25.0, 50.0, 75.0, 100.0 - low];
+    percentiles.retain(|p| *p >= low && *p <= 100.0 - low);
+    percentiles.dedup();
+    percentiles
+}
+
+/// What every Monte Carlo run ended with and the totals the percentile envelope is drawn from
+#[derive(Debug, Clone, Default)]
+pub struct MonteCarloRuns {
+    /// ADA, price and total of each run
+    pub finals: Vec<(f64, f64, f64)>,
+    /// Day of each sampled point
+    pub path_days: Vec<f64>,
+    /// The total of every run on each sampled day sorted ascending so a percentile can be read off directly
+    pub paths: Vec<Vec<f64>>,
+}
+
+/// Runs the pool runs times with random daily price shocks drawn from rng
+/// With graph_points the totals are sampled on up to that many days for the envelope a run that ran out of ADA is worth 0 after it stopped
+/// on_run gets the number of each finished run so the caller can show progress
+pub fn monte_carlo<R: Rng>(
+    pool: &StakedCardanoPool,
+    runs: u32,
+    graph_points: Option<usize>,
+    options: &SimulationOptions,
+    rng: &mut R,
+    mut on_run: impl FnMut(u32),
+) -> Result<MonteCarloRuns, String> {
+    let options = SimulationOptions {
+        record_history: graph_points.is_some(), // Only the envelope needs the daily path
+        record_payouts: false,
+        ..*options
+    };
+    let mut samples = MonteCarloRuns {
+        finals: Vec::with_capacity(runs as usize),
+        ..Default::default()
+    };
+    for run in 1..=runs {
+        let result = calculate_staked_pool_sampled(pool, &options, rng)?;
+        let has_history = !result.amount_historical.is_empty() && result.amount_historical.len() == result.price_historical.len();
+        if let (Some(max_points), true) = (graph_points, has_history) {
+            let len = result.amount_historical.len();
+            let full_len = len + result.depleted_day.map(|day| (pool.simulated_days() - day) as usize).unwrap_or(0);
+            if samples.paths.is_empty() {
+                let points = full_len.clamp(2, max_points);
+                let x_scale = result.days_as_float / full_len as f64;
+                samples.path_days = (0..points).map(|i| (i * (full_len - 1) / (points - 1)) as f64 * x_scale).collect();
+                samples.paths = vec![Vec::with_capacity(runs as usize); points];
+            }
+            let last_point = samples.path_days.len() - 1;
+            for (i, column) in samples.paths.iter_mut().enumerate() {
+                let day = i * (full_len - 1) / last_point;
+                column.push(if day < len { result.amount_historical[day] * result.price_historical[day] } else { 0.0 });
+            }
+        }
+        samples.finals.push((result.final_ada_amount, result.final_ada_price, result.total()));
+        on_run(run);
+    }
+    for column in samples.paths.iter_mut() {
+        column.sort_by(|a, b| a.total_cmp(b));
+    }
+    Ok(samples)
+}
+
+/// Total with one input moved down and up by the sensitivity percent and the change from the run each way
+#[derive(Debug, Serialize)]
+pub struct SensitivityRow {
+    pub parameter: &'static str,
+    pub low_total: f64,
+    pub low_change_pct: f64,
+    pub high_total: f64,
+    pub high_change_pct: f64,
+}
+
+/// Multiplies one input of the pool by the factor
+type ScaleInput = fn(&mut StakedCardanoPool, f64);
+
+/// Reruns the pool with ada, initial_price, annual_yield and the price growth each scaled by 1 -/+ pct percent
+/// The price growth is whichever of annual_price_growth_pct, daily_price_growth_pct or price_yield the pool uses
+/// Inputs that would make the pool invalid (like a yield above 100%) are left out the rows are sorted biggest move first
+pub fn sensitivity_rows(pool: &StakedCardanoPool, result: &StakedCardanoPoolResult, pct: f64, options: &SimulationOptions) -> Vec<SensitivityRow> {
+    let options = SimulationOptions {
+        record_history: false,
+        record_payouts: false,
+        ..*options
+    };
+    let price_growth: (&'static str, ScaleInput) = match (pool.daily_price_growth_pct, pool.annual_price_growth_pct) {
+        (Some(_), _) => ("daily_price_growth_pct", |pool, scale| pool.daily_price_growth_pct = pool.daily_price_growth_pct.map(|v| v * scale)),
+        (None, Some(_)) => ("annual_price_growth_pct", |pool, scale| pool.annual_price_growth_pct = pool.annual_price_growth_pct.map(|v| v * scale)),
+        // The daily growth is moved not the multiplier itself 10% of 1.01 would be +11% a day
+        (None, None) => ("price_yield", |pool, scale| pool.price_yield = 1.0 + (pool.price_yield - 1.0) * scale),
+    };
+    let inputs: [(&'static str, ScaleInput); 4] = [
+        ("ada", |pool, scale| pool.ada *= scale),
+        ("initial_price", |pool, scale| pool.initial_price *= scale),
+        ("annual_yield", |pool, scale| pool.annual_yield *= scale),
+        price_growth,
+    ];
+    let base = result.total();
+    let change = |total: f64| if base == 0.0 { 0.0 } else { (total / base - 1.0) * 100.0 };
+    let mut rows: Vec<SensitivityRow> = inputs
+        .iter()
+        .filter_map(|(parameter, scale)| {
+            let mut totals = [1.0 - pct / 100.0, 1.0 + pct / 100.0].map(|factor| {
+                let mut moved = pool.clone();
+                scale(&mut moved, factor);
+                moved.validate().ok().map(|_| calculate_staked_pool(&moved, &options).total())
+            });
+            let (low, high) = (totals[0].take()?, totals[1].take()?);
+            Some(SensitivityRow {
+                parameter,
+                low_total: low,
+                low_change_pct: change(low),
+                high_total: high,
+                high_change_pct: change(high),
+            })
+        })
+        .collect();
+    let impact = |row: &SensitivityRow| row.low_change_pct.abs().max(row.high_change_pct.abs());
+    rows.sort_by(|a, b| impact(b).total_cmp(&impact(a)));
+    rows
+}
+
+/// Answer of break_even_analysis the day is None when the money put in is not won back within years_holding
+#[derive(Debug, Serialize)]
+pub struct BreakEven {
+    pub drop_pct: f64,
+    pub day: Option<u64>,
+    pub date: Option<NaiveDate>,
+    /// Years of staking a flat price after the drop needs None when the ADA does not grow
+    pub years_needed_flat: Option<f64>,
+    /// Yearly price fall the ADA growth exactly makes up for
+    pub max_annual_decline_pct: f64,
+}
+
+/// Reruns the pool with the drop as a day 1 price shock the same as a "price_events" entry so it matches an ordinary run with one
+pub fn break_even_analysis(pool: &StakedCardanoPool, drop_pct: f64, options: &SimulationOptions) -> Result<BreakEven, String> {
+    let options = SimulationOptions {
+        record_history: false,
+        record_payouts: false,
+        ..*options
+    };
+    if drop_pct >= 100.0 {
+        return Err(format!("--assume-drop must be below 100 a 100% drop leaves nothing to win back (found {})", drop_pct));
+    }
+    let mut dropped = pool.clone();
+    if drop_pct != 0.0 {
+        dropped.price_events.push(PriceEvent {
+            day: Some(1),
+            date: None,
+            kind: PriceEventKind::Shock,
+            factor: 1.0 - drop_pct / 100.0,
+            value: 0.0,
+        });
+    }
+    dropped.validate()?;
+    let result = calculate_staked_pool(&dropped, &options);
+    // The price barely moves the ADA so its growth over the run is what a falling price has to outpace
+    let ada_growth = result.ada_cagr(&dropped);
+    let years_needed_flat = Some(ada_growth)
+        .filter(|growth| *growth > 0.0)
+        .map(|growth| (1.0 / (1.0 - drop_pct / 100.0)).ln().max(0.0) / (1.0 + growth).ln());
+    Ok(BreakEven {
+        drop_pct,
+        day: result.break_even_day,
+        date: result.break_even_day.map(|day| pool.date_on(day)),
+        years_needed_flat,
+        max_annual_decline_pct: (1.0 - 1.0 / (1.0 + ada_growth)) * 100.0,
+    })
+}
+
+/// Tax in USD under no tax, capital gains only and income tax on the rewards with capital gains on the rest
+/// Taxes are settled once at the end nothing is sold during the run to pay them
+/// Rewards taxed as income are valued at the price on the pay day and that value is added to the cost basis
+pub fn tax_scenarios(pool: &StakedCardanoPool, result: &StakedCardanoPoolResult, income_rate: f64, gains_rate: f64) -> [(&'static str, f64); 3] {
+    let total = result.total();
+    let cost_basis = pool.ada * pool.initial_price + result.contributed_usd;
+    let reward_income = result.gross_rewards_usd - result.fees_paid_usd;
+    let gains_tax = |basis: f64| (total - basis).max(0.0) * gains_rate;
+    [
+        ("No Tax", 0.0),
+        ("Capital Gains Only", gains_tax(cost_basis)),
+        (
+            "Income Tax On Rewards",
+            reward_income * income_rate + gains_tax(cost_basis + reward_income),
+        ),
+    ]
+}
+
+/// What scenarios and wallets are ranked by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareMetric {
+    /// Final total in USD
+    Total,
+    /// Gain against the money put in so pools of different sizes compare fairly
+    Yield,
+    /// Yearly compounded growth of the total so different horizons compare fairly too
+    Cagr,
+}
+
+impl CompareMetric {
+    pub fn value(&self, pool: &StakedCardanoPool, result: &StakedCardanoPoolResult) -> f64 {
+        match self {
+            CompareMetric::Total => result.total(),
+            CompareMetric::Yield => result.net_return_as_percentage(pool),
+            CompareMetric::Cagr => result.cagr(pool) * 100.0,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompareMetric::Total => "Total",
+            CompareMetric::Yield => "Yield",
+            CompareMetric::Cagr => "CAGR",
+        }
+    }
+}
+
+/// Every wallet of a portfolio added up the wallets share the price path and horizon
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Portfolio {
+    /// Money put in the starting ADA at the initial price and every contribution
+    pub put_in: f64,
+    pub total: f64,
+    pub final_ada: f64,
+    /// Total over the money put in 0 when nothing was put in
+    pub multiple: f64,
+    pub cagr: f64,
+}
+
+impl Portfolio {
+    /// wallets and results are in the same order with at least one wallet
+    pub fn new(wallets: &[(String, StakedCardanoPool)], results: &[(String, StakedCardanoPoolResult)]) -> Self {
+        let put_in: f64 = wallets
+            .iter()
+            .zip(results.iter())
+            .map(|((_, pool), (_, result))| pool.ada * pool.initial_price + result.contributed_usd)
+            .sum();
+        let total: f64 = results.iter().map(|(_, result)| result.total()).sum();
+        let final_ada: f64 = results.iter().map(|(_, result)| result.final_ada_amount).sum();
+        let multiple = if put_in > 0.0 { total / put_in } else { 0.0 };
+        let years = wallets[0].1.years_holding;
+        let cagr = if years > 0.0 && multiple > 0.0 { multiple.powf(1.0 / years) - 1.0 } else { 0.0 };
+        Portfolio {
+            put_in,
+            total,
+            final_ada,
+            multiple,
+            cagr,
+        }
+    }
+}
+
+/// ADA and total of one wallet on a history index a wallet whose withdrawals emptied it early stays at zero
+pub fn wallet_on(result: &StakedCardanoPoolResult, i: usize) -> (f64, f64) {
+    match (result.amount_historical.get(i), result.price_historical.get(i)) {
+        (Some(ada), Some(price)) => (*ada, ada * price),
+        _ => (0.0, 0.0),
+    }
+}
+
+/// One --sweep range the values run from start to end (inclusive) in steps
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepRange {
+    pub field: String,
+    pub values: Vec<f64>,
+}
+
+/// FIELD=START:END:STEP as given to --sweep
+pub fn parse_sweep(spec: &str) -> Result<SweepRange, String> {
+    let usage = || format!("--sweep {} should look like annual_yield=0.03:0.06:0.005", spec);
+    let (field, range) = spec.split_once('=').ok_or_else(usage)?;
+    let bounds: Vec<f64> = range
+        .split(':')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| usage())?;
+    let (start, end, step) = match bounds[..] {
+        [start, end, step] => (start, end, step),
+        _ => return Err(usage()),
+    };
+    if step.is_nan() || end.is_nan() || step <= 0.0 || end < start {
+        return Err(format!("--sweep {} needs END at least START and a STEP above 0", spec));
+    }
+    // The small slack keeps END in the range when the steps do not add up exactly in floating point
+    let count = ((end - start) / step + 1e-9).floor() as usize + 1;
+    Ok(SweepRange {
+        field: String::from(field.trim()),
+        // Rounded so 0.03 + 6 x 0.005 reads 0.06 instead of 0.060000000000000005
+        values: (0..count).map(|i| ((start + step * i as f64) * 1e9).round() / 1e9).collect(),
+    })
+}
+
+/// The pool with field set to value through its JSON form so any numeric field can be swept
+/// The three price growth fields replace each other the same way --price-yield does
+pub fn pool_with_field(pool: &StakedCardanoPool, field: &str, value: f64) -> Result<StakedCardanoPool, String> {
+    let mut json = serde_json::to_value(pool).map_err(|e| e.to_string())?;
+    if json.get(field).is_some_and(|v| !v.is_number()) {
+        return Err(format!("--sweep {} is not a numeric pool field", field));
+    }
+    if ["price_yield", "daily_price_growth_pct", "annual_price_growth_pct"].contains(&field) {
+        json["price_yield"] = serde_json::json!(0.0);
+        json["daily_price_growth_pct"] = serde_json::Value::Null;
+        json["annual_price_growth_pct"] = serde_json::Value::Null;
+    }
+    // Whole values go in as JSON integers so the u64 fields like epoch_in_days accept them the f64 fields take either
+    json[field] = if value.fract() == 0.0 && (0.0..=MAX_SAFE_INTEGER).contains(&value) {
+        serde_json::json!(value as u64)
+    } else {
+        serde_json::json!(value)
+    };
+    let swept: StakedCardanoPool = serde_json::from_value(json).map_err(|e| format!("--sweep {}: {}", field, e))?;
+    // Unknown fields are dropped by serde so one that does not come back was never a pool field
+    if serde_json::to_value(&swept).ok().and_then(|v| v.get(field).cloned()).is_none() {
+        return Err(format!("--sweep {} is not a numeric pool field", field));
+    }
+    swept
+        .validate()
+        .map_err(|e| format!("{} = {} gives an invalid pool: {}", field, value, e.replace('\n', " ")))?;
+    Ok(swept)
+}
+
+// Largest whole number an f64 holds exactly
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+/// Fields that hold whole numbers such as epoch_in_days and reward_lag_epochs can only be swept in whole steps
+pub fn check_sweep_range(pool: &StakedCardanoPool, range: &SweepRange) -> Result<(), String> {
+    let json = serde_json::to_value(pool).map_err(|e| e.to_string())?;
+    let whole_field = json.get(&range.field).is_some_and(|v| v.is_u64());
+    match range.values.iter().find(|value| value.fract() != 0.0 || **value < 0.0) {
+        Some(value) if whole_field => Err(format!(
+            "--sweep {} is a whole number field so START, END and STEP have to be whole numbers (got {})",
+            range.field, value
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Every combination of the ranges with the values used the last range changes fastest
+pub fn sweep_grid(pool: &StakedCardanoPool, ranges: &[SweepRange]) -> Result<Vec<(Vec<f64>, StakedCardanoPool)>, String> {
+    let runs = ranges.iter().map(|range| range.values.len()).product::<usize>();
+    let mut grid: Vec<(Vec<f64>, StakedCardanoPool)> = Vec::with_capacity(runs);
+    for index in 0..runs {
+        let mut rest = index;
+        let mut values = Vec::with_capacity(ranges.len());
+        let mut swept = pool.clone();
+        for range in ranges.iter().rev() {
+            let value = range.values[rest % range.values.len()];
+            rest /= range.values.len();
+            swept = pool_with_field(&swept, &range.field, value)?;
+            values.insert(0, value);
+        }
+        grid.push((values, swept));
+    }
+    Ok(grid)
+}
+
+/// ADA, total and Gainz of every combination of the grid spread over all cores
+/// on_progress gets the runs done so far about every 50 milliseconds while they run
+pub fn run_sweep(grid: &[(Vec<f64>, StakedCardanoPool)], options: &SimulationOptions, mut on_progress: impl FnMut(u64)) -> Vec<(f64, f64, f64)> {
+    let runs = grid.len();
+    let workers = std::thread::available_parallelism().map_or(1, |n| n.get()).min(runs.max(1));
+    let done = std::sync::atomic::AtomicU64::new(0);
+    let mut finals: Vec<(f64, f64, f64)> = vec![(0.0, 0.0, 0.0); runs];
+    std::thread::scope(|scope| {
+        let chunk = runs.div_ceil(workers).max(1);
+        let handles: Vec<_> = grid
+            .chunks(chunk)
+            .zip(finals.chunks_mut(chunk))
+            .map(|(combinations, out)| {
+                let done = &done;
+                scope.spawn(move || {
+                    for ((_, swept), slot) in combinations.iter().zip(out.iter_mut()) {
+                        let result = calculate_staked_pool(swept, options);
+                        *slot = (result.final_ada_amount, result.total(), result.net_return_as_percentage(swept));
+                        done.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    }
+                })
+            })
+            .collect();
+        while !handles.iter().all(|handle| handle.is_finished()) {
+            on_progress(done.load(std::sync::atomic::Ordering::Relaxed));
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+    });
+    finals
+}
+
+/// Average milliseconds of the daily loop and of the closed form over one horizon
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchRow {
+    pub years: f64,
+    pub loop_ms: f64,
+    pub closed_form_ms: f64,
+}
+
+// Average milliseconds per call over BENCH_RUNS runs along with the last result
+fn time_runs<F: Fn() -> StakedCardanoPoolResult>(run: F) -> (f64, StakedCardanoPoolResult) {
+    let start = Instant::now();
+    let mut result = run();
+    for _ in 1..BENCH_RUNS {
+        result = run();
+    }
+    (start.elapsed().as_secs_f64() * 1000.0 / BENCH_RUNS as f64, result)
+}
+
+fn relative_difference(a: f64, b: f64) -> f64 {
+    if a == b {
+        0.0
+    } else {
+        (a - b).abs() / a.abs().max(b.abs())
+    }
+}
+
+/// Times the daily loop against the closed form over every BENCH_YEARS horizon
+/// Both paths must agree within BENCH_EPSILON before anything is timed
+pub fn bench(pool: &StakedCardanoPool, options: &SimulationOptions) -> Result<Vec<BenchRow>, String> {
+    let mut rows = Vec::new();
+    for years in BENCH_YEARS.iter() {
+        let horizon = StakedCardanoPool {
+            years_holding: *years,
+            ..pool.clone()
+        };
+        let expected = calculate_closed_form(&horizon, options)?;
+        let actual = calculate_staked_pool(&horizon, options);
+        let difference = relative_difference(actual.final_ada_amount, expected.final_ada_amount)
+            .max(relative_difference(actual.final_ada_price, expected.final_ada_price));
+        if difference > BENCH_EPSILON {
+            return Err(format!(
+                "Loop and closed form disagree over {} years ({} ADA @ ${} vs {} ADA @ ${})",
+                years, actual.final_ada_amount, actual.final_ada_price, expected.final_ada_amount, expected.final_ada_price
+            ));
+        }
+        let (loop_ms, _) = time_runs(|| calculate_staked_pool(&horizon, options));
+        let (closed_form_ms, _) = time_runs(|| calculate_closed_form(&horizon, options).unwrap_or_default());
+        rows.push(BenchRow {
+            years: *years,
+            loop_ms,
+            closed_form_ms,
+        });
+    }
+    Ok(rows)
+}
@@ -0,0 +1,555 @@
+/*
+
+ADA Staking CLI Tool (⚠️ Not Offical or Investment Advice ⚠️)
+Copyright (C) 2022  zulrah93 <1s16slrse@mozmail.com>
+
+This program is free software; you can redistribute it and/or
+modify it under the terms of the GNU General Public License
+as published by the Free Software Foundation; either version 2
+of the License, or (at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program; if not, write to the Free Software
+Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301, USA.
+
+
+*/
+
+use crate::{describe_write_error, open_output_file, CliError};
+use ada_calc::{parse_date, StakedCardanoPool};
+use chrono::NaiveDate;
+use std::borrow::Cow;
+use std::fs::read_to_string;
+use std::io::{BufRead, Read, Write};
+
+// Pool files in JSON, TOML or YAML read, checked for unknown keys, converted and written by init plus the price and reward history inputs
+
+// Daily prices for a backtest day 0 is the first date in the file
+pub struct PriceHistory {
+    pub start: NaiveDate,
+    pub prices: Vec<f64>,
+    pub filled: usize, // Days missing from the file that got the price of the day before
+}
+
+// Rows are date,price with an optional header like a CoinGecko export (snapped_at,price,market_cap,total_volume)
+// Only the first 10 characters of the date are read so 2021-01-01 00:00:00 UTC works, extra columns are ignored
+// With a ; or tab delimiter a decimal comma is read as a point
+pub fn read_price_history(path: &str) -> Result<PriceHistory, String> {
+    let buffer = read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let header = buffer.lines().next().unwrap_or_default();
+    let delimiter = if header.contains('\t') {
+        '\t'
+    } else if header.contains(';') {
+        ';'
+    } else {
+        ','
+    };
+    let mut rows: Vec<(NaiveDate, f64)> = Vec::new();
+    for (index, line) in buffer.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(delimiter).map(|f| f.trim().trim_matches('"')).collect();
+        let parsed = match fields.as_slice() {
+            [date, price, ..] => NaiveDate::parse_from_str(date.get(..10).unwrap_or(date), "%Y-%m-%d").ok().zip(
+                if delimiter == ',' { price.parse::<f64>() } else { price.replace(',', ".").parse::<f64>() }.ok(),
+            ),
+            _ => None,
+        };
+        match parsed {
+            Some((_, price)) if price <= 0.0 => {
+                return Err(format!("{}: line {} has a price that is not above 0", path, index + 1));
+            }
+            Some((date, _)) if rows.last().is_some_and(|(last, _)| date < *last) => {
+                return Err(format!("{}: line {} is dated before the line above it the rows must be oldest first", path, index + 1));
+            }
+            Some((date, price)) => match rows.last_mut() {
+                Some(last) if last.0 == date => last.1 = price, // The later row of a repeated date wins
+                _ => rows.push((date, price)),
+            },
+            None if index == 0 => continue, // Header
+            None => {
+                return Err(format!("{}: line {} is not a date,price row: {}", path, index + 1, line));
+            }
+        }
+    }
+    let start = match rows.first() {
+        Some((start, _)) => *start,
+        None => return Err(format!("{}: no date,price rows found", path)),
+    };
+    let mut prices: Vec<f64> = Vec::new();
+    let mut filled = 0;
+    for (date, price) in rows.iter() {
+        let day = (*date - start).num_days() as usize;
+        while prices.len() < day {
+            prices.push(prices.last().copied().unwrap_or(*price));
+            filled += 1;
+        }
+        prices.push(*price);
+    }
+    Ok(PriceHistory { start, prices, filled })
+}
+
+// The pool starts on the first date at the first price and runs to the last date with the price growth replaced by the file
+pub fn apply_price_history(pool: &mut StakedCardanoPool, history: &PriceHistory) {
+    pool.start_date = Some(history.start);
+    pool.initial_price = history.prices[0];
+    pool.fetch_price_via_api = false;
+    pool.price_yield = 0.0;
+    pool.daily_price_growth_pct = None;
+    pool.annual_price_growth_pct = None;
+    pool.price_yields = Vec::new();
+    pool.daily_prices = history.prices.clone();
+    pool.years_holding = pool.years_for_days(history.prices.len() as u64 - 1);
+}
+
+// Past rewards of an existing stake summed into the starting balance
+pub struct RewardHistory {
+    pub epochs: usize,
+    pub total_reward: f64,
+    pub realized_yield: f64, // Average reward per epoch relative to the balance at the time annualized
+}
+
+// Rows are epoch,reward_ada a header line is allowed and rows may be in any order
+pub fn read_reward_history(path: &str, pool: &StakedCardanoPool) -> Result<RewardHistory, String> {
+    let buffer = read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut rows: Vec<(u64, f64)> = Vec::new();
+    for (index, line) in buffer.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        let parsed = match fields.as_slice() {
+            [epoch, reward] => epoch.parse::<u64>().ok().zip(reward.parse::<f64>().ok()),
+            _ => None,
+        };
+        match parsed {
+            Some((_, reward)) if reward < 0.0 => {
+                return Err(format!("{}: line {} has a negative reward", path, index + 1));
+            }
+            Some(row) => rows.push(row),
+            None if index == 0 => continue, // Header
+            None => {
+                return Err(format!(
+                    "{}: line {} is not an epoch,reward_ada row: {}",
+                    path,
+                    index + 1,
+                    line
+                ));
+            }
+        }
+    }
+    if rows.is_empty() {
+        return Err(format!("{}: no epoch,reward_ada rows found", path));
+    }
+    rows.sort_by_key(|(epoch, _)| *epoch);
+    let mut balance = pool.ada;
+    let mut rate_sum = 0.0;
+    for (_, reward) in rows.iter() {
+        rate_sum += reward / balance;
+        balance += reward;
+    }
+    let epochs_per_year = pool.epochs_per_year();
+    Ok(RewardHistory {
+        epochs: rows.len(),
+        total_reward: rows.iter().map(|(_, reward)| reward).sum(),
+        realized_yield: rate_sum / rows.len() as f64 * epochs_per_year,
+    })
+}
+
+pub fn parse_pool(buffer: &str) -> Result<StakedCardanoPool, String> {
+    serde_json::from_str(buffer).map_err(|e| explain_missing_field(format!("Invalid pool JSON: {}", e)))
+}
+
+// Serde only says missing field `ada` so the line for it from the example pool is shown too
+pub fn explain_missing_field(error: String) -> String {
+    let field = match error.split("missing field `").nth(1).and_then(|rest| rest.split('`').next()) {
+        Some(field) => field,
+        None => return error,
+    };
+    match EXAMPLE_POOL_JSON.lines().find(|line| line.trim_start().starts_with(&format!("\"{}\":", field))) {
+        Some(line) => format!("{}\nAdd it to the pool for example: {}", error, line.trim().trim_end_matches(',')),
+        None => format!("{}\nAdd {} to the pool", error, field),
+    }
+}
+
+// Levenshtein distance between two keys used to suggest the field a typo meant
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Warning for a key serde would silently ignore with the closest known key when it is only a typo away
+pub fn unknown_key_warning(key: &str, location: &str, extra_keys: &[&str]) -> String {
+    let suggestion = StakedCardanoPool::field_names()
+        .iter()
+        .chain(extra_keys)
+        .map(|known| (edit_distance(key, known), *known))
+        .min()
+        .filter(|(distance, _)| *distance <= (key.len() / 3).max(2));
+    match suggestion {
+        Some((_, known)) => format!("Warning: {}unknown field {} is ignored (did you mean {}?)", location, key, known),
+        None => format!("Warning: {}unknown field {} is ignored", location, key),
+    }
+}
+
+pub fn unknown_keys_in(
+    fields: &serde_json::Map<String, serde_json::Value>,
+    location: &str,
+    extra_keys: &[&str],
+    warnings: &mut Vec<String>,
+) {
+    let known = StakedCardanoPool::field_names();
+    for key in fields.keys() {
+        if !known.contains(&key.as_str()) && !extra_keys.contains(&key.as_str()) {
+            warnings.push(unknown_key_warning(key, location, extra_keys));
+        }
+    }
+}
+
+// Keys of a pool holding a date --date-format applies to
+pub const DATE_KEYS: [&str; 3] = ["start_date", "epoch_anchor_date", "current_epoch_start"];
+
+// With --date-format the pool's dates (scenario and wallet entries included) are rewritten as YYYY-MM-DD before parsing
+// Text that is not valid JSON is left as is the parse error is reported on its own
+pub fn apply_date_format<'a>(buffer: &'a str, format: Option<&str>) -> Result<Cow<'a, str>, String> {
+    let format = match format {
+        Some(format) => format,
+        None => return Ok(Cow::Borrowed(buffer)),
+    };
+    let mut json = match serde_json::from_str::<serde_json::Value>(buffer) {
+        Ok(json) => json,
+        Err(_) => return Ok(Cow::Borrowed(buffer)),
+    };
+    match &mut json {
+        serde_json::Value::Array(entries) => {
+            for entry in entries.iter_mut() {
+                if let serde_json::Value::Object(fields) = entry {
+                    rewrite_dates(fields, format)?;
+                }
+            }
+        }
+        serde_json::Value::Object(fields) => rewrite_dates(fields, format)?,
+        _ => {}
+    }
+    serde_json::to_string(&json).map(Cow::Owned).map_err(|e| e.to_string())
+}
+
+pub fn rewrite_dates(fields: &mut serde_json::Map<String, serde_json::Value>, format: &str) -> Result<(), String> {
+    for (key, value) in fields.iter_mut() {
+        match value {
+            serde_json::Value::String(date) if DATE_KEYS.contains(&key.as_str()) => {
+                *date = parse_date(date, Some(format))
+                    .map_err(|e| format!("Invalid pool JSON: {} {}", key, e))?
+                    .to_string();
+            }
+            serde_json::Value::Array(entries) if key == "scenarios" || key == "wallets" => {
+                for entry in entries.iter_mut() {
+                    if let serde_json::Value::Object(fields) = entry {
+                        rewrite_dates(fields, format)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// Warnings for every key of the pool JSON that is not a pool field (scenario and wallet entries included)
+// Text that is not valid JSON gives none the parse error is reported on its own
+pub fn unknown_pool_keys(buffer: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+    match serde_json::from_str::<serde_json::Value>(buffer) {
+        Ok(serde_json::Value::Array(entries)) => {
+            for (index, entry) in entries.iter().enumerate() {
+                if let serde_json::Value::Object(fields) = entry {
+                    unknown_keys_in(fields, &format!("scenario {} ", index + 1), &["name"], &mut warnings);
+                }
+            }
+        }
+        Ok(serde_json::Value::Object(base)) => {
+            unknown_keys_in(&base, "", &["name", "scenarios", "wallets"], &mut warnings);
+            for (list, extra_keys) in [("scenarios", &["name"][..]), ("wallets", &["name", "epoch_offset_days"][..])] {
+                if let Some(serde_json::Value::Array(entries)) = base.get(list) {
+                    for (index, entry) in entries.iter().enumerate() {
+                        if let serde_json::Value::Object(fields) = entry {
+                            let location = format!("{} {} ", list.trim_end_matches('s'), index + 1);
+                            unknown_keys_in(fields, &location, extra_keys, &mut warnings);
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    warnings
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+// Config format picked from the file extension
+pub fn config_format(path: &str) -> Option<ConfigFormat> {
+    match std::path::Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("json") => Some(ConfigFormat::Json),
+        Some("toml") => Some(ConfigFormat::Toml),
+        Some("yaml") | Some("yml") => Some(ConfigFormat::Yaml),
+        _ => None,
+    }
+}
+
+pub fn parse_pool_as(format: ConfigFormat, buffer: &str) -> Result<StakedCardanoPool, String> {
+    match format {
+        ConfigFormat::Json => parse_pool(buffer),
+        ConfigFormat::Toml => toml::from_str(buffer).map_err(|e| explain_missing_field(format!("Invalid pool TOML: {}", e))),
+        ConfigFormat::Yaml => serde_yaml::from_str(buffer).map_err(|e| explain_missing_field(format!("Invalid pool YAML: {}", e))),
+    }
+}
+
+// Pool files may be written in any config format the rest of the program works on the JSON text
+// A TOML or YAML pool is parsed and written back out as JSON an unknown extension is read as JSON with a warning
+// --input - reads the pool JSON from stdin instead of a file
+pub const STDIN_PATH: &str = "-";
+
+// Pool file contents or stdin for STDIN_PATH a terminal on stdin is refused so the run never sits waiting for typing
+pub fn read_pool_source(path: &str) -> std::io::Result<String> {
+    if path != STDIN_PATH {
+        return read_to_string(path);
+    }
+    if atty::is(atty::Stream::Stdin) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "stdin is a terminal pipe the pool JSON into ada_calc when using --input -",
+        ));
+    }
+    let mut buffer = String::new();
+    std::io::stdin().read_to_string(&mut buffer)?;
+    Ok(buffer)
+}
+
+pub fn pool_file_to_json(path: &str, buffer: String) -> Result<String, String> {
+    if path == STDIN_PATH {
+        return Ok(buffer); // Only JSON is read from stdin there is no extension to go by
+    }
+    match config_format(path) {
+        Some(ConfigFormat::Json) => Ok(buffer),
+        Some(format) => {
+            // The typed parse below drops keys it does not know so they are looked for before it
+            if let Some(fields) = config_value_as_json(format, &buffer) {
+                for warning in unknown_pool_keys(&fields.to_string()) {
+                    eprintln!("{}", warning);
+                }
+            }
+            parse_pool_as(format, &buffer).and_then(|pool| serialize_pool_as(ConfigFormat::Json, &pool))
+        }
+        None => {
+            eprintln!(
+                "Warning: {} does not end in .json, .toml, .yaml or .yml reading it as JSON",
+                path
+            );
+            Ok(buffer)
+        }
+    }
+}
+
+// The TOML or YAML text as a plain JSON value only its keys are looked at
+pub fn config_value_as_json(format: ConfigFormat, buffer: &str) -> Option<serde_json::Value> {
+    match format {
+        ConfigFormat::Json => serde_json::from_str(buffer).ok(),
+        ConfigFormat::Toml => toml::from_str(buffer).ok(),
+        ConfigFormat::Yaml => serde_yaml::from_str(buffer).ok(),
+    }
+}
+
+pub fn serialize_pool_as(format: ConfigFormat, pool: &StakedCardanoPool) -> Result<String, String> {
+    match format {
+        ConfigFormat::Json => serde_json::to_string_pretty(pool).map_err(|e| e.to_string()),
+        // Through a Value so the contribution and annual_yield_schedule tables are written after the plain fields TOML needs that order
+        ConfigFormat::Toml => toml::Value::try_from(pool)
+            .and_then(|value| toml::to_string_pretty(&value))
+            .map_err(|e| e.to_string()),
+        ConfigFormat::Yaml => serde_yaml::to_string(pool).map_err(|e| e.to_string()),
+    }
+}
+
+// The convert subcommand the config is validated and defaults are written out explicitly
+pub fn convert_pool_file(input: &str, output: &str) -> Result<(), CliError> {
+    let (input_format, output_format) = match (config_format(input), config_format(output)) {
+        (Some(i), Some(o)) => (i, o),
+        _ => return Err(CliError::Parse(String::from("Both files need a .json, .toml, .yaml or .yml extension"))),
+    };
+    let text = read_to_string(input)
+        .map_err(|e| CliError::MissingConfig(format!("{}: {}", input, e)))
+        .and_then(|buffer| parse_pool_as(input_format, &buffer).map_err(CliError::Parse))
+        .and_then(|pool| pool.validate().map(|_| pool).map_err(|e| CliError::invalid_pool("", &e)))
+        .and_then(|pool| serialize_pool_as(output_format, &pool).map_err(CliError::Parse))?;
+    std::fs::write(output, text).map_err(|e| CliError::Write(format!("Failed to Write [{}] to Disk. Reason: {}", output, e)))?;
+    println!("Converted {} to {}", input, output);
+    Ok(())
+}
+
+// Reads, parses and validates a pool file each step failing with its own kind of error
+pub fn read_pool_file(path: &str, date_format: Option<&str>) -> Result<StakedCardanoPool, CliError> {
+    let buffer = read_pool_source(path).map_err(|e| CliError::MissingConfig(e.to_string()))?;
+    let buffer = pool_file_to_json(path, buffer).map_err(CliError::Parse)?;
+    let buffer = apply_date_format(&buffer, date_format).map_err(CliError::Parse)?;
+    for warning in unknown_pool_keys(&buffer) {
+        eprintln!("{}", warning);
+    }
+    let pool = parse_pool(&buffer).map_err(CliError::Parse)?;
+    pool.validate().map_err(|e| CliError::invalid_pool("", &e))?;
+    Ok(pool)
+}
+
+// The validate subcommand nothing is printed when the pool is valid the reason is named with the path otherwise
+pub fn validate_pool_file(path: &str, date_format: Option<&str>) -> Result<(), CliError> {
+    read_pool_file(path, date_format).map(|_| ()).map_err(|e| e.map_message(|message| format!("{}: {}", path, message)))
+}
+
+// Starting point for init the README example every optional field takes its serde default
+pub const EXAMPLE_POOL_JSON: &str = r#"{
+    "ada": 10000,
+    "fetch_price_via_api": false,
+    "initial_price": 0.94,
+    "annual_price_growth_pct": 20,
+    "annual_yield": 0.05,
+    "epoch_in_days": 5,
+    "years_holding": 5
+}"#;
+
+// Fields asked for by init --interactive the optional ones are left for editing the file
+pub const INIT_FIELDS: [(&str, &str); 6] = [
+    ("ada", "How much ADA you are staking"),
+    ("initial_price", "Starting price of one ADA in USD"),
+    ("annual_price_growth_pct", "Yearly price change in percent 20 is +20% a year and 0 keeps the price flat"),
+    ("annual_yield", "Staking rewards per year in percent so 5 is 5% (the file keeps it as the fraction 0.05)"),
+    ("epoch_in_days", "Days between payouts Cardano pays every 5 days"),
+    ("years_holding", "How many years to simulate 0.5 is half a year"),
+];
+
+// Next trimmed answer or the default when left empty None once stdin is closed
+pub fn prompt(input: &mut impl BufRead, question: &str, default: &str) -> Option<String> {
+    print!("{} [{}]: ", question, default);
+    std::io::stdout().flush().ok()?;
+    let mut answer = String::new();
+    if input.read_line(&mut answer).ok()? == 0 {
+        return None;
+    }
+    let answer = answer.trim();
+    Some(String::from(if answer.is_empty() { default } else { answer }))
+}
+
+// Goes through the pool as JSON so each answer is checked by serde and validate() the same way a pool.json would be
+pub fn ask_pool_field(input: &mut impl BufRead, pool: &StakedCardanoPool, name: &str, help: &str) -> Option<StakedCardanoPool> {
+    let value = serde_json::to_value(pool).ok()?;
+    println!("{}", help);
+    loop {
+        // annual_yield is asked for in percent and written back as the fraction the pool uses
+        let percent = name == "annual_yield";
+        let default = match value[name].as_f64() {
+            Some(fraction) if percent => (fraction * 100.0).to_string(),
+            _ => value[name].to_string(),
+        };
+        let answer = prompt(input, if percent { "annual_yield (%)" } else { name }, &default)?;
+        let number: serde_json::Value = match serde_json::from_str(&answer) {
+            Ok(number @ serde_json::Value::Number(_)) => number,
+            _ => {
+                println!("  {} is not a number try again", answer);
+                continue;
+            }
+        };
+        if number.as_f64().unwrap_or_default() < 0.0 {
+            println!("  {} is negative try again", answer);
+            continue;
+        }
+        let number = match number.as_f64() {
+            Some(pct) if percent && pct > 0.0 && pct < 0.5 => {
+                println!("  {} would be {}% a year type 5 for 5% try again", answer, pct);
+                continue;
+            }
+            Some(pct) if percent && pct > 100.0 => {
+                println!("  {}% a year is more than the whole stake try again", answer);
+                continue;
+            }
+            Some(pct) if percent => serde_json::json!(pct / 100.0),
+            _ => number,
+        };
+        let mut answered = value.clone();
+        answered[name] = number;
+        let candidate: StakedCardanoPool = match serde_json::from_value(answered) {
+            Ok(candidate) => candidate,
+            Err(e) => {
+                println!("  {}", e);
+                continue;
+            }
+        };
+        match candidate.validate() {
+            Err(errors) if errors.lines().any(|e| e.starts_with(name)) => {
+                for error in errors.lines().filter(|e| e.starts_with(name)) {
+                    println!("  {}", error);
+                }
+            }
+            _ => return Some(candidate),
+        }
+    }
+}
+
+// The init subcommand
+pub fn init_pool_file(path: &str, interactive: bool, force: bool) -> Result<(), CliError> {
+    if std::path::Path::new(path).exists() && !force {
+        return Err(CliError::Write(format!("{} already exists pass --force to replace it", path)));
+    }
+    let mut pool: StakedCardanoPool = serde_json::from_str(EXAMPLE_POOL_JSON).map_err(|e| CliError::Parse(e.to_string()))?;
+    if interactive {
+        println!("Press enter to keep the value in brackets.");
+        let stdin = std::io::stdin();
+        let mut input = stdin.lock();
+        for (name, help) in INIT_FIELDS.iter() {
+            match ask_pool_field(&mut input, &pool, name, help) {
+                Some(answered) => pool = answered,
+                None => {
+                    eprintln!(); // Ends the prompt line the error goes under it
+                    return Err(CliError::MissingConfig(String::from(
+                        "Input closed before every value was answered nothing was written",
+                    )));
+                }
+            }
+        }
+    }
+    let format = config_format(path).unwrap_or(ConfigFormat::Json);
+    let written = serialize_pool_as(format, &pool)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        .and_then(|text| open_output_file(path, force).and_then(|mut file| file.write_all(text.as_bytes())));
+    written.map_err(|e| CliError::Write(format!("Failed to Write [{}] to Disk.\nReason: {}", path, describe_write_error(path, &e))))?;
+    println!(
+        "Saved {} to Disk. Edit it for pool fees, lump sums and the other optional fields.",
+        path
+    );
+    Ok(())
+}
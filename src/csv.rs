@@ -0,0 +1,566 @@
+/*
+
+ADA Staking CLI Tool (⚠️ Not Offical or Investment Advice ⚠️)
+Copyright (C) 2022  zulrah93 <1s16slrse@mozmail.com>
+
+This program is free software; you can redistribute it and/or
+modify it under the terms of the GNU General Public License
+as published by the Free Software Foundation; either version 2
+of the License, or (at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program; if not, write to the Free Software
+Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301, USA.
+
+
+*/
+
+// CSV files of a run the daily rows and the scenario, portfolio, sweep, Monte Carlo, tax and history ledger files
+use crate::{
+    absolute_path, describe_write_error, get_file_timestamp, in_output_dir, open_output_file, print_table, round_to, stem_prefix, CliError,
+    CommandOptions, ADA_DECIMALS,
+};
+use ada_calc::analysis::wallet_on;
+use ada_calc::{DayRecord, PayDay, RoundingMode, StakedCardanoPool, StakedCardanoPoolResult};
+use chrono::NaiveDate;
+use std::borrow::Cow;
+use std::fs::{read_to_string, File, OpenOptions};
+use std::io::{BufWriter, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsvGranularity {
+    Day,
+    Epoch, // One row per pay day
+    Week,
+    Month,
+    Year,
+}
+
+impl CsvGranularity {
+    fn period_in_days(&self, epoch_in_days: u64) -> f64 {
+        match self {
+            CsvGranularity::Day => 1.0,
+            CsvGranularity::Epoch => epoch_in_days as f64,
+            CsvGranularity::Week => 7.0,
+            CsvGranularity::Month => 365.25 / 12.0,
+            CsvGranularity::Year => 365.25,
+        }
+    }
+
+    // True on the first day of each new period the final simulated day is always written so the file ends on the final state
+    pub fn writes_row(&self, day: u64, last_day: u64, epoch_in_days: u64) -> bool {
+        let period = self.period_in_days(epoch_in_days);
+        day == last_day || (day as f64 / period).floor() > ((day - 1) as f64 / period).floor()
+    }
+}
+
+// Which CSV columns are written basic is the original Day,ADA,Price,Total for scripts that expect exactly those
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsvColumns {
+    Full,
+    Basic,
+}
+
+// Opens the CSV file and writes the header rows are streamed in as the simulation runs so memory stays bounded
+// Day, Date, Epoch, ADA, the totals and Reward Paid and Cumulative Rewards are always there the Contributed, Withdrawn and other columns
+// are only added for pools that use them --csv-columns basic writes only Day,ADA,Price,Total
+// Contributed is the USD put in Contributed ADA the ADA bought or added with it and Reward ADA the rest of the balance
+// Gross Reward and Net Reward are the ADA earned on a pay day before and after the pool fees
+pub fn open_csv_writer(csv_filename: &str, args: &CommandOptions, pool: &StakedCardanoPool) -> std::io::Result<BufWriter<File>> {
+    let file = open_output_file(csv_filename, args.force)?;
+    let mut writer = BufWriter::new(file);
+    writeln!(writer, "{}", args.csv_dialect.header(&data_header(args, pool)))?;
+    Ok(writer)
+}
+
+// Comma separated columns of the CSV and the workbook's Daily sheet before --csv-delimiter is applied
+pub fn data_header(args: &CommandOptions, pool: &StakedCardanoPool) -> String {
+    let currency = args.usd_format.currency;
+    if args.csv_columns == CsvColumns::Basic {
+        return format!("Day,ADA,{},{}", currency.column("Price"), currency.column("Total"));
+    }
+    let mut header = String::from("Day,Date,Epoch,ADA");
+    header.push_str(&format!(",{},{},{}", currency.column("Price"), currency.column("Total"), currency.column("Hold Total")));
+    header.push_str(",Reward Paid,Cumulative Rewards");
+    if pool.contribution.is_some() {
+        header.push_str(&format!(",{},Contributed ADA,Reward ADA", currency.column("Contributed")));
+    }
+    if pool.withdrawal_usd_per_interval > 0.0 {
+        header.push_str(",Withdrawn ADA");
+    }
+    if !pool.compound {
+        header.push_str(&format!(",Harvested ADA,{}", currency.column("Harvested Value")));
+    }
+    if has_pool_fees(pool) {
+        header.push_str(",Gross Reward,Net Reward");
+    }
+    if pool.reward_lag_epochs > 0 {
+        header.push_str(",Pending Rewards");
+    }
+    if pool.has_yield_schedule() {
+        header.push_str(",Effective Yield");
+    }
+    if pool.reserve_ada.is_some() {
+        header.push_str(",Reserve,Reserve Yield");
+    }
+    if args.csv_granularity != CsvGranularity::Day {
+        header.push_str(",Rewards Since Last Row"); // The rows in between are left out so their rewards are summed here
+    }
+    if pool.annual_inflation != 0.0 {
+        header.push_str(&format!(",{}", currency.column("Real Total")));
+    }
+    if !pool.price_events.is_empty() {
+        header.push_str(",Price Event");
+    }
+    header
+}
+
+pub fn has_pool_fees(pool: &StakedCardanoPool) -> bool {
+    pool.pool_margin > 0.0 || pool.pool_fixed_fee > 0.0
+}
+
+// How every CSV is written from --csv-delimiter, --csv-decimal-comma, --csv-precision and --csv-ada-precision
+// The defaults write the same rows as before comma separated with full precision prices
+#[derive(Debug, Clone, Copy)]
+pub struct CsvDialect {
+    pub delimiter: char,
+    pub decimal_comma: bool,
+    pub ada_precision: usize,           // Decimals of the ADA columns
+    pub value_precision: Option<usize>, // Decimals of the price and USD columns None writes every digit
+    pub rounding: RoundingMode,         // --rounding used for both
+}
+
+impl CsvDialect {
+    pub fn ada(&self, amount: f64) -> String {
+        self.decimal(round_to(amount, self.ada_precision, self.rounding))
+    }
+
+    pub fn value(&self, value: f64) -> String {
+        self.decimal(match self.value_precision {
+            Some(precision) => round_to(value, precision, self.rounding),
+            None => value.to_string(),
+        })
+    }
+
+    fn decimal(&self, number: String) -> String {
+        if self.decimal_comma {
+            number.replace('.', ",")
+        } else {
+            number
+        }
+    }
+
+    // Scenario names are free text so quote them when they would break the row
+    fn text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if text.contains([self.delimiter, '"', '\n']) {
+            Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")))
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    pub fn row(&self, fields: &[String]) -> String {
+        fields.join(self.delimiter.encode_utf8(&mut [0; 4]))
+    }
+
+    // Headers are written with commas in the source
+    pub fn header(&self, columns: &str) -> String {
+        columns.replace(',', self.delimiter.encode_utf8(&mut [0; 4]))
+    }
+
+    // A delimiter that is also the decimal separator or a quote would split or swallow fields
+    pub fn check(&self) -> Result<(), String> {
+        match (self.delimiter, self.decimal_comma) {
+            (',', true) => Err(String::from(
+                "--csv-decimal-comma needs another --csv-delimiter (for example ;) or every number would be split in two",
+            )),
+            ('.', false) => Err(String::from(
+                "--csv-delimiter . is the decimal point pick another or add --csv-decimal-comma",
+            )),
+            ('"' | '\n' | '\r', _) => Err(String::from("--csv-delimiter cannot be a quote or a newline")),
+            _ => Ok(()),
+        }
+    }
+}
+
+// How the workbook's Daily sheet rows are built before each cell is parsed back into a number
+pub const XLSX_DIALECT: CsvDialect = CsvDialect {
+    delimiter: ',',
+    decimal_comma: false,
+    ada_precision: ADA_DECIMALS,
+    value_precision: None,
+    rounding: RoundingMode::HalfEven, // Only the ADA is rounded and it is already whole lovelace
+};
+
+// One day row of the CSV and the workbook in the columns data_header lists
+pub fn data_row(
+    pool: &StakedCardanoPool,
+    args: &CommandOptions,
+    record: &DayRecord,
+    csv: CsvDialect,
+    cumulative_rewards: f64,
+    rewards_since_row: f64,
+) -> Vec<String> {
+    let day = record.day;
+    let (ada, price) = (record.start_ada, record.start_price);
+    let money = |usd: f64| csv.value(args.usd_format.currency.convert(usd));
+    let basic = args.csv_columns == CsvColumns::Basic;
+    let mut row = if basic {
+        vec![day.to_string(), csv.ada(ada), money(price), money(ada * price)]
+    } else {
+        // The absolute epoch with epoch_anchor_date otherwise epochs counted from 0 the pay day ending each one is in it
+        let epoch = pool.epoch_number(record.date).unwrap_or(((day - 1) / pool.epoch_in_days.max(1)) as i64);
+        vec![day.to_string(), record.date.to_string(), epoch.to_string()]
+    };
+    if !basic {
+        // Hold Total is the ADA put in valued at the same price what holding without staking would be worth
+        row.extend([csv.ada(ada), money(price), money(ada * price), money((pool.ada + record.start_contributed_ada) * price)]);
+        // Lands at the end of the day so it is in the next row's ADA
+        row.extend([csv.ada(record.credited), csv.ada(cumulative_rewards)]);
+        if pool.contribution.is_some() {
+            row.extend([
+                money(record.start_contributed_usd),
+                csv.ada(record.start_contributed_ada),
+                csv.ada(ada + record.start_withdrawn_ada - pool.ada - record.start_contributed_ada),
+            ]);
+        }
+        if pool.withdrawal_usd_per_interval > 0.0 {
+            row.push(csv.ada(record.start_withdrawn_ada));
+        }
+        if !pool.compound {
+            // Valued at the price on each payout day not today's price
+            row.extend([csv.ada(record.start_harvested_ada), money(record.start_harvested_usd)]);
+        }
+        if has_pool_fees(pool) {
+            let (gross, net) = match record.pay_day {
+                PayDay::Paid { gross, net, .. } => (gross, net),
+                _ => (0.0, 0.0),
+            };
+            row.extend([csv.ada(gross), csv.ada(net)]);
+        }
+        if pool.reward_lag_epochs > 0 {
+            row.push(csv.ada(record.start_in_flight)); // Earned but not yet in the ADA column
+        }
+        if pool.has_yield_schedule() {
+            row.push(csv.decimal(((record.annual_yield * 1e12).round() / 1e12).to_string())); // Without the float noise of the decay
+        }
+        if let Some(reserve) = record.start_reserve {
+            row.extend([csv.ada(reserve), csv.value(pool.reserve_annual_yield(reserve))]); // The yield falls with the reserve
+        }
+        if args.csv_granularity != CsvGranularity::Day {
+            row.push(csv.ada(rewards_since_row)); // Rows show the state at the start of the day so today's reward goes in the next row
+        }
+        if pool.annual_inflation != 0.0 {
+            // The row is the state at the start of the day so it is discounted over the days before it
+            row.push(money(ada * price / pool.inflation_factor(day.saturating_sub(1))));
+        }
+        if !pool.price_events.is_empty() {
+            // Fired at the end of the day so the moved price shows up in the next row
+            row.push(if record.price_event { pool.price_event_labels(day, record.price).join("; ") } else { String::new() });
+        }
+    }
+    row
+}
+
+pub const DEFAULT_LEDGER_PATH: &str = "ada_calc_history.csv";
+
+const LEDGER_HEADER: &str = "Timestamp,ADA,Initial Price,Annual Yield,Price Yield,Years,Final ADA,Final Price,Total,Gainz";
+
+// Adds one line for the run to the ledger the header only goes into a new (or empty) file
+// The line is written with a single append so runs at the same time never mix their lines
+pub fn append_ledger(path: &str, pool: &StakedCardanoPool, result: &StakedCardanoPoolResult) -> bool {
+    let written = OpenOptions::new().append(true).create(true).open(path).and_then(|mut file| {
+        let mut record = String::new();
+        if file.metadata()?.len() == 0 {
+            record.push_str(LEDGER_HEADER);
+            record.push('\n');
+        }
+        record.push_str(&format!(
+            "{},{:.6},{},{},{},{},{:.6},{:.6},{:.2},{:.2}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            pool.ada,
+            pool.initial_price,
+            pool.annual_yield,
+            pool.daily_price_yield(),
+            pool.years_holding,
+            result.final_ada_amount,
+            result.final_ada_price,
+            result.total(),
+            result.net_return_as_percentage(pool)
+        ));
+        file.write_all(record.as_bytes())
+    });
+    match written {
+        Ok(()) => {
+            println!("Recorded the run in {}.", absolute_path(path));
+            true
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to Append to the History Ledger [{}].", path);
+            eprintln!("Reason: {}", describe_write_error(path, &e));
+            false
+        }
+    }
+}
+
+// The history subcommand prints the ledger as an aligned table oldest run first
+pub fn print_ledger(path: &str) -> Result<(), CliError> {
+    let text = read_to_string(path)
+        .map_err(|e| CliError::MissingConfig(format!("Could not read {} ({}) run with --record first", path, e)))?;
+    let rows: Vec<Vec<String>> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.split(',').map(String::from).collect())
+        .collect();
+    if rows.len() < 2 {
+        println!("No runs recorded in {} yet.", path);
+        return Ok(());
+    }
+    print_table(&format!("Recorded Runs ({}):", rows.len() - 1), &rows, false);
+    Ok(())
+}
+
+pub fn default_csv_path(args: &CommandOptions) -> String {
+    in_output_dir(
+        args.output_dir.as_deref(),
+        &format!(
+            "raw_ada_calc_data_{}{}.csv",
+            stem_prefix(args),
+            get_file_timestamp(args.timestamp_format)
+        ),
+    )
+}
+
+// One row per combination the swept values then the results
+pub fn write_sweep_csv(
+    fields: &[&str],
+    grid: &[(Vec<f64>, StakedCardanoPool)],
+    finals: &[(f64, f64, f64)],
+    args: &CommandOptions,
+) -> bool {
+    let path = args.csv_out.clone().unwrap_or_else(|| {
+        in_output_dir(
+            args.output_dir.as_deref(),
+            &format!("sweep_results_{}{}.csv", stem_prefix(args), get_file_timestamp(args.timestamp_format)),
+        )
+    });
+    let written = open_output_file(&path, args.force).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        let (csv, currency) = (args.csv_dialect, args.usd_format.currency);
+        writeln!(writer, "{}", csv.header(&format!("{},Final ADA,{},Gainz", fields.join(","), currency.column("Total"))))?;
+        for ((values, _), (ada, total, gainz)) in grid.iter().zip(finals.iter()) {
+            let mut row: Vec<String> = values.iter().map(|v| csv.value(*v)).collect();
+            row.extend([csv.ada(*ada), csv.value(currency.convert(*total)), csv.value(*gainz)]);
+            writeln!(writer, "{}", csv.row(&row))?;
+        }
+        writer.flush()
+    });
+    match written {
+        Ok(()) => {
+            println!("Saved Sweep CSV as {} to Disk.", absolute_path(&path));
+            true
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to Write CSV [{}] to Disk.", path);
+            eprintln!("Reason: {}", describe_write_error(&path, &e));
+            false
+        }
+    }
+}
+
+pub fn percentile_name(p: f64) -> String {
+    if p == 50.0 {
+        String::from("Median")
+    } else {
+        format!("{}th Percentile", p)
+    }
+}
+
+// One row per run with its final values the daily rows of every run would be far too many
+pub fn write_monte_carlo_csv(finals: &[(f64, f64, f64)], args: &CommandOptions) -> bool {
+    let path = in_output_dir(
+        args.output_dir.as_deref(),
+        &format!(
+            "monte_carlo_runs_{}{}.csv",
+            stem_prefix(args),
+            get_file_timestamp(args.timestamp_format)
+        ),
+    );
+    let written = open_output_file(&path, args.force).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        let (csv, currency) = (args.csv_dialect, args.usd_format.currency);
+        let money = |usd: f64| csv.value(currency.convert(usd));
+        writeln!(writer, "{}", csv.header(&format!("Run,ADA,{},{}", currency.column("Price"), currency.column("Total"))))?;
+        for (run, (ada, price, total)) in finals.iter().enumerate() {
+            writeln!(writer, "{}", csv.row(&[(run + 1).to_string(), csv.ada(*ada), money(*price), money(*total)]))?;
+        }
+        writer.flush()
+    });
+    match written {
+        Ok(()) => {
+            println!("Saved Monte Carlo CSV as {} to Disk.", absolute_path(&path));
+            true
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to Write CSV [{}] to Disk.", path);
+            eprintln!("Reason: {}", describe_write_error(&path, &e));
+            false
+        }
+    }
+}
+
+pub fn tax_report_path(args: &CommandOptions) -> String {
+    in_output_dir(
+        args.output_dir.as_deref(),
+        &format!(
+            "tax_report_{}{}.csv",
+            stem_prefix(args),
+            get_file_timestamp(args.timestamp_format)
+        ),
+    )
+}
+
+// One row per payout the price on the day it landed is both what it is taxed at and its cost basis when sold
+pub fn write_tax_report_csv(pool: &StakedCardanoPool, result: &StakedCardanoPoolResult, args: &CommandOptions) -> bool {
+    let path = tax_report_path(args);
+    let taxed = pool.reward_tax_rate > 0.0;
+    let written = open_output_file(&path, args.force).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        let (csv, currency) = (args.csv_dialect, args.usd_format.currency);
+        let money = |usd: f64| csv.value(currency.convert(usd));
+        let mut header = format!("Day,Date,Year,ADA,{},{}", currency.column("Price"), currency.column("Income"));
+        if taxed {
+            header.push_str(&format!(",{}", currency.column("Tax Owed")));
+        }
+        writeln!(writer, "{}", csv.header(&header))?;
+        for (day, ada, price) in result.reward_payouts.iter() {
+            let income = ada * price;
+            let mut row = vec![
+                day.to_string(),
+                pool.date_on(*day).to_string(),
+                ((day.saturating_sub(1) as f64 / pool.days_per_year()) as u64 + 1).to_string(),
+                csv.ada(*ada),
+                money(*price),
+                money(income),
+            ];
+            if taxed {
+                row.push(money(income * pool.reward_tax_rate));
+            }
+            writeln!(writer, "{}", csv.row(&row))?;
+        }
+        writer.flush()
+    });
+    match written {
+        Ok(()) => {
+            println!("Saved Tax Report CSV as {} to Disk.", absolute_path(&path));
+            true
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to Write CSV [{}] to Disk.", path);
+            eprintln!("Reason: {}", describe_write_error(&path, &e));
+            false
+        }
+    }
+}
+
+// One CSV for every scenario with the scenario name as the first column rows follow --csv-granularity like the single pool CSV
+// calendars has the epoch_in_days and start date of each scenario
+pub fn write_scenarios_csv(
+    results: &[(String, StakedCardanoPoolResult)],
+    calendars: &[(u64, NaiveDate)],
+    args: &CommandOptions,
+) -> bool {
+    let path = args.csv_out.clone().unwrap_or_else(|| default_csv_path(args));
+    let written = open_output_file(&path, args.force).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        let (csv, currency) = (args.csv_dialect, args.usd_format.currency);
+        let money = |usd: f64| csv.value(currency.convert(usd));
+        writeln!(writer, "{}", csv.header(&format!("Scenario,Day,Date,ADA,{},{}", currency.column("Price"), currency.column("Total"))))?;
+        for ((name, result), (epoch_in_days, start)) in results.iter().zip(calendars.iter()) {
+            let first_day = result.baseline.map(|(day, ..)| day + 1).unwrap_or(1);
+            let last_day = first_day + result.amount_historical.len() as u64 - 1;
+            let history = result.amount_historical.iter().zip(result.price_historical.iter());
+            for (day, (ada, price)) in (first_day..).zip(history) {
+                if args.csv_granularity.writes_row(day, last_day, *epoch_in_days) {
+                    let date = *start + chrono::Duration::days(day as i64);
+                    let row = [
+                        csv.text(name).into_owned(),
+                        day.to_string(),
+                        date.to_string(),
+                        csv.ada(*ada),
+                        money(*price),
+                        money(ada * price),
+                    ];
+                    writeln!(writer, "{}", csv.row(&row))?;
+                }
+            }
+        }
+        writer.flush()
+    });
+    match written {
+        Ok(()) => {
+            println!("Saved CSV as {} to Disk.", absolute_path(&path));
+            true
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to Write CSV [{}] to Disk.", path);
+            eprintln!("Reason: {}", describe_write_error(&path, &e));
+            false
+        }
+    }
+}
+
+// Wide CSV one ADA and one total column per wallet then the portfolio the price is shared so it is written once
+// pool is any wallet only its calendar is used
+pub fn write_portfolio_csv(results: &[(String, StakedCardanoPoolResult)], pool: &StakedCardanoPool, args: &CommandOptions) -> bool {
+    let path = args.csv_out.clone().unwrap_or_else(|| default_csv_path(args));
+    let written = open_output_file(&path, args.force).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        let (csv, currency) = (args.csv_dialect, args.usd_format.currency);
+        let money = |usd: f64| csv.value(currency.convert(usd));
+        let mut header = format!("Day,Date,{}", currency.column("Price"));
+        for (name, _) in results.iter() {
+            header.push_str(&format!(",ADA {},{}", name, currency.column(&format!("Total {}", name))));
+        }
+        header.push_str(&format!(",Portfolio ADA,{}", currency.column("Portfolio Total")));
+        writeln!(writer, "{}", csv.header(&header))?;
+        let longest = results.iter().max_by_key(|(_, result)| result.price_historical.len()).map(|(_, result)| result);
+        let (prices, first_day) = match longest {
+            Some(result) => (&result.price_historical, result.baseline.map(|(day, ..)| day + 1).unwrap_or(1)),
+            None => return writer.flush(),
+        };
+        let last_day = first_day + prices.len() as u64 - 1;
+        for (i, (day, price)) in (first_day..).zip(prices.iter()).enumerate() {
+            if !args.csv_granularity.writes_row(day, last_day, pool.epoch_in_days) {
+                continue;
+            }
+            let mut row = vec![day.to_string(), pool.date_on(day).to_string(), money(*price)];
+            let (mut ada, mut total) = (0.0, 0.0);
+            for (_, result) in results.iter() {
+                let (wallet_ada, wallet_total) = wallet_on(result, i);
+                row.extend([csv.ada(wallet_ada), money(wallet_total)]);
+                ada += wallet_ada;
+                total += wallet_total;
+            }
+            row.extend([csv.ada(ada), money(total)]);
+            writeln!(writer, "{}", csv.row(&row))?;
+        }
+        writer.flush()
+    });
+    match written {
+        Ok(()) => {
+            println!("Saved CSV as {} to Disk.", absolute_path(&path));
+            true
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to Write CSV [{}] to Disk.", path);
+            eprintln!("Reason: {}", describe_write_error(&path, &e));
+            false
+        }
+    }
+}
@@ -0,0 +1,719 @@
+/*
+
+ADA Staking CLI Tool (⚠️ Not Offical or Investment Advice ⚠️)
+Copyright (C) 2022  zulrah93 <1s16slrse@mozmail.com>
+
+This program is free software; you can redistribute it and/or
+modify it under the terms of the GNU General Public License
+as published by the Free Software Foundation; either version 2
+of the License, or (at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program; if not, write to the Free Software
+Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301, USA.
+
+
+*/
+
+// Growth graphs of a run as SVG or PNG plus the scenario, portfolio and Monte Carlo overlays
+use crate::csv::percentile_name;
+use crate::{
+    absolute_path, ada_symbol, describe_write_error, get_file_timestamp, in_output_dir, open_output_file,
+    print_diagnostic, stem_prefix, usd_symbol, CliError, CommandOptions,
+};
+use ada_calc::analysis::{monte_carlo_percentiles, percentile, wallet_on};
+use ada_calc::{StakedCardanoPool, StakedCardanoPoolResult};
+use poloto::prelude::*;
+use std::borrow::Cow;
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphFormat {
+    Svg,
+    Png,  // Rasterized from the SVG with resvg
+    Both, // The SVG and the PNG next to each other with the same name
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphTheme {
+    Light, // poloto's default simple theme
+    Dark,
+}
+
+// How the price, ADA and total are drawn they are far apart in size so one absolute scale flattens two of them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphLayout {
+    Indexed,  // One graph each line divided by its first value times 100
+    Combined, // One graph with the absolute values
+    Split,    // One graph per line with its own scale (_price, _ada and _total next to the graph path)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphScale {
+    Linear,
+    Log, // log10 of each value is plotted and the ticks are written back as the real values
+}
+
+// Upper bound on payout dots drawn on the graph
+const MAX_PAYOUT_DOTS: usize = 120;
+
+// Upper bound on year boundary ticks with --graph-annotations a century long run marks every other year or so
+const MAX_YEAR_TICKS: usize = 50;
+
+// Default --graph-max-points a 5 year run is drawn day by day and longer ones are thinned to this many points per line
+pub const DEFAULT_GRAPH_MAX_POINTS: usize = 2000;
+
+// Widest or tallest PNG rendered about 170 MB of pixels at the graph's aspect ratio
+const MAX_GRAPH_WIDTH: u32 = 8192;
+
+// PNG size without --graph-size or --graph-width fits for chat previews
+const DEFAULT_PNG_SIZE: (u32, u32) = (1280, 720);
+
+// Title and axis labels with --graph-title and --graph-axis applied over the defaults
+fn graph_labels(args: &CommandOptions, title: &str, y_label: String) -> (String, String, String) {
+    (
+        args.graph_title.clone().unwrap_or_else(|| title.to_string()),
+        args.graph_axis.0.clone().unwrap_or_else(|| String::from("Days")),
+        args.graph_axis.1.clone().unwrap_or(y_label),
+    )
+}
+
+fn themed_svg<P: SimpleTheme>(plot: P, args: &CommandOptions) -> String {
+    match args.graph_theme {
+        GraphTheme::Light => format!("{}", poloto::disp(|w| plot.simple_theme(w))),
+        GraphTheme::Dark => format!("{}", poloto::disp(|w| plot.simple_theme_dark(w))),
+    }
+}
+
+// Divides by the first value times 100 a start of 0 is left as it is rather than dividing by it
+fn index_to_100(start: f64) -> f64 {
+    if start > 0.0 {
+        100.0 / start
+    } else {
+        1.0
+    }
+}
+
+// Smallest value drawn with --graph-scale log zero balances sit on it instead of becoming -inf in the SVG
+const LOG_SCALE_FLOOR: f64 = 0.01;
+
+// Value as it is plotted on the growth graph log10 with --graph-scale log
+fn graph_y(value: f64, args: &CommandOptions) -> f64 {
+    match args.graph_scale {
+        GraphScale::Linear => value,
+        GraphScale::Log if value.is_finite() => value.max(LOG_SCALE_FLOOR).log10(),
+        GraphScale::Log => LOG_SCALE_FLOOR.log10(),
+    }
+}
+
+// The origin marker keeps the value axis at zero on a linear graph a log graph starts at its lowest value instead
+fn graph_origin(args: &CommandOptions) -> poloto::build::plot_iter_impl::Marker<std::option::IntoIter<f64>, std::option::IntoIter<f64>> {
+    let y = Some(0.0).filter(|_| args.graph_scale == GraphScale::Linear);
+    poloto::build::markers(Some(0.0), y)
+}
+
+// Price, ADA and total on one graph absolute with --graph-layout combined otherwise indexed to 100 at the start
+// The pool is only needed for --graph-include-real a graph redrawn from a CSV has none
+pub fn render_graph_svg(pool: Option<&StakedCardanoPool>, result: &StakedCardanoPoolResult, args: &CommandOptions) -> String {
+    let prices = &result.price_historical;
+    let adas = &result.amount_historical;
+
+    // Functions the index is clamped so a range that overshoots the history by a step reads the last day instead of panicking
+    // Dollar lines are drawn in the --currency like the rest of the output
+    let last = adas.len().min(prices.len()).saturating_sub(1);
+    let currency = args.usd_format.currency;
+    let indexed = args.graph_layout == GraphLayout::Indexed;
+    let (price_scale, ada_scale, total_scale) = if indexed {
+        (index_to_100(prices[0]), index_to_100(adas[0]), index_to_100(adas[0] * prices[0]))
+    } else {
+        (currency.convert(1.0), 1.0, currency.convert(1.0))
+    };
+    let p = |x: f64| graph_y(prices[(x as usize).min(last)] * price_scale, args);
+    let a = |x: f64| graph_y(adas[(x as usize).min(last)] * ada_scale, args);
+    let t = |x: f64| graph_y(adas[(x as usize).min(last)] * prices[(x as usize).min(last)] * total_scale, args);
+
+    // Each line is drawn through the same points at most --graph-max-points of them
+    let samples = graph_samples(result, args);
+    let points = |value: &dyn Fn(usize) -> f64| -> Vec<[f64; 2]> { samples.iter().map(|(x, i)| [*x, value(*i)]).collect() };
+    let (ada, usd) = (ada_symbol(args), usd_symbol(args));
+    let (price_unit, ada_unit) = if indexed {
+        (String::from("Index"), String::from("Index"))
+    } else {
+        (usd.to_string(), ada.to_string())
+    };
+    let line_prices = poloto::build::line(format!("Prices ({})", price_unit), points(&|i| p(i as f64)));
+    let line_adas = poloto::build::line(format!("ADAs ({})", ada_unit), points(&|i| a(i as f64)));
+    let line_total = poloto::build::line(format!("Total ({})", price_unit), points(&|i| t(i as f64)));
+    // The ADA put in held without staking priced the same as the total so the gap between the two is the staking
+    let helds = &result.held_historical;
+    let h = |i: usize| graph_y(helds.get(i).copied().unwrap_or(adas[i]) * prices[i] * total_scale, args);
+    let line_held = poloto::build::line(format!("Hold ({})", price_unit), points(&h));
+
+    // Dots on the ADA line for each payout thinned so long horizons do not turn into a solid smear
+    let payouts: Vec<usize> = result
+        .payout_indices
+        .iter()
+        .copied()
+        .filter(|i| *i < adas.len())
+        .collect();
+    let thin_step = (payouts.len() / MAX_PAYOUT_DOTS).max(1);
+    let x_scale = history_span(result) / adas.len().max(1) as f64;
+    let payout_dots = poloto::build::scatter(
+        format!("Payouts ({})", ada_unit),
+        payouts
+            .iter()
+            .step_by(thin_step)
+            .map(|i| [*i as f64 * x_scale, graph_y(adas[*i] * ada_scale, args)]),
+    );
+
+    // Optional reference lines share one plots_dyn since poloto only copes with an empty one at the end of the chain
+    let mut reference_lines: Vec<(String, Vec<[f64; 2]>)> = Vec::new();
+
+    // Flat line at the initial investment so it is obvious when the total is above or below cost
+    let breakeven = graph_y(result.initial_investment * total_scale, args);
+    if args.graph_breakeven_line {
+        reference_lines.push((format!("Initial Investment ({})", price_unit), points(&|_| breakeven)));
+    }
+
+    // Rewards taken out instead of restaked valued when they were taken so it only ever goes up
+    if !result.harvested_historical.is_empty() {
+        let harvested = &result.harvested_historical;
+        reference_lines.push((
+            format!("Harvested ({})", price_unit),
+            points(&|i| graph_y(harvested[i.min(harvested.len() - 1)] * total_scale, args)),
+        ));
+    }
+
+    // The total discounted to today's dollars starts on the nominal total and falls below it as inflation adds up
+    if let (true, Some(pool)) = (args.graph_include_real, pool.filter(|pool| pool.annual_inflation != 0.0)) {
+        reference_lines.push((
+            format!("Real Total ({})", price_unit),
+            points(&|i| graph_y(adas[i] * prices[i] * total_scale / pool.inflation_factor(i as u64), args)),
+        ));
+    }
+
+    // A vertical line per event from zero to the top of the chart labelled in the legend
+    if args.graph_overlay_events {
+        let top = (0..adas.len())
+            .map(|i| p(i as f64).max(a(i as f64)).max(t(i as f64)))
+            .fold(breakeven, f64::max);
+        // A log axis has no zero so the lines start at the lowest value drawn
+        let bottom = match args.graph_scale {
+            GraphScale::Linear => 0.0,
+            GraphScale::Log => (0..adas.len())
+                .map(|i| p(i as f64).min(a(i as f64)).min(t(i as f64)))
+                .fold(breakeven, f64::min),
+        };
+        for (i, label) in result.events.iter().filter(|(i, _)| *i < adas.len()) {
+            let x = *i as f64 * x_scale;
+            reference_lines.push((label.clone(), vec![[x, bottom], [x, top]]));
+        }
+    }
+    let mut extra_plots: Vec<_> = reference_lines
+        .into_iter()
+        .map(|(label, points)| poloto::build::line(label, points))
+        .collect();
+
+    // Payout dots on the total line, a dot on the value axis floor at every year boundary and one at the end of the total
+    // labelled with the final value so the graph still explains itself away from the terminal output
+    if args.graph_annotations {
+        extra_plots.push(poloto::build::scatter(
+            String::from("Total Payouts"),
+            payouts.iter().step_by(thin_step).map(|i| [*i as f64 * x_scale, t(*i as f64)]).collect(),
+        ));
+        let floor = match args.graph_scale {
+            GraphScale::Linear => 0.0,
+            GraphScale::Log => (0..adas.len()).map(|i| p(i as f64).min(a(i as f64)).min(t(i as f64))).fold(f64::MAX, f64::min),
+        };
+        let first_day = result.baseline.map(|(day, ..)| day).unwrap_or(0);
+        let boundaries = year_boundaries(pool, first_day, history_span(result));
+        let tick_step = (boundaries.len() / MAX_YEAR_TICKS).max(1);
+        extra_plots.push(poloto::build::scatter(
+            String::from("Years"),
+            boundaries.iter().step_by(tick_step).map(|x| [*x, floor]).collect(),
+        ));
+        let years = pool.map(|pool| pool.years_holding).unwrap_or(history_span(result) / 365.25);
+        extra_plots.push(poloto::build::scatter(
+            // Whole units so the legend entry is not cut off at the edge of the graph
+            format!("{}{:.0} In {}y", currency.symbol, currency.convert(result.total()), (years * 100.0).round() / 100.0),
+            vec![[last as f64 * x_scale, t(last as f64)]],
+        ));
+    }
+    let extra_plots = poloto::build::plots_dyn(extra_plots);
+
+    let m = graph_origin(args);
+    let data = poloto::plots!(line_prices, line_adas, line_total, line_held, payout_dots, extra_plots, m);
+
+    let y_label = if indexed { String::from("Index (Start = 100)") } else { format!("{} {}", usd, ada) };
+    let title = match pool.filter(|_| args.graph_annotations) {
+        Some(pool) => format!("Cardano Staking Growth ({})", graph_assumptions(pool)),
+        None => String::from("Cardano Staking Growth"),
+    };
+    let svg = growth_svg(data, graph_labels(args, &title, y_label), args);
+    // The hold total is the fourth plot dashed so it reads as a reference rather than another holding
+    svg.replacen("</style>", ".poloto3stroke{stroke-dasharray:10 5}</style>", 1)
+}
+
+// Starting ADA, yield and price growth for the annotated graph title
+fn graph_assumptions(pool: &StakedCardanoPool) -> String {
+    let price = if !pool.daily_prices.is_empty() {
+        String::from("Historical Prices")
+    } else if !pool.price_yields.is_empty() {
+        String::from("Yearly Price Schedule")
+    } else {
+        format!("Price {:+.1}%/Year", (pool.daily_price_yield().powf(pool.days_per_year()) - 1.0) * 100.0)
+    };
+    format!("{} ADA, {:.2}% Yield, {}", pool.ada, pool.annual_yield * 100.0, price)
+}
+
+// Graph x of every whole year into the run calendar years from the pool and 365.25 days without one (a graph redrawn from a CSV)
+// first_day is the day the history starts after it --skip-epochs moved it
+fn year_boundaries(pool: Option<&StakedCardanoPool>, first_day: u64, span: f64) -> Vec<f64> {
+    let last_day = first_day + span as u64;
+    match pool {
+        Some(pool) => (first_day + 1..=last_day)
+            .filter(|day| pool.years_on(*day).floor() > pool.years_on(day - 1).floor())
+            .map(|day| (day - first_day) as f64)
+            .collect(),
+        None => (1..)
+            .map(|year| year as f64 * 365.25)
+            .take_while(|day| *day <= last_day as f64)
+            .filter(|day| *day >= first_day as f64)
+            .map(|day| day - first_day as f64)
+            .collect(),
+    }
+}
+
+// Renders the growth plots with the value axis ticks at --precision decimals when it was given
+fn growth_svg<P: poloto::build::marker::Markerable<X = f64, Y = f64> + poloto::build::PlotIterator<Item = (f64, f64)>>(
+    data: P,
+    (title, x_label, y_label): (String, String, String),
+    args: &CommandOptions,
+) -> String {
+    let log = args.graph_scale == GraphScale::Log;
+    let decimals = match (args.graph_decimals, log) {
+        (Some(decimals), _) => Some(decimals),
+        (None, true) => None,
+        (None, false) => {
+            let p = poloto::simple_fmt!(data, title, x_label, y_label);
+            return themed_svg(p, args);
+        }
+    };
+    let y_label = if log { format!("{} (Log Scale)", y_label) } else { y_label };
+    // Same as simple_fmt! with the value axis ticks written at --precision decimals
+    // On a log graph the tick is the exponent so it is written back as the real value
+    let data = poloto::data(data);
+    let opt = poloto::render::render_opt_builder().build();
+    let (bx, by) = poloto::ticks::bounds(&data, &opt);
+    let xt = poloto::ticks::from_default(bx);
+    let yt = poloto::ticks::from_default(by).with_tick_fmt(move |w, v| {
+        let v = if log { 10f64.powf(*v) } else { *v };
+        // Without --precision values under 1 keep 2 decimals on a log graph so 0.01 and 0.1 do not both read 0
+        let decimals = decimals.unwrap_or(if v < 1.0 { 2 } else { 0 });
+        write!(w, "{:.*}", decimals, v)
+    });
+    let p = poloto::plot_with(data, opt, poloto::plot_fmt(title, x_label, y_label, xt, yt));
+    themed_svg(p, args)
+}
+
+// File suffix title line name and the value at each history index
+type SplitSeries<'a> = (&'static str, &'static str, String, Box<dyn Fn(usize) -> f64 + 'a>);
+
+// --graph-layout split one absolute graph per line each file named with its suffix
+fn render_split_svgs(result: &StakedCardanoPoolResult, args: &CommandOptions) -> Vec<(&'static str, String)> {
+    let prices = &result.price_historical;
+    let adas = &result.amount_historical;
+    let currency = args.usd_format.currency;
+    let samples = graph_samples(result, args);
+    let (ada, usd) = (ada_symbol(args), usd_symbol(args));
+    let series: [SplitSeries; 3] = [
+        ("price", "Cardano Price", format!("Prices ({})", usd), Box::new(|i| graph_y(currency.convert(prices[i]), args))),
+        ("ada", "Cardano Staked ADA", format!("ADAs ({})", ada), Box::new(|i| graph_y(adas[i], args))),
+        (
+            "total",
+            "Cardano Staking Total",
+            format!("Total ({})", usd),
+            Box::new(|i| graph_y(currency.convert(adas[i] * prices[i]), args)),
+        ),
+    ];
+    series
+        .iter()
+        .map(|(suffix, title, name, value)| {
+            let line = poloto::build::line(name.clone(), samples.iter().map(|(x, i)| [*x, value(*i)]).collect::<Vec<_>>());
+            let m = graph_origin(args);
+            let unit = if *suffix == "ada" { ada } else { usd };
+            (*suffix, growth_svg(poloto::plots!(line, m), graph_labels(args, title, unit.to_string()), args))
+        })
+        .collect()
+}
+
+// Graph path with _price, _ada or _total added before the extension
+pub fn split_graph_path(path: &str, suffix: &str) -> String {
+    let path = std::path::Path::new(path);
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    path.with_file_name(format!("{}_{}{}", stem, suffix, extension)).display().to_string()
+}
+
+// Printed instead of drawing a graph of a run with no days in its history
+pub const NO_GRAPH_HISTORY_WARNING: &str =
+    "Warning: No History To Graph (every day was skipped by --skip-epochs or the horizon is shorter than a day) skipping the graph.";
+
+// Returns false when the SVG could not be written to disk an empty history only warns since there is nothing to write
+pub fn generate_graph(
+    optional_path: Option<String>,
+    pool: Option<&StakedCardanoPool>,
+    result: &StakedCardanoPoolResult,
+    args: &CommandOptions,
+    output_to_stdout: bool,
+) -> bool {
+    if !has_graph_history(result) {
+        print_diagnostic(NO_GRAPH_HISTORY_WARNING, output_to_stdout);
+        return true;
+    }
+    if args.graph_layout == GraphLayout::Split {
+        // Every file is attempted even after one fails
+        return render_split_svgs(result, args).iter().fold(true, |written, (suffix, svg)| {
+            let path = optional_path.as_ref().map(|path| split_graph_path(path, suffix));
+            write_graph(path, svg, args, output_to_stdout) && written
+        });
+    }
+    write_graph(optional_path, &render_graph_svg(pool, result, args), args, output_to_stdout)
+}
+
+// History read back from a CSV written with --generate_csv for the graph subcommand any dialect and granularity is accepted
+// The delimiter is picked from the header and with a ; or tab delimiter a decimal comma is read as a point
+fn read_csv_history(path: &str) -> Result<StakedCardanoPoolResult, CliError> {
+    let text = std::fs::read_to_string(path).map_err(|e| CliError::MissingConfig(format!("Could not read {}: {}", path, e)))?;
+    let mut lines = text.lines();
+    let header = lines.next().unwrap_or_default();
+    let delimiter = if header.contains('\t') {
+        '\t'
+    } else if header.contains(';') {
+        ';'
+    } else {
+        ','
+    };
+    let columns: Vec<&str> = header.split(delimiter).collect();
+    // Money columns carry the --currency code like Price (EUR)
+    let column = |name: &str| columns.iter().position(|column| *column == name || column.starts_with(&format!("{} (", name)));
+    let (day_column, ada_column, price_column) = match (column("Day"), column("ADA"), column("Price")) {
+        (Some(day), Some(ada), Some(price)) => (day, ada, price),
+        _ => {
+            return Err(CliError::Parse(format!(
+                "{} has no Day, ADA and Price columns it needs to be a CSV written with --generate_csv",
+                path
+            )))
+        }
+    };
+    let hold_column = column("Hold Total");
+    let number = |field: &str| {
+        let field = if delimiter == ',' { Cow::Borrowed(field) } else { Cow::Owned(field.replace(',', ".")) };
+        field.trim().parse::<f64>().ok()
+    };
+    let (mut days, mut adas, mut prices, mut helds) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for (line, row) in lines.enumerate().filter(|(_, row)| !row.trim().is_empty()) {
+        let fields: Vec<&str> = row.split(delimiter).collect();
+        let value = |index: usize| fields.get(index).and_then(|field| number(field));
+        match (value(day_column), value(ada_column), value(price_column)) {
+            (Some(day), Some(ada), Some(price)) => {
+                days.push(day);
+                adas.push(ada);
+                prices.push(price);
+                helds.push(hold_column.and_then(value).filter(|_| price > 0.0).map_or(adas[0], |total| total / price));
+            }
+            _ => return Err(CliError::Parse(format!("{} line {} is not a row of numbers: {}", path, line + 2, row))),
+        }
+    }
+    if adas.is_empty() {
+        return Err(CliError::Parse(format!("{} has no rows to graph", path)));
+    }
+    let last_day = days.last().copied().unwrap_or_default();
+    let mut result = StakedCardanoPoolResult::new(adas[adas.len() - 1], prices[prices.len() - 1], adas, prices, last_day as u64);
+    result.initial_investment = result.amount_historical[0] * result.price_historical[0];
+    result.held_historical = helds;
+    Ok(result)
+}
+
+// The graph subcommand redraws the growth graph from a saved CSV without simulating
+pub fn graph_from_csv(csv_path: &str, out: Option<&str>, args: &CommandOptions) -> Result<(), CliError> {
+    let result = read_csv_history(csv_path)?;
+    let path = out.map(String::from).or_else(|| args.svg_out.clone()).unwrap_or_else(|| default_graph_path(args));
+    // The graph is the only thing this subcommand makes so --on-write-error warn does not apply
+    if generate_graph(Some(path), None, &result, args, false) {
+        Ok(())
+    } else {
+        Err(CliError::Write(String::from("The graph could not be written")))
+    }
+}
+
+// Days the history is drawn over a run that ran out of ADA stopped early so it only covers the days simulated
+fn history_span(result: &StakedCardanoPoolResult) -> f64 {
+    match result.depleted_day {
+        Some(_) => result.amount_historical.len() as f64,
+        None => result.days_as_float,
+    }
+}
+
+// Indices of a history drawn on a graph every one when there are at most max_points
+// A longer history keeps the lowest and highest value of each bucket so payout steps and price swings survive
+// and the first and last index are always kept so the line ends on the final result
+fn graph_indices(len: usize, max_points: usize, value: impl Fn(usize) -> f64) -> Vec<usize> {
+    if len <= max_points.max(2) {
+        return (0..len).collect();
+    }
+    let buckets = (max_points.saturating_sub(2) / 2).max(1);
+    let size = (len - 2).div_ceil(buckets);
+    let mut indices = vec![0];
+    for start in (1..len - 1).step_by(size) {
+        let end = (start + size).min(len - 1);
+        let (mut low, mut high) = (start, start);
+        for i in start..end {
+            if value(i) < value(low) {
+                low = i;
+            }
+            if value(i) > value(high) {
+                high = i;
+            }
+        }
+        indices.push(low.min(high));
+        if low != high {
+            indices.push(low.max(high));
+        }
+    }
+    indices.push(len - 1);
+    indices
+}
+
+// Graph x and history index of each point on the growth graph lines picked on the total
+fn graph_samples(result: &StakedCardanoPoolResult, args: &CommandOptions) -> Vec<(f64, usize)> {
+    let (adas, prices) = (&result.amount_historical, &result.price_historical);
+    let len = adas.len().min(prices.len());
+    let last = len.saturating_sub(1);
+    if prices.len() <= args.graph_max_points {
+        // Every day at the x the graph has always used
+        return poloto::range_iter([0.0, history_span(result)], prices.len())
+            .map(|x| (x, (x as usize).min(last)))
+            .collect();
+    }
+    let x_scale = history_span(result) / last.max(1) as f64;
+    graph_indices(len, args.graph_max_points, |i| adas[i] * prices[i])
+        .into_iter()
+        .map(|i| (i as f64 * x_scale, i))
+        .collect()
+}
+
+// render_graph_svg indexes both histories by day so they have to be filled and the same length
+pub fn has_graph_history(result: &StakedCardanoPoolResult) -> bool {
+    !result.amount_historical.is_empty() && result.amount_historical.len() == result.price_historical.len()
+}
+
+// Default graph file name the extension follows --graph-format
+pub fn default_graph_path(args: &CommandOptions) -> String {
+    let extension = match args.graph_format {
+        GraphFormat::Svg | GraphFormat::Both => "svg",
+        GraphFormat::Png => "png",
+    };
+    in_output_dir(
+        args.output_dir.as_deref(),
+        &format!(
+            "ada_growth_graph_{}{}.{}",
+            stem_prefix(args),
+            get_file_timestamp(args.timestamp_format),
+            extension
+        ),
+    )
+}
+
+// Rasterizes the graph system fonts are loaded so the axis labels and legend are drawn
+// --graph-width scales the whole graph otherwise it is fitted and centered in --graph-size on the theme's background
+fn render_png(svg: &str, args: &CommandOptions) -> Result<Vec<u8>, String> {
+    let mut options = resvg::usvg::Options::default();
+    let fonts = options.fontdb_mut();
+    fonts.load_system_fonts();
+    // poloto asks for Roboto or sans-serif which fontdb maps to Arial so fall back to any installed font when neither is there
+    let query = resvg::usvg::fontdb::Query {
+        families: &[resvg::usvg::fontdb::Family::SansSerif],
+        ..Default::default()
+    };
+    if fonts.query(&query).is_none() {
+        let families: Vec<(String, bool)> = fonts
+            .faces()
+            .filter_map(|face| face.families.first().map(|(family, _)| (family.clone(), face.monospaced)))
+            .collect();
+        let fallback = families
+            .iter()
+            .find(|(family, monospaced)| !monospaced && family.contains("Sans"))
+            .or_else(|| families.first())
+            .map(|(family, _)| family.clone());
+        if let Some(family) = fallback {
+            fonts.set_sans_serif_family(family);
+        }
+    }
+    let tree = resvg::usvg::Tree::from_str(svg, &options).map_err(|e| e.to_string())?;
+    let size = tree.size();
+    let (width, height, scale) = match args.graph_width {
+        Some(width) => {
+            let scale = width as f32 / size.width();
+            (width, (size.height() * scale).ceil() as u32, scale)
+        }
+        None => {
+            let (width, height) = args.graph_size.unwrap_or(DEFAULT_PNG_SIZE);
+            (width, height, (width as f32 / size.width()).min(height as f32 / size.height()))
+        }
+    };
+    if width > MAX_GRAPH_WIDTH || height > MAX_GRAPH_WIDTH {
+        return Err(format!("{}x{} is above {} pixels on a side", width, height, MAX_GRAPH_WIDTH));
+    }
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| String::from("the image size is zero or too large"))?;
+    // Same colors as the poloto_background rule of each theme
+    pixmap.fill(match args.graph_theme {
+        GraphTheme::Light => resvg::tiny_skia::Color::from_rgba8(240, 248, 255, 255),
+        GraphTheme::Dark => resvg::tiny_skia::Color::from_rgba8(38, 38, 38, 255),
+    });
+    let x = (width as f32 - size.width() * scale) / 2.0;
+    let y = (height as f32 - size.height() * scale) / 2.0;
+    resvg::render(
+        &tree,
+        resvg::tiny_skia::Transform::from_scale(scale, scale).post_translate(x, y),
+        &mut pixmap.as_mut(),
+    );
+    pixmap.encode_png().map_err(|e| e.to_string())
+}
+
+// Shared by the single pool and scenario graphs
+pub fn write_graph(optional_path: Option<String>, svg: &str, args: &CommandOptions, output_to_stdout: bool) -> bool {
+    if output_to_stdout {
+        println!("<div class='svg' style='width:1in;height:1in'>{}</div>", svg);
+    }
+    else if let Some(path) = optional_path.as_ref() {
+        let mut files = Vec::new();
+        let mut rendered = true;
+        if args.graph_format != GraphFormat::Png {
+            files.push((graph_file_path(path, "svg", args), svg.as_bytes().to_vec(), "SVG"));
+        }
+        if args.graph_format != GraphFormat::Svg {
+            let png_path = graph_file_path(path, "png", args);
+            match render_png(svg, args) {
+                Ok(png) => files.push((png_path, png, "PNG")),
+                // The SVG still has the whole graph so it is written next to where the PNG would have gone
+                Err(e) if args.graph_format == GraphFormat::Png => {
+                    let svg_path = std::path::Path::new(path).with_extension("svg").display().to_string();
+                    eprintln!("Warning: Failed to render the PNG [{}] ({}) writing the SVG to {} instead", png_path, e, svg_path);
+                    files.push((svg_path, svg.as_bytes().to_vec(), "SVG"));
+                }
+                // The SVG is still written and the run fails after it
+                Err(e) => {
+                    eprintln!("Error: Failed to Render PNG [{}].", png_path);
+                    eprintln!("Reason: {}", e);
+                    rendered = false;
+                }
+            }
+        }
+        for (path, bytes, format) in files.iter() {
+            let written = open_output_file(path, args.force).and_then(|mut file| file.write_all(bytes));
+            if let Err(e) = written {
+                eprintln!("Error: Failed to Write {} [{}] to Disk.", format, path);
+                eprintln!("Reason: {}", describe_write_error(path, &e));
+                return false;
+            }
+            println!("Generated Graph in {} Format Under {}", format, absolute_path(path));
+        }
+        return rendered;
+    }
+    true
+}
+
+// With --graph-format both the SVG and PNG share the graph path's name with their own extension
+pub fn graph_file_path(path: &str, extension: &str, args: &CommandOptions) -> String {
+    match args.graph_format {
+        GraphFormat::Both => std::path::Path::new(path).with_extension(extension).display().to_string(),
+        _ => path.to_string(),
+    }
+}
+
+// One total line per scenario labelled with its name
+pub fn render_scenarios_svg(results: &[(String, StakedCardanoPoolResult)], args: &CommandOptions) -> String {
+    let usd = usd_symbol(args);
+    let lines = poloto::build::plots_dyn(
+        results
+            .iter()
+            .map(|(name, result)| {
+                let x_scale = history_span(result) / result.amount_historical.len().max(1) as f64;
+                let (adas, prices) = (&result.amount_historical, &result.price_historical);
+                let total = |i: usize| adas[i] * prices[i];
+                let points: Vec<[f64; 2]> = graph_indices(adas.len().min(prices.len()), args.graph_max_points, total)
+                    .into_iter()
+                    .map(|i| [i as f64 * x_scale, args.usd_format.currency.convert(total(i))])
+                    .collect();
+                poloto::build::line(format!("{} Total ({})", name, usd), points)
+            })
+            .collect(),
+    );
+    let m = poloto::build::origin();
+    let (title, x_label, y_label) = graph_labels(args, "Cardano Staking Scenarios", usd.to_string());
+    let p = poloto::simple_fmt!(poloto::plots!(lines, m), title, x_label, y_label);
+    themed_svg(p, args)
+}
+
+// The portfolio total and with --graph-wallets each wallet's total under it
+pub fn render_portfolio_svg(results: &[(String, StakedCardanoPoolResult)], args: &CommandOptions) -> String {
+    let usd = usd_symbol(args);
+    let currency = args.usd_format.currency;
+    let longest = results.iter().map(|(_, result)| result).max_by_key(|result| result.amount_historical.len());
+    let (len, x_scale) = match longest {
+        Some(result) => (result.amount_historical.len(), history_span(result) / result.amount_historical.len().max(1) as f64),
+        None => (0, 1.0),
+    };
+    let portfolio_total = |i: usize| -> f64 { results.iter().map(|(_, result)| wallet_on(result, i).1).sum() };
+    // Every line uses the points picked on the portfolio total so the wallets still add up to it
+    let indices = graph_indices(len, args.graph_max_points, portfolio_total);
+    let mut lines = vec![poloto::build::line(
+        format!("Portfolio Total ({})", usd),
+        indices
+            .iter()
+            .map(|i| [*i as f64 * x_scale, currency.convert(portfolio_total(*i))])
+            .collect::<Vec<[f64; 2]>>(),
+    )];
+    if args.graph_wallets {
+        for (name, result) in results.iter() {
+            lines.push(poloto::build::line(
+                format!("{} Total ({})", name, usd),
+                indices.iter().map(|i| [*i as f64 * x_scale, currency.convert(wallet_on(result, *i).1)]).collect(),
+            ));
+        }
+    }
+    let lines = poloto::build::plots_dyn(lines);
+    let m = poloto::build::origin();
+    let (title, x_label, y_label) = graph_labels(args, "Cardano Staking Portfolio", usd.to_string());
+    let p = poloto::simple_fmt!(poloto::plots!(lines, m), title, x_label, y_label);
+    themed_svg(p, args)
+}
+
+// Median total with the --confidence percentile lines around it columns are sorted totals per graph point
+pub fn render_monte_carlo_svg(days: &[f64], columns: &[Vec<f64>], args: &CommandOptions) -> String {
+    let usd = usd_symbol(args);
+    let lines = poloto::build::plots_dyn(
+        monte_carlo_percentiles(args.confidence)
+            .iter()
+            .map(|p| {
+                let points: Vec<[f64; 2]> = days
+                    .iter()
+                    .zip(columns.iter())
+                    .map(|(day, column)| [*day, args.usd_format.currency.convert(percentile(column, *p))])
+                    .collect();
+                poloto::build::line(format!("{} Total ({})", percentile_name(*p), usd), points)
+            })
+            .collect(),
+    );
+    let m = poloto::build::origin();
+    let (title, x_label, y_label) = graph_labels(args, "Cardano Staking Monte Carlo", usd.to_string());
+    let p = poloto::simple_fmt!(poloto::plots!(lines, m), title, x_label, y_label);
+    themed_svg(p, args)
+}
@@ -0,0 +1,2519 @@
+/*
+
+ADA Staking CLI Tool (⚠️ Not Offical or Investment Advice ⚠️)
+Copyright (C) 2022  zulrah93 <1s16slrse@mozmail.com>
+
+This program is free software; you can redistribute it and/or
+modify it under the terms of the GNU General Public License
+as published by the Free Software Foundation; either version 2
+of the License, or (at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program; if not, write to the Free Software
+Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301, USA.
+
+
+*/
+
+//! The staking simulation itself main.rs wraps it with the command line, printing and file output
+
+pub mod analysis;
+
+use chrono::{Datelike, NaiveDate};
+use rand::Rng;
+use rand_distr::{Distribution, LogNormal};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// What the simulation needs from the command line everything else there only affects output
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimulationOptions {
+    /// Use the compounding-equivalent per epoch rate instead of a simple slice of the annual yield
+    pub compound_exact: bool,
+    /// Rewards are not reinvested only the ADA bought earns
+    pub simple_interest: bool,
+    /// Epochs compounded before reporting starts the state after them becomes the baseline
+    pub skip_epochs: u64,
+    /// Keep the per day ADA and price history (needed for graphs and series dumps)
+    pub record_history: bool,
+    /// Keep every reward payout with its day and price (needed for the tax report)
+    pub record_payouts: bool,
+    /// Stop on the first reported day the target is reached
+    pub target: Option<Target>,
+    /// How the configured ada, amount_ada and tx_fee_ada go to whole lovelace rewards and purchases always round down like the ledger
+    pub rounding: RoundingMode,
+}
+
+/// How a value is rounded to a whole number of its last unit (a lovelace for ADA and a cent for USD)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RoundingMode {
+    /// Ties away from zero
+    HalfUp,
+    /// Ties to the even digit (banker's rounding)
+    #[default]
+    HalfEven,
+    /// Drop the extra digits
+    Truncate,
+}
+
+impl RoundingMode {
+    /// Rounds to a whole number callers scale first for decimal places
+    pub fn round(&self, value: f64) -> f64 {
+        let noise = 4.0 * f64::EPSILON * value.abs().max(1.0);
+        let floor = value.floor();
+        // Matches ties that are only off by float noise (for example 2.675 * 100.0 lands at 267.49999999999997)
+        let tie = (value - floor - 0.5).abs() <= noise;
+        match self {
+            // A value only float noise away from a whole number is that number so 0.29 * 100.0 still truncates to 29
+            RoundingMode::Truncate if (value - value.round()).abs() <= noise => value.round(),
+            RoundingMode::Truncate => value.trunc(),
+            RoundingMode::HalfUp if tie => (floor + 0.5).round(),
+            RoundingMode::HalfEven if tie => {
+                if floor % 2.0 == 0.0 {
+                    floor
+                } else {
+                    floor + 1.0
+                }
+            }
+            RoundingMode::HalfUp | RoundingMode::HalfEven => value.round(),
+        }
+    }
+}
+
+/// A value to stop at instead of running the whole years_holding
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    /// Total in USD the same total the final result shows
+    Usd(f64),
+    /// ADA owned including rewards waiting to be restaked
+    Ada(f64),
+}
+
+/// What happened on a day as far as rewards go
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PayDay {
+    No,
+    /// A pay day spent undelegated
+    Skipped,
+    /// Reward earned this epoch (gross is after saturation) it lands reward_lag_epochs pay days later
+    Paid { gross: f64, fees: f64, net: f64, saturation: f64 },
+}
+
+/// One row of the yearly summary a row per year from the start date the last one is partial when the horizon ends mid year
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct YearSummary {
+    pub year: u64,
+    pub end_day: u64,
+    pub end_date: NaiveDate,
+    /// The run ended before this year did
+    pub partial: bool,
+    /// ADA at the end of the year including rewards waiting to be restaked
+    pub ada: f64,
+    pub price: f64,
+    /// Total in USD the same total the final result shows
+    pub total: f64,
+    /// Rewards that landed during the year
+    pub rewards_ada: f64,
+    /// Same rewards valued at the price on the day each one landed
+    pub rewards_usd: f64,
+    /// Total against the money put in so far as a percentage gain
+    pub gain_pct: f64,
+}
+
+/// A reported day handed to the calculate_staked_pool_with callback
+#[derive(Debug, Clone, Copy)]
+pub struct DayRecord {
+    pub day: u64,
+    /// Calendar date of day from the pool's start date
+    pub date: NaiveDate,
+    /// Final day of the run
+    pub last_day: u64,
+    /// ADA before anything happened on this day
+    pub start_ada: f64,
+    pub start_price: f64,
+    /// USD put in on top of the initial investment before this day
+    pub start_contributed_usd: f64,
+    /// ADA bought or added on top of the initial ADA before this day
+    pub start_contributed_ada: f64,
+    /// ADA taken out by the periodic withdrawals before this day
+    pub start_withdrawn_ada: f64,
+    /// Rewards taken out instead of restaked when compound is false before this day
+    pub start_harvested_ada: f64,
+    /// Same rewards valued at the price on the day each one was taken out
+    pub start_harvested_usd: f64,
+    /// Rewards earned but still waiting out reward_lag_epochs before this day
+    pub start_in_flight: f64,
+    /// ADA left in the reserve before this day when reserve_ada is set
+    pub start_reserve: Option<f64>,
+    /// USD and ADA of the periodic contribution bought on this day
+    pub contribution: Option<(f64, f64)>,
+    /// ADA at the end of the day
+    pub ada: f64,
+    pub price: f64,
+    pub pay_day: PayDay,
+    /// annual_yield in effect on this day after annual_yield_schedule and annual_yield_decay
+    pub annual_yield: f64,
+    /// Reward that landed on this day the one earned reward_lag_epochs pay days before
+    pub credited: f64,
+    /// Rewards earned but not yet credited at the end of the day
+    pub in_flight: f64,
+    /// A price_events shock fired or a floor or cap held the price on this day
+    pub price_event: bool,
+}
+
+/// How often rewards are credited and the reward base recomputed epoch is how Cardano pays the others are for comparison
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compounding {
+    /// Every epoch_in_days days
+    #[default]
+    Epoch,
+    /// Every day ada_per_year / 365.25
+    Daily,
+    /// Every 365.25 / 12 (about 30.44) days
+    Monthly,
+}
+
+/// How long a year is for the run length, the per epoch reward and the price growth
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum DayCount {
+    /// Whole years follow the calendar from start_date so leap days count rates use the 365.25 day average
+    #[default]
+    #[serde(rename = "actual")]
+    Actual,
+    /// Every year is 365 days like most spreadsheets
+    #[serde(rename = "365")]
+    Days365,
+    /// Every year is 365.25 days the run is years_holding x 365.25 days rounded down
+    #[serde(rename = "365.25")]
+    Days365_25,
+}
+
+impl DayCount {
+    /// Days in one year of this day count
+    pub fn days_per_year(&self) -> f64 {
+        match self {
+            DayCount::Days365 => 365.0,
+            DayCount::Actual | DayCount::Days365_25 => 365.25,
+        }
+    }
+}
+
+/// Average days in a month used by monthly compounding
+pub const DAYS_PER_MONTH: f64 = 365.25 / 12.0;
+
+/// A fixed USD buy and or fixed ADA amount added every cadence_days (dollar-cost averaging)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Contribution {
+    /// USD of ADA bought at the price on the day
+    #[serde(default)]
+    pub amount_usd: f64,
+    /// ADA added as is its value on the day counts as money put in
+    #[serde(default)]
+    pub amount_ada: f64,
+    pub cadence_days: u64,
+}
+
+/// What a price_events entry does to the price
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceEventKind {
+    /// Multiplies the price by factor once at the end of the day
+    Shock,
+    /// The price never ends a day below value from the day on
+    Floor,
+    /// The price never ends a day above value from the day on
+    Cap,
+}
+
+/// A scripted price move {"day": 500, "kind": "shock", "factor": 0.4} is a 60% crash on day 500
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PriceEvent {
+    /// Day the event fires a floor or cap without a day or date holds from day 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day: Option<u64>,
+    /// Calendar date instead of a day counted from the start
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    pub kind: PriceEventKind,
+    /// Shock multiplier 0.4 is a 60% crash and 2.0 doubles the price
+    #[serde(default, skip_serializing_if = "is_unset")]
+    pub factor: f64,
+    /// Floor or cap in USD
+    #[serde(default, skip_serializing_if = "is_unset")]
+    pub value: f64,
+}
+
+impl PriceEvent {
+    /// Label of the event like Price Shock x0.4 used in warnings and on the graph
+    pub fn describe(&self) -> String {
+        match self.kind {
+            PriceEventKind::Shock => format!("Price Shock x{}", self.factor),
+            PriceEventKind::Floor => format!("Price Floor ${}", self.value),
+            PriceEventKind::Cap => format!("Price Cap ${}", self.value),
+        }
+    }
+}
+
+/// A breakpoint of annual_yield_schedule the yield applies from after_years until the next breakpoint
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct YieldStep {
+    /// Years from the start the step begins 0 is the first day
+    pub after_years: f64,
+    /// Fraction like annual_yield
+    #[serde(rename = "yield")]
+    pub annual_yield: f64,
+}
+
+/// A pool file every field of pool.json the CLI reads it with serde_json so a library caller can too
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StakedCardanoPool {
+    /// Total amount of ADA which uses 6 decimal places can be changed in the source code format options
+    pub ada: f64,
+    /// Start from the live CoinGecko price instead of initial_price (initial_price is still used if the fetch fails)
+    #[serde(default)]
+    pub fetch_price_via_api: bool,
+    /// Optional fiat code like eur also fetched and shown next to the USD price the run itself stays in USD
+    #[serde(default)]
+    pub price_api_currency: Option<String>,
+    /// Starting price in USD
+    pub initial_price: f64,
+    /// Deprecated daily price multiplier 1.0 keeps the price flat 0 when the pool uses one of the growth percentages below
+    #[serde(default, skip_serializing_if = "is_unset")]
+    pub price_yield: f64,
+    /// Daily price change in percent 0.1 is +0.1% a day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_price_growth_pct: Option<f64>,
+    /// Yearly price change in percent 20 is +20% a year spread evenly over 365.25 days
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annual_price_growth_pct: Option<f64>,
+    /// Optional daily multiplier per year [year 1, year 2, ...] years past the end use price_yield
+    #[serde(default)]
+    pub price_yields: Vec<f64>,
+    /// Optional USD price for each day from day 0 (a backtest) replaces the price growth the price stays flat after the last one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub daily_prices: Vec<f64>,
+    /// Expressed as a fraction for example 5% is 0.05
+    pub annual_yield: f64,
+    /// Optional [{"after_years": 2, "yield": 0.035}, ...] annual_yield is used before the first step
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annual_yield_schedule: Vec<YieldStep>,
+    /// Optional fraction the yield shrinks by every year 0.1 turns 5% into 4.5% in year 2
+    #[serde(default, skip_serializing_if = "is_unset")]
+    pub annual_yield_decay: f64,
+    /// How many days before a payout happens this is fixed by ADA currently 5 days but can be changed for future purposes
+    #[serde(default = "default_epoch_in_days")]
+    pub epoch_in_days: u64,
+    /// How many years will it be staked less than 1 one means less than a year for exaple 0.5 means half of the first year from start_date
+    pub years_holding: f64,
+    /// Optional first day of the run like "2025-01-31" (or one of the other DATE_FORMATS) today when left out day N of the output is N days after it
+    #[serde(default, deserialize_with = "deserialize_start_date")]
+    pub start_date: Option<NaiveDate>,
+    /// Optional date an epoch started on pay days then fall on the real epoch boundaries instead of every epoch_in_days from day 0
+    #[serde(default, alias = "current_epoch_start", deserialize_with = "deserialize_anchor_date")]
+    pub epoch_anchor_date: Option<NaiveDate>,
+    /// Number of the epoch starting on epoch_anchor_date worked out from mainnet when left out
+    #[serde(default)]
+    pub epoch_anchor_number: Option<u64>,
+    /// Optional inclusive day ranges where the ADA is not delegated for example [[30, 60]] no rewards are paid but price still moves
+    #[serde(default)]
+    pub undelegated_ranges: Vec<(u64, u64)>,
+    /// Optional ceiling in USD the price stops growing once it is reached
+    #[serde(default)]
+    pub max_price: Option<f64>,
+    /// Optional scripted shocks, floors and caps applied in order after the daily price change
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub price_events: Vec<PriceEvent>,
+    /// Fraction of the rewards (after the fixed fee) kept by the pool operator for example 2% is 0.02
+    #[serde(default)]
+    pub pool_margin: f64,
+    /// ADA taken by the pool operator from your rewards every epoch before the margin is applied
+    #[serde(default, alias = "pool_fixed_fee_ada")]
+    pub pool_fixed_fee: f64,
+    /// Optional one time buys as [day, usd_amount] pairs ADA is bought at the price on that day
+    #[serde(default)]
+    pub lump_sums: Vec<(u64, f64)>,
+    /// Fractional price impact per USD bought for example 0.000001 means a $10,000 buy pays 1% over the price
+    #[serde(default)]
+    pub slippage_per_usd: f64,
+    /// Fraction of each reward kept as ADA (compounding) the rest is sold for USD at the price on the pay day
+    #[serde(default = "default_reward_ada_fraction")]
+    pub reward_ada_fraction: f64,
+    /// Rewards wait in a pending bucket (not earning) and are restaked every Nth paid epoch 1 restakes every epoch
+    #[serde(default = "default_restake_every_n_epochs")]
+    pub restake_every_n_epochs: u64,
+    /// Optional ADA left in the reserve when set rewards come from the reserve emission below instead of annual_yield
+    #[serde(default)]
+    pub reserve_ada: Option<f64>,
+    /// Fraction of the reserve released every epoch the protocol uses 0.003
+    #[serde(default = "default_rho")]
+    pub rho: f64,
+    /// Fraction of the released rewards sent to the treasury the protocol uses 0.2
+    #[serde(default = "default_tau")]
+    pub tau: f64,
+    /// ADA delegated across the whole network your share of the epoch rewards is ada / total_stake_ada
+    #[serde(default = "default_total_stake_ada")]
+    pub total_stake_ada: f64,
+    /// Optional [day, fraction_lost] pairs for comparing with chains that slash Cardano never does
+    #[serde(default)]
+    pub slash_events: Vec<(u64, f64)>,
+    /// Optional gross ADA the whole pool earns per epoch set with pool_active_stake to split real pool stats instead of annual_yield
+    #[serde(default)]
+    pub pool_epoch_reward: Option<f64>,
+    /// Optional ADA delegated to the pool including yours pool_fixed_fee and pool_margin then apply to the pool reward as on chain (alone the pool reward is modelled from your reward)
+    #[serde(default, alias = "pool_total_stake_ada")]
+    pub pool_active_stake: Option<f64>,
+    /// Pay days between earning a reward and it landing in the balance 0 (default) pays immediately Cardano really pays two epochs late
+    #[serde(default, alias = "reward_delay_epochs")]
+    pub reward_lag_epochs: u64,
+    /// ADA paid for each withdrawal or manual restake transaction (only with reward_ada_fraction below 1 or restake_every_n_epochs above 1)
+    #[serde(default = "default_tx_fee_ada")]
+    pub tx_fee_ada: f64,
+    /// Fraction of the normal reward an oversaturated pool pays 1.0 is an unsaturated pool (not used with pool_epoch_reward which is already what the pool paid)
+    #[serde(default = "default_saturation_factor")]
+    pub saturation_factor: f64,
+    /// Optional saturation_factor per year [year 1, year 2, ...] for a pool that fills up years past the end use saturation_factor
+    #[serde(default)]
+    pub saturation_factors: Vec<f64>,
+    /// Optional {"amount_usd": 100, "cadence_days": 30} ADA is bought at the price on every cadence_days day
+    #[serde(default)]
+    pub contribution: Option<Contribution>,
+    /// Daily volatility of the price used by --monte-carlo for example 0.03 is roughly a 3% daily swing
+    #[serde(default, alias = "daily_volatility")]
+    pub price_volatility: f64,
+    /// Optional income tax rate on rewards as a fraction each reward is taxed at its USD value on the day it is received
+    #[serde(default, alias = "income_tax_rate")]
+    pub reward_tax_rate: f64,
+    /// Optional yearly inflation as a fraction for example 3% is 0.03 used to report the real (inflation adjusted) total
+    #[serde(default, alias = "annual_inflation_rate")]
+    pub annual_inflation: f64,
+    /// "epoch" (default) "daily" or "monthly" how often rewards are credited (reward_lag_epochs then counts credits)
+    #[serde(default)]
+    pub compounding: Compounding,
+    /// "actual" (default) "365" or "365.25" days in a year for the run length, the per epoch reward and the price growth
+    #[serde(default)]
+    pub day_count: DayCount,
+    /// Rewards are restaked false withdraws every reward as it lands (reward_ada_fraction and restake_every_n_epochs are then ignored)
+    #[serde(default = "default_compound", alias = "restake_rewards")]
+    pub compound: bool,
+    /// Optional USD of ADA sold every withdrawal_interval_days the run stops early if the ADA runs out
+    #[serde(default)]
+    pub withdrawal_usd_per_interval: f64,
+    #[serde(default)]
+    pub withdrawal_interval_days: u64,
+    /// Optional pool stake above which rewards are scaled by limit / pool stake the pool stake is pool_active_stake with your ADA as it grows
+    #[serde(default)]
+    pub saturation_limit_ada: Option<f64>,
+    /// Move to a fresh pool once saturation_limit_ada is passed so rewards are never scaled down
+    #[serde(default)]
+    pub auto_redelegate: bool,
+}
+
+// Days from start to the same date years later a February 29 start lands on February 28 in other years
+fn calendar_days(start: NaiveDate, years: i32) -> Option<u64> {
+    let year = start.year().checked_add(years)?;
+    let end = NaiveDate::from_ymd_opt(year, start.month(), start.day())
+        .or_else(|| NaiveDate::from_ymd_opt(year, start.month(), start.day() - 1))?;
+    Some((end - start).num_days() as u64)
+}
+
+// The first Shelley epoch on mainnet 208 started on July 29 2020 later epochs are every 5 days from it
+// Other epoch lengths are not mainnet so the anchor is counted as epoch 0
+fn mainnet_epoch_number(anchor: NaiveDate, epoch_in_days: u64) -> i64 {
+    match (epoch_in_days, NaiveDate::from_ymd_opt(2020, 7, 29)) {
+        (5, Some(shelley)) => 208 + (anchor - shelley).num_days().div_euclid(5),
+        _ => 0,
+    }
+}
+
+// ADA on chain is a whole number of lovelace (0.000001 ADA)
+const LOVELACE_PER_ADA: f64 = 1_000_000.0;
+
+// Balances inside the simulation are counted in whole lovelace so decades of rewards never pick up float noise prices stay f64
+// Configured amounts go to a whole lovelace with the run's RoundingMode
+fn lovelace(ada: f64, rounding: RoundingMode) -> u128 {
+    rounding.round(ada * LOVELACE_PER_ADA).max(0.0) as u128
+}
+
+// Rewards and purchases are rounded down to whole lovelace like the ledger does the small nudge keeps 6.844627 from flooring to 6.844626
+fn floor_lovelace(ada: f64) -> u128 {
+    (ada * LOVELACE_PER_ADA + 1e-6).floor().max(0.0) as u128
+}
+
+fn to_ada(lovelace: u128) -> f64 {
+    lovelace as f64 / LOVELACE_PER_ADA
+}
+
+/// Formats start_date and epoch_anchor_date may be written in with the name shown in errors ISO 8601 is tried first
+pub const DATE_FORMATS: [(&str, &str); 3] = [("%Y-%m-%d", "YYYY-MM-DD"), ("%m/%d/%Y", "MM/DD/YYYY"), ("%d.%m.%Y", "DD.MM.YYYY")];
+
+/// Parses a date in format (a chrono format string like %d/%m/%Y) when one is given and then in the DATE_FORMATS
+/// the error lists every format tried
+pub fn parse_date(value: &str, format: Option<&str>) -> Result<NaiveDate, String> {
+    let value = value.trim();
+    format
+        .into_iter()
+        .chain(DATE_FORMATS.iter().map(|(format, _)| *format))
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+        .ok_or_else(|| {
+            let mut accepted: Vec<&str> = format.into_iter().collect();
+            accepted.extend(DATE_FORMATS.iter().map(|(_, name)| *name));
+            format!("{} is not a date the accepted formats are {}", value, accepted.join(", "))
+        })
+}
+
+fn deserialize_date_field<'de, D: serde::Deserializer<'de>>(deserializer: D, field: &str) -> Result<Option<NaiveDate>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value) => parse_date(&value, None)
+            .map(Some)
+            .map_err(|e| serde::de::Error::custom(format!("{} {}", field, e))),
+        None => Ok(None),
+    }
+}
+
+fn deserialize_start_date<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveDate>, D::Error> {
+    deserialize_date_field(deserializer, "start_date")
+}
+
+fn deserialize_anchor_date<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveDate>, D::Error> {
+    deserialize_date_field(deserializer, "epoch_anchor_date")
+}
+
+fn is_unset(value: &f64) -> bool {
+    *value == 0.0
+}
+
+fn default_epoch_in_days() -> u64 {
+    5
+}
+
+fn default_reward_ada_fraction() -> f64 {
+    1.0
+}
+
+fn default_restake_every_n_epochs() -> u64 {
+    1
+}
+
+fn default_tx_fee_ada() -> f64 {
+    0.17
+}
+
+fn default_compound() -> bool {
+    true
+}
+
+fn default_saturation_factor() -> f64 {
+    1.0
+}
+
+fn default_rho() -> f64 {
+    0.003
+}
+
+fn default_tau() -> f64 {
+    0.2
+}
+
+fn default_total_stake_ada() -> f64 {
+    22_000_000_000.0
+}
+
+// Old names still accepted through #[serde(alias)]
+const FIELD_ALIASES: [&str; 8] = [
+    "current_epoch_start",
+    "pool_fixed_fee_ada",
+    "pool_total_stake_ada",
+    "daily_volatility",
+    "income_tax_rate",
+    "annual_inflation_rate",
+    "restake_rewards",
+    "reward_delay_epochs",
+];
+
+// Deserializer that only records the field list serde derive hands to deserialize_struct then gives up
+struct FieldNameCollector<'a>(&'a mut &'static [&'static str]);
+
+impl<'de, 'a> serde::Deserializer<'de> for FieldNameCollector<'a> {
+    type Error = serde::de::value::Error;
+
+    fn deserialize_any<V: serde::de::Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(serde::de::Error::custom("only the field names are collected"))
+    }
+
+    fn deserialize_struct<V: serde::de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = fields;
+        self.deserialize_any(visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+impl StakedCardanoPool {
+    /// Every key a pool config may hold the fields come straight from the derive so they never drift from the struct
+    /// This serde does not list aliases there so they are added by hand
+    pub fn field_names() -> Vec<&'static str> {
+        let mut fields: &'static [&'static str] = &[];
+        let _ = StakedCardanoPool::deserialize(FieldNameCollector(&mut fields));
+        fields.iter().chain(FIELD_ALIASES.iter()).copied().collect()
+    }
+
+    /// Days stepped through by the simulation day 0 is the starting state
+    /// Whole years follow the calendar from the start so leap days count and the fraction left is that share of the next year
+    pub fn simulated_days(&self) -> u64 {
+        if self.day_count != DayCount::Actual {
+            // The tiny slack keeps 4 x 365.25 at 1461 when the product lands just under it
+            return (self.years_holding * self.days_per_year() + 1e-9) as u64;
+        }
+        let start = self.start();
+        let whole = self.years_holding.trunc();
+        match (calendar_days(start, whole as i32), calendar_days(start, whole as i32 + 1)) {
+            (Some(days), Some(next)) => days + ((self.years_holding - whole) * (next - days) as f64) as u64,
+            _ => (self.years_holding * 365.25) as u64, // Past the last date chrono can hold
+        }
+    }
+
+    /// years_holding that makes simulated_days exactly days
+    pub fn years_for_days(&self, days: u64) -> f64 {
+        if self.day_count != DayCount::Actual {
+            return (days as f64 + 0.5) / self.days_per_year();
+        }
+        let start = self.start();
+        let mut whole = 0;
+        while calendar_days(start, whole + 1).is_some_and(|next| next <= days) {
+            whole += 1;
+        }
+        match (calendar_days(start, whole), calendar_days(start, whole + 1)) {
+            // Half a day over so the fraction rounds down to exactly days
+            (Some(from), Some(next)) => whole as f64 + ((days - from) as f64 + 0.5) / (next - from) as f64,
+            _ => (days as f64 + 0.5) / 365.25,
+        }
+    }
+
+    /// Years gone by at the end of day whole calendar years plus the fraction of the current one with the actual day count
+    /// so a year boundary is exactly a whole number the same way simulated_days counts years
+    pub fn years_on(&self, day: u64) -> f64 {
+        if self.day_count != DayCount::Actual {
+            return day as f64 / self.days_per_year();
+        }
+        let start = self.start();
+        let mut whole = 0;
+        while calendar_days(start, whole + 1).is_some_and(|next| next <= day) {
+            whole += 1;
+        }
+        match (calendar_days(start, whole), calendar_days(start, whole + 1)) {
+            (Some(from), Some(next)) => whole as f64 + (day - from) as f64 / (next - from) as f64,
+            _ => day as f64 / 365.25,
+        }
+    }
+
+    /// Dividing a day's USD value by this gives it in today's dollars 1.0 without annual_inflation
+    pub fn inflation_factor(&self, day: u64) -> f64 {
+        (1.0 + self.annual_inflation).powf(self.years_on(day))
+    }
+
+    /// First day of the run start_date or today
+    pub fn start(&self) -> NaiveDate {
+        self.start_date.unwrap_or_else(|| chrono::Local::now().naive_local().date())
+    }
+
+    /// Calendar date of day counted from the start
+    pub fn date_on(&self, day: u64) -> NaiveDate {
+        self.start() + chrono::Duration::days(day as i64)
+    }
+
+    /// Day a price event fires its date counted from the start None for a floor or cap that holds from day 1
+    pub fn price_event_day(&self, event: &PriceEvent) -> Option<i64> {
+        match (event.day, event.date) {
+            (Some(day), _) => Some(day as i64),
+            (None, Some(date)) => Some((date - self.start()).num_days()),
+            (None, None) => None,
+        }
+    }
+
+    // Price at the end of day after price_events in the order given and whether any of them fired
+    fn apply_price_events(&self, day: u64, mut price: f64) -> (f64, Vec<usize>) {
+        let mut fired = Vec::new();
+        for (i, event) in self.price_events.iter().enumerate() {
+            let event_day = self.price_event_day(event);
+            let active = match event.kind {
+                PriceEventKind::Shock => event_day == Some(day as i64),
+                PriceEventKind::Floor | PriceEventKind::Cap => event_day.unwrap_or(0) <= day as i64,
+            };
+            if !active {
+                continue;
+            }
+            let moved = match event.kind {
+                PriceEventKind::Shock => price * event.factor,
+                PriceEventKind::Floor => price.max(event.value),
+                PriceEventKind::Cap => price.min(event.value),
+            };
+            if moved != price || event.kind == PriceEventKind::Shock {
+                fired.push(i);
+            }
+            price = moved;
+        }
+        (price, fired)
+    }
+
+    /// Events past the horizon are allowed a longer years_holding would reach them
+    pub fn price_event_warnings(&self) -> Vec<String> {
+        let last_day = self.simulated_days() as i64;
+        self.price_events
+            .iter()
+            .enumerate()
+            .filter_map(|(i, event)| {
+                let day = self.price_event_day(event).filter(|day| *day > last_day)?;
+                Some(format!(
+                    "price_events entry {} is on day {} after the last simulated day {} so it never fires",
+                    i + 1,
+                    day,
+                    last_day
+                ))
+            })
+            .collect()
+    }
+
+    /// Labels of the price_events that fired on a day close_price is the price the day ended at
+    /// A floor or cap counts while it holds the price at its value
+    pub fn price_event_labels(&self, day: u64, close_price: f64) -> Vec<String> {
+        self.price_events
+            .iter()
+            .filter(|event| match event.kind {
+                PriceEventKind::Shock => self.price_event_day(event) == Some(day as i64),
+                PriceEventKind::Floor | PriceEventKind::Cap => {
+                    self.price_event_day(event).unwrap_or(0) <= day as i64 && close_price == event.value
+                }
+            })
+            .map(|event| event.describe())
+            .collect()
+    }
+
+    /// Days in a year under day_count
+    pub fn days_per_year(&self) -> f64 {
+        self.day_count.days_per_year()
+    }
+
+    /// Epochs in a year under day_count fractional unless the year is a whole number of epochs
+    pub fn epochs_per_year(&self) -> f64 {
+        self.days_per_year() / (self.epoch_in_days as f64)
+    }
+
+    /// Times a year rewards are credited with the compounding mode
+    pub fn credits_per_year(&self) -> f64 {
+        match self.compounding {
+            Compounding::Epoch => self.epochs_per_year(),
+            Compounding::Daily => self.days_per_year(),
+            Compounding::Monthly => 12.0,
+        }
+    }
+
+    /// A month boundary falls on the first day at or past each multiple of DAYS_PER_MONTH
+    pub fn is_credit_day(&self, day: u64) -> bool {
+        self.is_credit_day_at(day, self.epoch_phase())
+    }
+
+    // phase is epoch_phase worked out once by the simulation rather than every day
+    fn is_credit_day_at(&self, day: u64, phase: u64) -> bool {
+        match self.compounding {
+            Compounding::Epoch => day > 0 && (day + phase).is_multiple_of(self.epoch_in_days),
+            Compounding::Daily => day > 0,
+            Compounding::Monthly => {
+                day > 0 && (day as f64 / DAYS_PER_MONTH).floor() > ((day - 1) as f64 / DAYS_PER_MONTH).floor()
+            }
+        }
+    }
+
+    /// How often rewards are credited in words like every 5 days
+    pub fn describe_compounding(&self) -> String {
+        match self.compounding {
+            Compounding::Epoch => format!("every {} days", self.epoch_in_days),
+            Compounding::Daily => String::from("every day"),
+            Compounding::Monthly => format!("every month ({:.2} days)", DAYS_PER_MONTH),
+        }
+    }
+
+    /// Days the start is into its epoch 0 without epoch_anchor_date so pay days are every epoch_in_days from day 0
+    pub fn epoch_phase(&self) -> u64 {
+        match self.epoch_anchor_date {
+            Some(anchor) => (self.start() - anchor).num_days().rem_euclid(self.epoch_in_days.max(1) as i64) as u64,
+            None => 0,
+        }
+    }
+
+    /// Part of a full epoch the pay day on day covers only the first one after an anchored start can be partial
+    pub fn epoch_share(&self, day: u64) -> f64 {
+        match self.compounding {
+            Compounding::Epoch => day.min(self.epoch_in_days) as f64 / self.epoch_in_days as f64,
+            _ => 1.0,
+        }
+    }
+
+    /// Absolute number of the epoch date falls in None without epoch_anchor_date
+    pub fn epoch_number(&self, date: NaiveDate) -> Option<i64> {
+        let anchor = self.epoch_anchor_date?;
+        let first = match self.epoch_anchor_number {
+            Some(number) => number as i64,
+            None => mainnet_epoch_number(anchor, self.epoch_in_days),
+        };
+        Some(first + (date - anchor).num_days().div_euclid(self.epoch_in_days.max(1) as i64))
+    }
+
+    /// Daily price multiplier from whichever of daily_price_growth_pct, annual_price_growth_pct or price_yield is set
+    pub fn daily_price_yield(&self) -> f64 {
+        match (self.daily_price_growth_pct, self.annual_price_growth_pct) {
+            (Some(pct), _) => 1.0 + pct / 100.0,
+            (None, Some(pct)) => (1.0 + pct / 100.0).powf(1.0 / self.days_per_year()),
+            (None, None) if self.price_yield == 0.0 && !self.daily_prices.is_empty() => 1.0, // After the last daily price it stays flat
+            (None, None) => self.price_yield,
+        }
+    }
+
+    /// Daily price multiplier for the move from day - 1 to day
+    pub fn price_yield_on(&self, day: u64) -> f64 {
+        if !self.daily_prices.is_empty() {
+            let last = self.daily_prices.len() - 1;
+            let (today, yesterday) = ((day as usize).min(last), (day.saturating_sub(1) as usize).min(last));
+            return self.daily_prices[today] / self.daily_prices[yesterday];
+        }
+        let year = (day.saturating_sub(1) as f64 / self.days_per_year()) as usize;
+        self.price_yields.get(year).copied().unwrap_or(self.daily_price_yield())
+    }
+
+    /// Old pool files still set price_yield directly it keeps working with this note
+    pub fn deprecation_note(&self) -> Option<String> {
+        match (self.price_yield, self.daily_price_growth_pct, self.annual_price_growth_pct) {
+            (price_yield, None, None) if price_yield != 0.0 => Some(format!(
+                "price_yield is deprecated use \"annual_price_growth_pct\": {:.4} or \"daily_price_growth_pct\": {:.4} for the same {} daily multiplier",
+                (price_yield.powf(365.25) - 1.0) * 100.0,
+                (price_yield - 1.0) * 100.0,
+                price_yield
+            )),
+            _ => None,
+        }
+    }
+
+    /// annual_yield in effect on day from annual_yield_schedule and annual_yield_decay
+    pub fn annual_yield_on(&self, day: u64) -> f64 {
+        let years = day.saturating_sub(1) as f64 / self.days_per_year();
+        let scheduled = self
+            .annual_yield_schedule
+            .iter()
+            .rev()
+            .find(|step| step.after_years <= years)
+            .map_or(self.annual_yield, |step| step.annual_yield);
+        scheduled * (1.0 - self.annual_yield_decay).powi(years as i32)
+    }
+
+    /// True when annual_yield changes over the run
+    pub fn has_yield_schedule(&self) -> bool {
+        !self.annual_yield_schedule.is_empty() || self.annual_yield_decay > 0.0
+    }
+
+    /// Reward multiplier for the pay day on day with the same yearly lookup as price_yields
+    pub fn saturation_on(&self, day: u64) -> f64 {
+        let year = (day.saturating_sub(1) as f64 / self.days_per_year()) as usize;
+        self.saturation_factors.get(year).copied().unwrap_or(self.saturation_factor)
+    }
+
+    /// ADA delegated to the pool with your stake swapped in for your starting ADA just yours without pool_active_stake
+    pub fn pool_stake(&self, stake: f64) -> f64 {
+        self.pool_active_stake.map(|active| active - self.ada + stake).unwrap_or(stake)
+    }
+
+    /// Reward multiplier from saturation_limit_ada 1.0 below the limit or when auto_redelegate moves to a fresh pool
+    pub fn stake_saturation(&self, stake: f64) -> f64 {
+        match self.saturation_limit_ada {
+            Some(limit) if !self.auto_redelegate && self.pool_stake(stake) > limit => limit / self.pool_stake(stake),
+            _ => 1.0,
+        }
+    }
+
+    /// price_yield as shown in messages including the yearly values when there are some
+    pub fn describe_price_yield(&self) -> String {
+        if !self.daily_prices.is_empty() {
+            format!("historical prices for {} days", self.daily_prices.len())
+        } else if self.price_yields.is_empty() {
+            self.daily_price_yield().to_string()
+        } else {
+            format!("{:?} per year then {}", self.price_yields, self.daily_price_yield())
+        }
+    }
+
+    /// True when day falls in one of the undelegated_ranges
+    pub fn is_undelegated(&self, day: u64) -> bool {
+        self.undelegated_ranges
+            .iter()
+            .any(|(start, end)| day >= *start && day <= *end)
+    }
+
+    /// Catches the inputs that would crash the simulation or make it meaningless
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors: Vec<String> = Vec::new();
+        if self.ada <= 0.0 {
+            errors.push(format!("ada must be greater than 0 (found {})", self.ada));
+        }
+        if self.initial_price <= 0.0 {
+            errors.push(format!(
+                "initial_price must be greater than 0 (found {})",
+                self.initial_price
+            ));
+        }
+        if self.epoch_in_days == 0 {
+            errors.push(String::from("epoch_in_days must be at least 1 (found 0)"));
+        }
+        if self.epoch_anchor_date.is_some() && self.compounding != Compounding::Epoch {
+            errors.push(String::from(
+                "epoch_anchor_date only moves epoch pay days remove it or use compounding epoch",
+            ));
+        }
+        if self.epoch_anchor_number.is_some() && self.epoch_anchor_date.is_none() {
+            errors.push(String::from(
+                "epoch_anchor_number needs epoch_anchor_date the date that epoch started",
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.annual_yield) {
+            errors.push(format!(
+                "annual_yield must be between 0 and 1 it is a fraction so 5% is 0.05 (found {})",
+                self.annual_yield
+            ));
+        }
+        let growth_fields = [
+            self.price_yield != 0.0,
+            self.daily_price_growth_pct.is_some(),
+            self.annual_price_growth_pct.is_some(),
+        ];
+        match growth_fields.iter().filter(|set| **set).count() {
+            0 if !self.daily_prices.is_empty() => {}
+            0 => errors.push(String::from(
+                "annual_price_growth_pct is missing set it (20 is +20% a year) or daily_price_growth_pct (0.1 is +0.1% a day)",
+            )),
+            1 => {}
+            _ => errors.push(String::from(
+                "annual_price_growth_pct, daily_price_growth_pct and price_yield set the same thing only use one of them",
+            )),
+        }
+        // A multiplier under 0.5 halves the price every day it is almost always a percentage typed into price_yield
+        if self.price_yield != 0.0 && self.price_yield < 0.5 {
+            errors.push(format!(
+                "price_yield {} is a daily multiplier (1.0 keeps the price flat) so the price would fall over 50% every day for a percentage use daily_price_growth_pct or annual_price_growth_pct instead",
+                self.price_yield
+            ));
+        }
+        if self.price_yield >= 2.0 {
+            errors.push(format!(
+                "price_yield must be between 0.5 and 2 it is a daily multiplier so 1.0005 is roughly +20% a year (found {})",
+                self.price_yield
+            ));
+        }
+        if let Some(pct) = self.daily_price_growth_pct {
+            if pct <= -50.0 || pct >= 100.0 {
+                errors.push(format!(
+                    "daily_price_growth_pct must be between -50 and 100 it is a percentage so 0.1 is +0.1% a day (found {})",
+                    pct
+                ));
+            }
+        }
+        if let Some(pct) = self.annual_price_growth_pct {
+            if pct <= -100.0 {
+                errors.push(format!(
+                    "annual_price_growth_pct must be greater than -100 it is a percentage so 20 is +20% a year (found {})",
+                    pct
+                ));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.saturation_factor) {
+            errors.push(format!(
+                "saturation_factor must be between 0 and 1 (found {})",
+                self.saturation_factor
+            ));
+        }
+        for (i, step) in self.annual_yield_schedule.iter().enumerate() {
+            if !(0.0..=1.0).contains(&step.annual_yield) {
+                errors.push(format!(
+                    "annual_yield_schedule step {} yield must be between 0 and 1 it is a fraction so 5% is 0.05 (found {})",
+                    i + 1,
+                    step.annual_yield
+                ));
+            }
+            let previous = if i == 0 { 0.0 } else { self.annual_yield_schedule[i - 1].after_years };
+            if step.after_years < previous || (i > 0 && step.after_years == previous) {
+                errors.push(format!(
+                    "annual_yield_schedule step {} after_years must be at least 0 and after the step before it (found {})",
+                    i + 1,
+                    step.after_years
+                ));
+            }
+        }
+        if !(0.0..1.0).contains(&self.annual_yield_decay) {
+            errors.push(format!(
+                "annual_yield_decay must be at least 0 and below 1 it is the fraction lost each year (found {})",
+                self.annual_yield_decay
+            ));
+        }
+        for (year, factor) in self.saturation_factors.iter().enumerate() {
+            if !(0.0..=1.0).contains(factor) {
+                errors.push(format!(
+                    "saturation_factors for year {} must be between 0 and 1 (found {})",
+                    year + 1,
+                    factor
+                ));
+            }
+        }
+        if let Some(contribution) = &self.contribution {
+            if contribution.amount_usd < 0.0 || contribution.amount_ada < 0.0 {
+                errors.push(format!(
+                    "contribution amount_usd and amount_ada must not be negative (found {} and {})",
+                    contribution.amount_usd, contribution.amount_ada
+                ));
+            } else if contribution.amount_usd == 0.0 && contribution.amount_ada == 0.0 {
+                errors.push(String::from(
+                    "contribution needs amount_usd or amount_ada greater than 0 (found neither)",
+                ));
+            }
+            if contribution.cadence_days == 0 {
+                errors.push(String::from("contribution cadence_days must be at least 1 (found 0)"));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.reward_tax_rate) {
+            errors.push(format!(
+                "reward_tax_rate must be between 0 and 1 (found {})",
+                self.reward_tax_rate
+            ));
+        }
+        if self.price_volatility < 0.0 {
+            errors.push(format!(
+                "price_volatility must not be negative (found {})",
+                self.price_volatility
+            ));
+        }
+        if self.annual_inflation <= -1.0 {
+            errors.push(format!(
+                "annual_inflation must be greater than -1 (found {})",
+                self.annual_inflation
+            ));
+        }
+        if self.compounding != Compounding::Epoch && (self.reserve_ada.is_some() || self.pool_epoch_reward.is_some()) {
+            errors.push(String::from(
+                "compounding must be epoch with reserve_ada or pool_epoch_reward since those rewards are paid per epoch",
+            ));
+        }
+        if self.withdrawal_usd_per_interval < 0.0 {
+            errors.push(format!(
+                "withdrawal_usd_per_interval must not be negative (found {})",
+                self.withdrawal_usd_per_interval
+            ));
+        } else if self.withdrawal_usd_per_interval > 0.0 && self.withdrawal_interval_days == 0 {
+            errors.push(String::from(
+                "withdrawal_interval_days must be at least 1 when withdrawal_usd_per_interval is set (found 0)",
+            ));
+        }
+        match self.saturation_limit_ada {
+            Some(limit) if limit <= 0.0 => errors.push(format!(
+                "saturation_limit_ada must be greater than 0 (found {})",
+                limit
+            )),
+            Some(_) if self.pool_epoch_reward.is_some() => errors.push(String::from(
+                "saturation_limit_ada is not used with pool_epoch_reward which is already what the pool paid",
+            )),
+            _ => {}
+        }
+        if self.years_holding <= 0.0 {
+            errors.push(format!(
+                "years_holding must be greater than 0 (found {})",
+                self.years_holding
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.pool_margin) {
+            errors.push(format!(
+                "pool_margin must be between 0 and 1 (found {})",
+                self.pool_margin
+            ));
+        }
+        if self.pool_fixed_fee < 0.0 {
+            errors.push(format!(
+                "pool_fixed_fee must not be negative (found {})",
+                self.pool_fixed_fee
+            ));
+        }
+        if let Some((day, price)) = self.daily_prices.iter().enumerate().find(|(_, price)| **price <= 0.0) {
+            errors.push(format!("daily_prices for day {} must be greater than 0 (found {})", day, price));
+        }
+        for (year, multiplier) in self.price_yields.iter().enumerate() {
+            if *multiplier <= 0.0 {
+                errors.push(format!(
+                    "price_yields for year {} must be greater than 0 (found {})",
+                    year + 1,
+                    multiplier
+                ));
+            }
+        }
+        for (i, event) in self.price_events.iter().enumerate() {
+            let day = self.price_event_day(event);
+            match event.kind {
+                PriceEventKind::Shock if event.factor <= 0.0 || event.factor.is_nan() => errors.push(format!(
+                    "price_events entry {} shock factor must be greater than 0 (found {})",
+                    i + 1,
+                    event.factor
+                )),
+                PriceEventKind::Floor | PriceEventKind::Cap if event.value <= 0.0 || event.value.is_nan() => errors.push(format!(
+                    "price_events entry {} {} value must be greater than 0 (found {})",
+                    i + 1,
+                    if event.kind == PriceEventKind::Floor { "floor" } else { "cap" },
+                    event.value
+                )),
+                _ => {}
+            }
+            match day {
+                None if event.kind == PriceEventKind::Shock => errors.push(format!(
+                    "price_events entry {} shock needs a day or a date",
+                    i + 1
+                )),
+                Some(day) if day < 0 => errors.push(format!(
+                    "price_events entry {} date {} is before the start {}",
+                    i + 1,
+                    event.date.map(|date| date.to_string()).unwrap_or_default(),
+                    self.start()
+                )),
+                Some(0) if event.kind == PriceEventKind::Shock => errors.push(format!(
+                    "price_events entry {} shock is on day 0 the first simulated day is 1 (set initial_price for day 0)",
+                    i + 1
+                )),
+                _ => {}
+            }
+        }
+        for (day, fraction) in self.slash_events.iter() {
+            if !(0.0..=1.0).contains(fraction) {
+                errors.push(format!(
+                    "slash_events fraction on day {} must be between 0 and 1 (found {})",
+                    day, fraction
+                ));
+            }
+        }
+        match (self.pool_epoch_reward, self.pool_active_stake) {
+            (Some(_), None) => errors.push(String::from(
+                "pool_epoch_reward needs pool_active_stake to split the pool reward",
+            )),
+            (_, Some(stake)) if stake < self.ada => errors.push(format!(
+                "pool_active_stake ({}) must include your ada ({})",
+                stake, self.ada
+            )),
+            (Some(_), Some(_)) if self.reserve_ada.is_some() => errors.push(String::from(
+                "reserve_ada and pool_epoch_reward are different reward models set only one",
+            )),
+            _ => {}
+        }
+        if self.reserve_ada.is_some() && self.total_stake_ada <= 0.0 {
+            errors.push(format!(
+                "total_stake_ada must be greater than 0 when reserve_ada is set (found {})",
+                self.total_stake_ada
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    /// ADA received for a USD buy and the USD lost to slippage large buys fill at a worse price than the current one
+    pub fn buy_ada(&self, usd: f64, price: f64) -> (f64, f64) {
+        let fill_price = price * (1.0 + self.slippage_per_usd * usd);
+        let bought = usd / fill_price;
+        (bought, usd - bought * price)
+    }
+
+    /// Legal but suspicious inputs only reported by --sanity-report
+    pub fn sanity_warnings(&self) -> Vec<String> {
+        let mut warnings: Vec<String> = Vec::new();
+        if self.annual_yield > 0.2 {
+            warnings.push(format!(
+                "annual_yield {} is above 20% it is a fraction so 5% is 0.05",
+                self.annual_yield
+            ));
+        }
+        let price_yield = self.daily_price_yield();
+        if price_yield > 1.01 {
+            warnings.push(format!(
+                "price growth {:.4}% a day is over {:.0}% per year",
+                (price_yield - 1.0) * 100.0,
+                (price_yield.powf(365.25) - 1.0) * 100.0
+            ));
+        }
+        if price_yield < 1.0 {
+            warnings.push(format!(
+                "price growth {:.4}% a day is below 0 so the price declines every day",
+                (price_yield - 1.0) * 100.0
+            ));
+        }
+        if self.years_holding > 100.0 {
+            warnings.push(format!(
+                "years_holding {} is longer than a lifetime",
+                self.years_holding
+            ));
+        }
+        warnings.extend(self.price_event_warnings());
+        if self.pool_margin > 0.1 {
+            warnings.push(format!(
+                "pool_margin {} is unusually high (most pools charge under 5%)",
+                self.pool_margin
+            ));
+        }
+        warnings
+    }
+
+    /// Gross epoch reward for a stake out of the reserve emission before pool fees the reserve itself shrinks by reserve * rho every epoch
+    pub fn reserve_epoch_reward(&self, reserve: f64, stake: f64) -> f64 {
+        reserve * self.rho * (1.0 - self.tau) * (stake / self.total_stake_ada)
+    }
+
+    /// Gross pool reward and your share of the pool when pool stats are set the other delegators stake stays fixed while yours grows
+    pub fn pool_share(&self, stake: f64) -> Option<(f64, f64)> {
+        Some((self.pool_epoch_reward?, self.stake_share(stake)?))
+    }
+
+    /// Your fraction of the pool when pool_active_stake is set
+    pub fn stake_share(&self, stake: f64) -> Option<f64> {
+        let others = self.pool_active_stake? - self.ada;
+        Some(stake / (others + stake))
+    }
+
+    /// Yearly reward as a fraction of the stake for a given reserve before fees used for reporting only
+    pub fn reserve_annual_yield(&self, reserve: f64) -> f64 {
+        self.reserve_epoch_reward(reserve, 1.0) * self.epochs_per_year()
+    }
+
+    /// Operator cut of a gross epoch reward the fixed fee comes off the top then the margin applies to what is left
+    /// Never more than the gross reward so a tiny reward nets to zero instead of going negative
+    pub fn epoch_fees(&self, gross_reward: f64) -> f64 {
+        self.credit_fees(gross_reward, self.pool_fixed_fee)
+    }
+
+    /// Same as epoch_fees with the fixed fee given since daily and monthly credits take a share of the per epoch fee
+    pub fn credit_fees(&self, gross_reward: f64, fixed_fee: f64) -> f64 {
+        let fixed = fixed_fee.min(gross_reward);
+        (fixed + (gross_reward - fixed) * self.pool_margin).min(gross_reward)
+    }
+
+    // Reward of one credit from annual_yield alone before fees the default is a simple slice of the annual yield which compounded
+    // over a year slightly overshoots annual_yield compound_exact uses the rate that compounds to exactly annual_yield instead
+    fn modelled_reward(&self, reward_base: f64, annual_yield: f64, saturation: f64, compound_exact: bool) -> f64 {
+        if compound_exact {
+            reward_base * ((1.0 + annual_yield).powf(1.0 / self.credits_per_year()) - 1.0) * saturation
+        } else {
+            reward_base * annual_yield / self.credits_per_year() * saturation
+        }
+    }
+
+    // With pool stats the operator takes the fixed fee and margin from the whole pool reward before it is split
+    // With only pool_active_stake the pool reward is scaled up from yours so the fixed fee is shared by every delegator
+    fn reward_fees(&self, reward_base: f64, epoch_reward: f64, fixed_fee: f64) -> f64 {
+        match (self.pool_share(reward_base), self.stake_share(reward_base)) {
+            (Some((pool_reward, share)), _) => self.epoch_fees(pool_reward) * share,
+            (None, Some(share)) if share > 0.0 => self.credit_fees(epoch_reward / share, fixed_fee) * share,
+            _ => self.credit_fees(epoch_reward, fixed_fee),
+        }
+    }
+}
+
+/// Figures from walking the recorded total (ADA times price) day by day
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct HistoryStatistics {
+    /// Largest fall from a running peak as a fraction 0 for a total that never falls
+    pub max_drawdown: f64,
+    /// Day and fractional change of the biggest day over day rise
+    pub best_day: (u64, f64),
+    /// Day and fractional change of the biggest day over day fall (or smallest rise)
+    pub worst_day: (u64, f64),
+    /// Day and total of the highest recorded total
+    pub peak: (u64, f64),
+}
+
+/// Everything a run produced serializable so a library caller can hand it to serde_json or the like as is
+#[derive(Default, Serialize)]
+pub struct StakedCardanoPoolResult {
+    pub final_ada_amount: f64,
+    pub final_ada_price: f64,
+    pub amount_historical: Vec<f64>,
+    pub price_historical: Vec<f64>,
+    /// ADA owned on each history day had it been held without staking (the ADA put in rewards excluded)
+    pub held_historical: Vec<f64>,
+    /// USD of the rewards taken out so far on each history day only when compound is false
+    pub harvested_historical: Vec<f64>,
+    /// Note: Used by the generate graph option not used otherwise
+    pub days_as_float: f64,
+    /// Days spent inside one of the undelegated ranges
+    pub undelegated_days: u64,
+    /// ADA rewards that would have been paid out had the ADA stayed delegated
+    pub forgone_rewards: f64,
+    /// First day the price reached max_price if it ever did
+    pub price_cap_day: Option<u64>,
+    /// First day the pool stake passed saturation_limit_ada if it ever did
+    pub saturated_day: Option<u64>,
+    /// ADA earned before pool fees
+    pub gross_rewards: f64,
+    /// USD value of the gross rewards at the price on each pay day
+    pub gross_rewards_usd: f64,
+    /// ADA paid to the pool operator (fixed fee + margin)
+    pub fees_paid: f64,
+    /// USD value of the fees at the price on each pay day
+    pub fees_paid_usd: f64,
+    /// USD spent on lump sum and contribution buys on top of the initial investment (part of the cost basis)
+    pub contributed_usd: f64,
+    /// ADA bought with the lump sums and contributions
+    pub contributed_ada: f64,
+    /// Part of contributed_usd spent on the periodic contribution
+    pub periodic_usd: f64,
+    /// Rewards received valued at the price on the day each one landed (taxable income)
+    pub reward_income_usd: f64,
+    /// ADA bought with the periodic contribution
+    pub periodic_ada: f64,
+    /// USD lost to price impact on buys
+    pub slippage_usd: f64,
+    /// Day, ADA and price when reporting started after --skip-epochs
+    pub baseline: Option<(u64, f64, f64)>,
+    /// Indices into amount_historical where a payout first shows up used for the graph dots
+    pub payout_indices: Vec<usize>,
+    /// Day, ADA and price of every reward that landed when record_payouts is set contributions and withdrawals are not in it
+    pub reward_payouts: Vec<(u64, f64, f64)>,
+    /// The CSV or the workbook could not be written
+    pub write_failed: bool,
+    /// USD from the part of the rewards not kept as ADA (reward_ada_fraction)
+    pub usd_balance: f64,
+    /// Rewards withdrawn instead of restaked when compound is false
+    pub withdrawn_ada: f64,
+    /// Same rewards valued at the price on the day each one was withdrawn
+    pub withdrawn_usd: f64,
+    /// ADA rewards that landed on the reported days the last Cumulative Rewards of the CSV
+    pub credited_rewards: f64,
+    /// ADA sold by the periodic withdrawals (spent so not part of the total)
+    pub periodic_withdrawn_ada: f64,
+    /// USD those withdrawals paid out
+    pub periodic_withdrawn_usd: f64,
+    /// Day the periodic withdrawals used up the ADA the history stops there
+    pub depleted_day: Option<u64>,
+    /// Day the SimulationOptions target was reached the run stops there
+    pub target_day: Option<u64>,
+    /// Starting ADA times the starting price
+    pub initial_investment: f64,
+    /// Rewards earned but not yet restaked at the end (included in final_ada_amount)
+    pub pending_rewards: f64,
+    /// ADA left in the reserve at the end when reserve_ada is set
+    pub final_reserve: Option<f64>,
+    /// ADA lost to slash_events
+    pub slashed_ada: f64,
+    /// ADA spent on withdrawal and restake transactions
+    pub tx_fees_ada: f64,
+    /// Same fees valued at the price on the day they were paid
+    pub tx_fees_usd: f64,
+    /// Withdrawal and restake transactions made
+    pub transactions: u64,
+    /// Rewards earned but still waiting out reward_lag_epochs at the end (not in final_ada_amount)
+    pub in_flight_rewards: f64,
+    /// annual_yield in effect on the last day simulated
+    pub final_annual_yield: f64,
+    /// A row per year of the reported days
+    pub yearly: Vec<YearSummary>,
+    /// Largest fall of the total from a peak as a fraction with the peak and trough days
+    pub max_drawdown: (f64, u64, u64),
+    /// First reported day the total was above the money put in so far None if it never was
+    pub break_even_day: Option<u64>,
+    /// History index where each lump sum, slash, delegation change or price cap first shows up and its label
+    pub events: Vec<(usize, String)>,
+}
+
+impl StakedCardanoPoolResult {
+    /// A result with only the final ADA, price and history filled in everything else starts at zero
+    pub fn new(
+        final_ada_amount: f64,
+        final_ada_price: f64,
+        amount_historical: Vec<f64>,
+        price_historical: Vec<f64>,
+        days: u64,
+    ) -> Self {
+        StakedCardanoPoolResult {
+            final_ada_amount,
+            final_ada_price,
+            amount_historical,
+            price_historical,
+            days_as_float: days as f64,
+            ..Default::default()
+        }
+    }
+
+    /// Final ADA times the final price plus the USD taken out of the rewards
+    pub fn total(&self) -> f64 {
+        self.final_ada_amount * self.final_ada_price + self.usd_balance + self.withdrawn_usd
+    }
+
+    /// ADA owned at the end had it been held without staking the starting ADA and every ADA put in since
+    pub fn held_ada(&self, pool_info: &StakedCardanoPool) -> f64 {
+        pool_info.ada + self.contributed_ada
+    }
+
+    /// Final value of held_ada the price moves the same whether or not it is staked
+    pub fn hold_total(&self, pool_info: &StakedCardanoPool) -> f64 {
+        self.held_ada(pool_info) * self.final_ada_price
+    }
+
+    /// Extra final value staking made as a percentage of the hold total
+    pub fn staking_advantage_as_percentage(&self, pool_info: &StakedCardanoPool) -> f64 {
+        let hold_total = self.hold_total(pool_info);
+        if hold_total > 0.0 {
+            (self.total() / hold_total - 1.0) * 100.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Value of the ADA still staked at the end without the withdrawn rewards
+    pub fn staked_total(&self) -> f64 {
+        self.final_ada_amount * self.final_ada_price + self.usd_balance
+    }
+
+    /// Final total as a multiple of the money put in 2.0 when it doubled 0 when nothing was put in so it never divides by zero
+    pub fn value_multiple(&self, pool_info: &StakedCardanoPool) -> f64 {
+        let put_in = pool_info.initial_price * pool_info.ada + self.contributed_usd;
+        if put_in > 0.0 {
+            self.total() / put_in
+        } else {
+            0.0
+        }
+    }
+
+    /// Final total as a percentage of the money put in 200 when it doubled net_return_as_percentage is the gain
+    pub fn yield_as_percentage(&self, pool_info: &StakedCardanoPool) -> f64 {
+        self.value_multiple(pool_info) * 100.0
+    }
+
+    /// Last day simulated the run stops early when the funds run out or the target is reached
+    pub fn last_day(&self, pool_info: &StakedCardanoPool) -> u64 {
+        self.depleted_day.or(self.target_day).unwrap_or_else(|| pool_info.simulated_days())
+    }
+
+    /// Years the run lasted on the pool's day count shorter than years_holding when it stopped early
+    pub fn years_held(&self, pool_info: &StakedCardanoPool) -> f64 {
+        pool_info.years_on(self.last_day(pool_info))
+    }
+
+    /// Total in today's dollars discounted over the days simulated the same way as the CSV Real Total column
+    pub fn real_total(&self, pool_info: &StakedCardanoPool) -> f64 {
+        self.total() / pool_info.inflation_factor(self.last_day(pool_info))
+    }
+
+    /// Same as net_return_as_percentage but measured with the real total
+    pub fn real_return_as_percentage(&self, pool_info: &StakedCardanoPool) -> f64 {
+        (self.real_total(pool_info) / (pool_info.initial_price * pool_info.ada + self.contributed_usd) - 1.0) * 100.0
+    }
+
+    /// Same as cagr but measured with the real total so inflation is taken out of the growth rate
+    pub fn real_cagr(&self, pool_info: &StakedCardanoPool) -> f64 {
+        let put_in = pool_info.initial_price * pool_info.ada + self.contributed_usd;
+        let years = self.years_held(pool_info);
+        if years <= 0.0 || put_in <= 0.0 || self.real_total(pool_info) <= 0.0 {
+            return 0.0;
+        }
+        (self.real_total(pool_info) / put_in).powf(1.0 / years) - 1.0
+    }
+
+    /// Income tax on the rewards received at reward_tax_rate
+    pub fn tax_owed(&self, pool_info: &StakedCardanoPool) -> f64 {
+        self.reward_income_usd * pool_info.reward_tax_rate
+    }
+
+    /// Total once the tax owed is paid
+    pub fn after_tax_total(&self, pool_info: &StakedCardanoPool) -> f64 {
+        self.total() - self.tax_owed(pool_info)
+    }
+
+    /// Profit or loss as a percentage of the money put in negative when the run ends below cost
+    pub fn net_return_as_percentage(&self, pool_info: &StakedCardanoPool) -> f64 {
+        self.yield_as_percentage(pool_info) - 100.0
+    }
+
+    /// Growth of the ADA count alone as a percentage measured against the ADA put in so lump sums and contributions
+    /// bought at the day's price are not counted as growth
+    pub fn ada_growth_as_percentage(&self, pool_info: &StakedCardanoPool) -> f64 {
+        let put_in = self.held_ada(pool_info);
+        if put_in <= 0.0 {
+            return 0.0;
+        }
+        (self.final_ada_amount / put_in - 1.0) * 100.0
+    }
+
+    /// Yearly compounded growth rate of the total against the money put in as a fraction over the years the run lasted
+    pub fn cagr(&self, pool_info: &StakedCardanoPool) -> f64 {
+        let years = self.years_held(pool_info);
+        if years <= 0.0 || self.value_multiple(pool_info) <= 0.0 {
+            return 0.0;
+        }
+        self.value_multiple(pool_info).powf(1.0 / years) - 1.0
+    }
+
+    /// Yearly compounded growth rate of the ADA count against the ADA put in as a fraction
+    pub fn ada_cagr(&self, pool_info: &StakedCardanoPool) -> f64 {
+        let (put_in, years) = (self.held_ada(pool_info), self.years_held(pool_info));
+        if years <= 0.0 || put_in <= 0.0 {
+            return 0.0;
+        }
+        (self.final_ada_amount / put_in).powf(1.0 / years) - 1.0
+    }
+
+    /// None unless at least two days of history were recorded days count from the baseline after --skip-epochs
+    pub fn history_statistics(&self) -> Option<HistoryStatistics> {
+        let totals: Vec<f64> = self
+            .amount_historical
+            .iter()
+            .zip(self.price_historical.iter())
+            .map(|(ada, price)| ada * price)
+            .collect();
+        if totals.len() < 2 {
+            return None;
+        }
+        let first_day = self.baseline.map(|(day, ..)| day).unwrap_or(0);
+        let mut stats = HistoryStatistics {
+            max_drawdown: 0.0,
+            best_day: (first_day + 1, f64::MIN),
+            worst_day: (first_day + 1, f64::MAX),
+            peak: (first_day, totals[0]),
+        };
+        for (i, pair) in totals.windows(2).enumerate() {
+            let day = first_day + i as u64 + 1;
+            if pair[0] > 0.0 {
+                let change = pair[1] / pair[0] - 1.0;
+                if change > stats.best_day.1 {
+                    stats.best_day = (day, change);
+                }
+                if change < stats.worst_day.1 {
+                    stats.worst_day = (day, change);
+                }
+            }
+            if pair[1] > stats.peak.1 {
+                stats.peak = (day, pair[1]);
+            } else if stats.peak.1 > 0.0 {
+                stats.max_drawdown = stats.max_drawdown.max(1.0 - pair[1] / stats.peak.1);
+            }
+        }
+        if stats.best_day.1 > stats.worst_day.1 {
+            return Some(stats);
+        }
+        // Only when the total sat at zero the whole time so there was never a change to measure
+        stats.best_day.1 = 0.0;
+        stats.worst_day.1 = 0.0;
+        Some(stats)
+    }
+
+}
+
+/// Fast path of the daily loop for the plain scenario the price compounds in one step and only the pay days are stepped for the ADA
+pub fn calculate_closed_form(pool: &StakedCardanoPool, options: &SimulationOptions) -> Result<StakedCardanoPoolResult, String> {
+    // Anything that moves the ADA or the price off the plain compounding needs the daily loop
+    let unsupported: Vec<&str> = [
+        (!pool.undelegated_ranges.is_empty(), "undelegated_ranges"),
+        (pool.max_price.is_some(), "max_price"),
+        (pool.pool_fixed_fee != 0.0, "pool_fixed_fee"),
+        (!pool.lump_sums.is_empty(), "lump_sums"),
+        (pool.contribution.is_some(), "contribution"),
+        (pool.reward_ada_fraction != 1.0, "reward_ada_fraction"),
+        (pool.restake_every_n_epochs > 1, "restake_every_n_epochs"),
+        (pool.reserve_ada.is_some(), "reserve_ada"),
+        (!pool.slash_events.is_empty(), "slash_events"),
+        (!pool.price_events.is_empty(), "price_events"),
+        (pool.pool_epoch_reward.is_some(), "pool_epoch_reward"),
+        (!pool.price_yields.is_empty(), "price_yields"),
+        (!pool.daily_prices.is_empty(), "daily_prices"),
+        (!pool.saturation_factors.is_empty(), "saturation_factors"),
+        (pool.has_yield_schedule(), "annual_yield_schedule or annual_yield_decay"),
+        (!pool.compound, "compound false"),
+        (pool.compounding != Compounding::Epoch, "compounding other than epoch"),
+        (pool.withdrawal_usd_per_interval > 0.0, "withdrawals"),
+        (pool.saturation_limit_ada.is_some(), "saturation_limit_ada"),
+        (pool.epoch_anchor_date.is_some(), "epoch_anchor_date"),
+    ]
+    .into_iter()
+    .filter_map(|(used, field)| used.then_some(field))
+    .collect();
+    if !unsupported.is_empty() {
+        return Err(format!("--summary-only does not support {} (run without it)", unsupported.join(", ")));
+    }
+    let days = pool.simulated_days() + 1;
+    let simulated_days = days.saturating_sub(1); // The loop runs over 1..days
+    // Nothing but the pay days moves the ADA so only those are stepped each payout floored to whole lovelace like the loop
+    let mut staked = lovelace(pool.ada, options.rounding);
+    let mut in_flight: VecDeque<u128> = VecDeque::new();
+    for day in (pool.epoch_in_days..=simulated_days).step_by(pool.epoch_in_days.max(1) as usize) {
+        let reward_base = to_ada(staked);
+        let saturation = pool.saturation_on(day) * pool.stake_saturation(reward_base);
+        let epoch_reward = pool.modelled_reward(reward_base, pool.annual_yield_on(day), saturation, options.compound_exact)
+            * pool.epoch_share(day);
+        in_flight.push_back(floor_lovelace(epoch_reward - pool.reward_fees(reward_base, epoch_reward, pool.pool_fixed_fee)));
+        if in_flight.len() as u64 > pool.reward_lag_epochs {
+            staked += in_flight.pop_front().unwrap_or_default();
+        }
+    }
+    let final_price = pool.initial_price * pool.daily_price_yield().powf(simulated_days as f64);
+    Ok(StakedCardanoPoolResult::new(
+        to_ada(staked),
+        final_price,
+        Vec::new(),
+        Vec::new(),
+        days,
+    ))
+}
+
+/// The simulation with no output at all same inputs always give the same result
+///
+/// ```
+/// use ada_calc::{calculate_staked_pool, SimulationOptions, StakedCardanoPool};
+///
+/// let pool: StakedCardanoPool = serde_json::from_str(
+///     r#"{"ada": 1000, "initial_price": 0.5, "daily_price_growth_pct": 0.1, "annual_yield": 0.05,
+///         "epoch_in_days": 5, "years_holding": 1, "start_date": "2024-01-01", "reward_lag_epochs": 0}"#,
+/// )
+/// .unwrap();
+/// let result = calculate_staked_pool(&pool, &SimulationOptions::default());
+/// assert_eq!(format!("{:.6}", result.final_ada_amount), "1051.217114");
+/// assert_eq!(format!("{:.2}", result.total()), "757.77");
+/// ```
+pub fn calculate_staked_pool(pool: &StakedCardanoPool, options: &SimulationOptions) -> StakedCardanoPoolResult {
+    calculate_staked_pool_with(pool, options, |_| {})
+}
+
+/// Steps through every day calling on_day after each reported day so a caller can print or write rows as it goes
+pub fn calculate_staked_pool_with<F: FnMut(&DayRecord)>(
+    pool: &StakedCardanoPool,
+    options: &SimulationOptions,
+    on_day: F,
+) -> StakedCardanoPoolResult {
+    simulate(pool, options, on_day, || 1.0)
+}
+
+/// One random run for Monte Carlo a geometric Brownian motion step every daily price multiplier is scaled by a lognormal shock
+/// with a mean of 1 so price_yield stays the drift and price_volatility is the daily volatility
+pub fn calculate_staked_pool_sampled<R: Rng>(
+    pool: &StakedCardanoPool,
+    options: &SimulationOptions,
+    rng: &mut R,
+) -> Result<StakedCardanoPoolResult, String> {
+    let sigma = pool.price_volatility;
+    let shock = LogNormal::new(-sigma * sigma / 2.0, sigma).map_err(|e| format!("Invalid price_volatility: {}", e))?;
+    Ok(simulate(pool, options, |_| {}, || shock.sample(rng)))
+}
+
+// Each daily price multiplier is scaled by price_shock always 1.0 outside Monte Carlo so those runs are exact
+fn simulate<F: FnMut(&DayRecord), S: FnMut() -> f64>(
+    pool: &StakedCardanoPool,
+    options: &SimulationOptions,
+    mut on_day: F,
+    mut price_shock: S,
+) -> StakedCardanoPoolResult {
+    let mut staked = lovelace(pool.ada, options.rounding); // Staked balance in whole lovelace
+    let mut price = pool.initial_price;
+    let days = pool.simulated_days() + 1;
+    let start_date = pool.start();
+    let epoch_phase = pool.epoch_phase();
+    // The per epoch fixed fee is spread over the credits so daily and monthly compounding pay the same fee a year
+    let fixed_fee = match pool.compounding {
+        Compounding::Epoch => pool.pool_fixed_fee,
+        _ => pool.pool_fixed_fee * pool.epochs_per_year() / pool.credits_per_year(),
+    };
+    let mut principal = pool.ada; // ADA bought with money put in rewards excluded
+
+    let mut adas: Vec<f64> = Vec::new();
+    let mut prices: Vec<f64> = Vec::new();
+    let mut helds: Vec<f64> = Vec::new();
+    let mut harvesteds: Vec<f64> = Vec::new();
+    let mut undelegated_days: u64 = 0;
+    let mut forgone_rewards = 0.0;
+    let mut price_cap_day: Option<u64> = None;
+    let mut yearly: Vec<YearSummary> = Vec::new();
+    let mut year: u64 = 1;
+    let mut year_end = calendar_days(start_date, 1).unwrap_or(u64::MAX); // Last day of year on the calendar
+    let mut year_rewards = (0.0, 0.0);
+    let mut final_annual_yield = pool.annual_yield_on(1);
+    let mut saturated_day: Option<u64> = None;
+    let mut gross_rewards = 0.0;
+    let mut gross_rewards_usd = 0.0;
+    let mut fees_paid = 0.0;
+    let mut fees_paid_usd = 0.0;
+    let mut contributed_usd = 0.0;
+    let mut contributed_ada = 0.0; // Kept apart from principal which withdrawals and slashing take down
+    let mut periodic_usd = 0.0;
+    let mut reward_income_usd = 0.0;
+    let mut reward_payouts: Vec<(u64, f64, f64)> = Vec::new();
+    let mut periodic_ada = 0.0;
+    let mut slippage_usd = 0.0;
+    let mut usd_balance = 0.0;
+    let mut withdrawn_ada = 0.0;
+    let mut credited_rewards = 0.0; // Summed in the same order as the CSV Cumulative Rewards so the two match to the bit
+    let mut withdrawn_usd = 0.0;
+    let mut periodic_withdrawn_ada = 0.0;
+    let mut periodic_withdrawn_usd = 0.0;
+    let mut depleted_day: Option<u64> = None;
+    let mut target_day: Option<u64> = None;
+    let mut pending_rewards: u128 = 0; // Lovelace credited but not restaked yet with restake_every_n_epochs
+    let mut paid_epochs: u64 = 0;
+    let mut reserve = pool.reserve_ada;
+    let mut slashed_ada = 0.0;
+    let mut tx_fees_ada = 0.0;
+    let mut events: Vec<(usize, String)> = Vec::new();
+    let mut price_events_marked: Vec<usize> = Vec::new(); // Floors and caps fire every day they hold the price only the first is an event
+    let mut in_flight: VecDeque<u128> = VecDeque::new();
+    let mut tx_fees_usd = 0.0;
+    let mut transactions: u64 = 0;
+
+    let skip_days = options.skip_epochs * pool.epoch_in_days;
+    // Starts at the initial investment so a price that only falls still has a peak to measure from (reset when reporting starts after skipped epochs)
+    let mut peak = (0, pool.ada * pool.initial_price);
+    let mut max_drawdown: (f64, u64, u64) = (0.0, 0, 0);
+    let mut break_even_day: Option<u64> = None;
+    let mut baseline: Option<(u64, f64, f64)> = None;
+    let mut payout_indices: Vec<usize> = Vec::new();
+
+    for day in 1..days {
+        // Compounding continues through the skipped epochs but nothing is reported or recorded until they are over
+        let reporting = day > skip_days;
+        if reporting && baseline.is_none() && skip_days > 0 {
+            baseline = Some((day - 1, to_ada(staked), price));
+            peak = (day - 1, to_ada(staked + pending_rewards) * price + usd_balance);
+        }
+
+        if reporting && options.record_history {
+            adas.push(to_ada(staked));
+            prices.push(price);
+            helds.push(principal);
+            if !pool.compound {
+                harvesteds.push(withdrawn_usd);
+            }
+        }
+        let (start_ada, start_price, start_contributed_usd) = (to_ada(staked), price, contributed_usd);
+        let start_reserve = reserve;
+        let start_in_flight = to_ada(in_flight.iter().sum());
+        let start_contributed_ada = contributed_ada;
+        let start_withdrawn_ada = periodic_withdrawn_ada;
+        let (start_harvested_ada, start_harvested_usd) = (withdrawn_ada, withdrawn_usd);
+
+        // Events land on the next history entry the same way payouts do
+        let record_events = reporting && options.record_history;
+
+        let undelegated = pool.is_undelegated(day);
+        if undelegated {
+            undelegated_days += 1;
+        }
+        if record_events && undelegated != pool.is_undelegated(day - 1) {
+            let label = if undelegated { "Undelegated" } else { "Redelegated" };
+            events.push((adas.len(), format!("{} (Day {})", label, day)));
+        }
+
+        for (_, usd) in pool.lump_sums.iter().filter(|(lump_day, _)| *lump_day == day) {
+            let (bought, slippage) = pool.buy_ada(*usd, price);
+            let bought = floor_lovelace(bought);
+            slippage_usd += slippage;
+            staked += bought;
+            principal += to_ada(bought);
+            contributed_ada += to_ada(bought);
+            contributed_usd += usd;
+            if record_events {
+                events.push((adas.len(), format!("Lump Sum ${} (Day {})", usd, day)));
+            }
+        }
+        // Not marked on the graph a monthly buy over decades would bury the other events
+        let contribution = match pool.contribution {
+            Some(contribution) if day.is_multiple_of(contribution.cadence_days.max(1)) => {
+                let (bought, slippage) = if contribution.amount_usd > 0.0 {
+                    pool.buy_ada(contribution.amount_usd, price)
+                } else {
+                    (0.0, 0.0)
+                };
+                let added = floor_lovelace(bought) + lovelace(contribution.amount_ada, options.rounding);
+                let put_in = contribution.amount_usd + contribution.amount_ada * price;
+                slippage_usd += slippage;
+                staked += added;
+                principal += to_ada(added);
+                contributed_ada += to_ada(added);
+                contributed_usd += put_in;
+                periodic_usd += put_in;
+                periodic_ada += to_ada(added);
+                Some((put_in, to_ada(added)))
+            }
+            _ => None,
+        };
+        // Sold before the rewards so the reward is on what is left selling more than is staked empties it and ends the run
+        if pool.withdrawal_usd_per_interval > 0.0 && day.is_multiple_of(pool.withdrawal_interval_days.max(1)) && price > 0.0 {
+            let sold = floor_lovelace(pool.withdrawal_usd_per_interval / price).min(staked);
+            staked -= sold;
+            principal = principal.min(to_ada(staked));
+            periodic_withdrawn_ada += to_ada(sold);
+            periodic_withdrawn_usd += to_ada(sold) * price;
+            if staked == 0 {
+                depleted_day = Some(day);
+                if record_events {
+                    events.push((adas.len(), format!("Funds Exhausted (Day {})", day)));
+                }
+            }
+        }
+        for (_, fraction) in pool.slash_events.iter().filter(|(slash_day, _)| *slash_day == day) {
+            let slashed = ((staked as f64 * fraction).floor() as u128).min(staked);
+            slashed_ada += to_ada(slashed);
+            staked -= slashed;
+            principal -= principal * fraction;
+            if record_events {
+                events.push((adas.len(), format!("Slashed {}% (Day {})", fraction * 100.0, day)));
+            }
+        }
+        // With simple interest the rewards are kept but never staked so only the ADA bought earns
+        let reward_base = if options.simple_interest { principal } else { to_ada(staked) };
+        let annual_yield = pool.annual_yield_on(day);
+        final_annual_yield = annual_yield;
+
+        let pay_day = pool.is_credit_day_at(day, epoch_phase);
+        let pool_share = pool.pool_share(reward_base);
+        // Real pool stats already include any saturation penalty so the factor only scales the modelled rewards
+        let saturation = if pool_share.is_some() { 1.0 } else { pool.saturation_on(day) * pool.stake_saturation(reward_base) };
+        if let (Some(limit), None) = (pool.saturation_limit_ada, saturated_day) {
+            if pool.pool_stake(reward_base) > limit {
+                saturated_day = Some(day);
+                if reporting && options.record_history {
+                    let label = if pool.auto_redelegate { "Redelegated" } else { "Pool Saturated" };
+                    events.push((adas.len(), format!("{} (Day {})", label, day)));
+                }
+            }
+        }
+        let epoch_reward = if let Some((pool_reward, share)) = pool_share {
+            pool_reward * share
+        } else if let Some(reserve) = reserve {
+            pool.reserve_epoch_reward(reserve, reward_base) * saturation
+        } else {
+            pool.modelled_reward(reward_base, annual_yield, saturation, options.compound_exact)
+        };
+        // The first epoch after an anchored start is usually partial so its reward is prorated by the days staked in it
+        let epoch_reward = epoch_reward * pool.epoch_share(day);
+        let mut earned: u128 = 0;
+        let pay_day_status = if pay_day && undelegated {
+            forgone_rewards += epoch_reward; // Nothing is earned while undelegated
+            PayDay::Skipped
+        } else if pay_day {
+            let fees = pool.reward_fees(reward_base, epoch_reward, fixed_fee);
+            gross_rewards += epoch_reward;
+            gross_rewards_usd += epoch_reward * price;
+            fees_paid += fees;
+            fees_paid_usd += fees * price;
+            earned = floor_lovelace(epoch_reward - fees);
+            PayDay::Paid {
+                gross: epoch_reward,
+                fees,
+                net: to_ada(earned),
+                saturation,
+            }
+        } else {
+            PayDay::No
+        };
+        // Rewards earned this epoch are paid reward_lag_epochs pay days later undelegated epochs still pay out what was earned before
+        if pay_day {
+            in_flight.push_back(earned);
+        }
+        let credited = if pay_day && in_flight.len() as u64 > pool.reward_lag_epochs {
+            in_flight.pop_front().unwrap_or_default()
+        } else {
+            0
+        };
+        if credited > 0 {
+            reward_income_usd += to_ada(credited) * price; // Income is valued on the day it lands not when it was earned
+            if options.record_payouts {
+                reward_payouts.push((day, to_ada(credited), price));
+            }
+            if reporting && options.record_history {
+                payout_indices.push(adas.len()); // The next history entry is the first one with this payout
+            }
+        }
+        if credited > 0 && !pool.compound {
+            // Every reward is taken out as it lands so the staked balance stays at the ADA put in the withdrawal fee comes out of the reward
+            let tx_fee = lovelace(pool.tx_fee_ada, options.rounding).min(credited);
+            withdrawn_ada += to_ada(credited - tx_fee);
+            withdrawn_usd += to_ada(credited - tx_fee) * price;
+            tx_fees_ada += to_ada(tx_fee);
+            tx_fees_usd += to_ada(tx_fee) * price;
+            transactions += 1;
+        } else if credited > 0 {
+            // The part kept as ADA is rounded down the lovelace left over is sold with the rest
+            let kept = if pool.reward_ada_fraction >= 1.0 {
+                credited
+            } else {
+                ((credited as f64 * pool.reward_ada_fraction).floor() as u128).min(credited)
+            };
+            pending_rewards += kept;
+            paid_epochs += 1;
+            let restakes = paid_epochs.is_multiple_of(pool.restake_every_n_epochs.max(1));
+            if restakes {
+                staked += pending_rewards;
+                pending_rewards = 0;
+            }
+            usd_balance += to_ada(credited - kept) * price;
+            // Rewards left alone compound on chain for free withdrawing to sell or to restake by hand is one transaction
+            if pool.reward_ada_fraction < 1.0 || (restakes && pool.restake_every_n_epochs > 1) {
+                let tx_fee = lovelace(pool.tx_fee_ada, options.rounding).min(staked);
+                staked -= tx_fee;
+                tx_fees_ada += to_ada(tx_fee);
+                tx_fees_usd += to_ada(tx_fee) * price;
+                transactions += 1;
+            }
+        }
+        if let (true, Some(reserve)) = (pay_day, reserve.as_mut()) {
+            *reserve -= *reserve * pool.rho; // The reserve drains every epoch whether or not this stake was delegated
+        }
+
+        price *= pool.price_yield_on(day) * price_shock(); // Increase price by average positive change use less than 1 (or a year in price_yields) for a downward trend
+        let mut price_event = false;
+        if !pool.price_events.is_empty() {
+            let (moved, fired) = pool.apply_price_events(day, price);
+            price = moved;
+            price_event = !fired.is_empty();
+            for i in fired {
+                // Marked once each a floor or cap holding for months would bury the other events
+                if reporting && options.record_history && !price_events_marked.contains(&i) {
+                    price_events_marked.push(i);
+                    events.push((adas.len(), format!("{} (Day {})", pool.price_events[i].describe(), day)));
+                }
+            }
+        }
+        if let Some(max_price) = pool.max_price {
+            if price >= max_price {
+                price = max_price;
+                if price_cap_day.is_none() {
+                    price_cap_day = Some(day);
+                    if reporting && options.record_history {
+                        events.push((adas.len(), format!("Price Cap (Day {})", day)));
+                    }
+                }
+            }
+        }
+
+        // Deepest fall of the total from its running peak a declining price_yield shows up here long before the final loss
+        if reporting {
+            let total = to_ada(staked + pending_rewards) * price + usd_balance + withdrawn_usd;
+            if total > peak.1 {
+                peak = (day, total);
+            } else if peak.1 > 0.0 && 1.0 - total / peak.1 > max_drawdown.0 {
+                max_drawdown = (1.0 - total / peak.1, peak.0, day);
+            }
+            if break_even_day.is_none() && total > pool.ada * pool.initial_price + contributed_usd {
+                break_even_day = Some(day);
+            }
+            let reached = match options.target {
+                Some(Target::Usd(usd)) => total >= usd,
+                Some(Target::Ada(target)) => to_ada(staked + pending_rewards) >= target,
+                None => false,
+            };
+            if reached {
+                target_day = Some(day);
+            }
+        }
+
+        if reporting {
+            credited_rewards += to_ada(credited);
+            on_day(&DayRecord {
+                day,
+                date: start_date + chrono::Duration::days(day as i64),
+                last_day: days - 1,
+                start_ada,
+                start_price,
+                start_contributed_usd,
+                start_contributed_ada,
+                start_withdrawn_ada,
+                start_harvested_ada,
+                start_harvested_usd,
+                start_in_flight,
+                start_reserve,
+                contribution,
+                ada: to_ada(staked),
+                price,
+                pay_day: pay_day_status,
+                annual_yield,
+                credited: to_ada(credited),
+                in_flight: to_ada(in_flight.iter().sum()),
+                price_event,
+            });
+        }
+        let last_day = day == days - 1 || depleted_day.is_some() || target_day.is_some();
+        if reporting {
+            year_rewards = (year_rewards.0 + to_ada(credited), year_rewards.1 + to_ada(credited) * price);
+        }
+        if reporting && (day == year_end || last_day) {
+            let total = to_ada(staked + pending_rewards) * price + usd_balance + withdrawn_usd;
+            yearly.push(YearSummary {
+                year,
+                end_day: day,
+                end_date: start_date + chrono::Duration::days(day as i64),
+                partial: day != year_end,
+                ada: to_ada(staked + pending_rewards),
+                price,
+                total,
+                rewards_ada: year_rewards.0,
+                rewards_usd: year_rewards.1,
+                gain_pct: (total / (pool.ada * pool.initial_price + contributed_usd) - 1.0) * 100.0,
+            });
+            year_rewards = (0.0, 0.0);
+        }
+        if day == year_end {
+            year += 1;
+            year_end = calendar_days(start_date, year as i32).unwrap_or(u64::MAX);
+        }
+        if last_day {
+            break;
+        }
+    }
+    // Pending rewards are still owned so they count towards the final amount
+    let mut result = StakedCardanoPoolResult::new(to_ada(staked + pending_rewards), price, adas, prices, days - skip_days.min(days - 1));
+    result.pending_rewards = to_ada(pending_rewards);
+    result.held_historical = helds;
+    result.harvested_historical = harvesteds;
+    result.final_reserve = reserve;
+    result.slashed_ada = slashed_ada;
+    result.tx_fees_ada = tx_fees_ada;
+    result.events = events;
+    result.in_flight_rewards = to_ada(in_flight.iter().sum());
+    result.final_annual_yield = final_annual_yield;
+    result.yearly = yearly;
+    result.max_drawdown = max_drawdown;
+    result.break_even_day = break_even_day;
+    result.tx_fees_usd = tx_fees_usd;
+    result.transactions = transactions;
+    result.baseline = baseline;
+    result.payout_indices = payout_indices;
+    result.undelegated_days = undelegated_days;
+    result.forgone_rewards = forgone_rewards;
+    result.price_cap_day = price_cap_day;
+    result.saturated_day = saturated_day;
+    result.gross_rewards = gross_rewards;
+    result.gross_rewards_usd = gross_rewards_usd;
+    result.fees_paid = fees_paid;
+    result.fees_paid_usd = fees_paid_usd;
+    result.contributed_usd = contributed_usd;
+    result.periodic_usd = periodic_usd;
+    result.reward_income_usd = reward_income_usd;
+    result.reward_payouts = reward_payouts;
+    result.periodic_ada = periodic_ada;
+    result.contributed_ada = contributed_ada;
+    result.slippage_usd = slippage_usd;
+    result.usd_balance = usd_balance;
+    result.withdrawn_ada = withdrawn_ada;
+    result.withdrawn_usd = withdrawn_usd;
+    result.credited_rewards = credited_rewards;
+    result.periodic_withdrawn_ada = periodic_withdrawn_ada;
+    result.periodic_withdrawn_usd = periodic_withdrawn_usd;
+    result.depleted_day = depleted_day;
+    result.target_day = target_day;
+    result.initial_investment = pool.ada * pool.initial_price;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Pools in the tests always start on the same date so the calendar years do not depend on today
+    fn pool(json: &str) -> StakedCardanoPool {
+        let mut pool: StakedCardanoPool = serde_json::from_str(json).expect("test pool parses");
+        pool.start_date.get_or_insert(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        pool
+    }
+
+    #[test]
+    fn multi_decade_run_matches_an_exact_lovelace_reference() {
+        // 5% over 73.05 five day epochs a year is exactly 1/1461 of the balance per epoch
+        let pool = pool(r#"{"ada": 12345.678901, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05,
+            "epoch_in_days": 5, "years_holding": 40, "reward_lag_epochs": 0}"#);
+        let mut reference: u128 = 12_345_678_901;
+        for _ in 0..pool.simulated_days() / 5 {
+            reference += reference / 1461;
+        }
+        let result = calculate_staked_pool(&pool, &SimulationOptions::default());
+        let lovelace = (result.final_ada_amount * LOVELACE_PER_ADA).round() as u128;
+        assert!(lovelace.abs_diff(reference) <= 1, "{} lovelace vs {} exact", lovelace, reference);
+        assert_eq!(format!("{:.6}", result.final_ada_amount), format!("{}.{:06}", reference / 1_000_000, reference % 1_000_000));
+    }
+
+    #[test]
+    fn final_ada_has_no_float_noise_past_six_decimals() {
+        let pool = pool(r#"{"ada": 100000, "initial_price": 0.3, "price_yield": 1.0001, "annual_yield": 0.04,
+            "epoch_in_days": 5, "years_holding": 20, "restake_every_n_epochs": 6}"#);
+        let result = calculate_staked_pool(&pool, &SimulationOptions::default());
+        let lovelace = result.final_ada_amount * LOVELACE_PER_ADA;
+        assert_eq!(lovelace, lovelace.round());
+        assert_eq!(result.final_ada_amount.to_string().split('.').nth(1).map_or(0, str::len), 6);
+    }
+
+    #[test]
+    fn closed_form_floors_every_payout_like_the_loop() {
+        for (years, lag) in [(0.5, 2), (1.0, 2), (10.0, 0), (30.0, 2)] {
+            let pool = pool(&format!(
+                r#"{{"ada": 6471, "initial_price": 0.56, "daily_price_growth_pct": 1.0, "annual_yield": 0.05,
+                "epoch_in_days": 5, "years_holding": {}, "reward_lag_epochs": {}}}"#,
+                years, lag
+            ));
+            let options = SimulationOptions::default();
+            let looped = calculate_staked_pool(&pool, &options);
+            let closed = calculate_closed_form(&pool, &options).unwrap();
+            assert_eq!(looped.final_ada_amount, closed.final_ada_amount, "{} years", years);
+        }
+        // Only the fields the pool actually uses are named
+        let harvested = pool(r#"{"ada": 1000, "initial_price": 0.5, "price_yield": 1.0, "annual_yield": 0.05, "years_holding": 1,
+            "compound": false, "max_price": 2.0}"#);
+        assert_eq!(
+            calculate_closed_form(&harvested, &SimulationOptions::default()).err().as_deref(),
+            Some("--summary-only does not support max_price, compound false (run without it)")
+        );
+    }
+
+    // Golden results for small fixed pools 2024 is a leap year so one year is 366 days and 73 five day pay days
+    // At 5% each five day epoch pays 0.05 / 73.05 = 1/1461 of the balance rounded down to a whole lovelace
+
+    #[test]
+    fn golden_one_year_five_day_epochs() {
+        let pool = pool(r#"{"ada": 1000, "initial_price": 0.5, "price_yield": 1.001, "annual_yield": 0.05,
+            "epoch_in_days": 5, "years_holding": 1, "reward_lag_epochs": 0}"#);
+        let result = calculate_staked_pool(&pool, &SimulationOptions::default());
+        assert_eq!(pool.simulated_days(), 366);
+        assert_eq!(result.final_ada_amount, 1051.217114);
+        assert_eq!(result.final_ada_price, 0.7208457823714751);
+    }
+
+    #[test]
+    fn golden_one_year_with_two_epochs_of_reward_delay() {
+        // The last two epochs are still in flight at the end of the year
+        let pool = pool(r#"{"ada": 1000, "initial_price": 0.5, "price_yield": 1.0, "annual_yield": 0.05,
+            "epoch_in_days": 5, "years_holding": 1, "reward_lag_epochs": 2}"#);
+        let result = calculate_staked_pool(&pool, &SimulationOptions::default());
+        assert_eq!(result.final_ada_amount, 1049.711401);
+        assert_eq!(result.final_ada_price, 0.5);
+        assert!(result.in_flight_rewards > 0.0);
+    }
+
+    #[test]
+    fn golden_daily_epochs() {
+        let pool = pool(r#"{"ada": 1000, "initial_price": 0.5, "price_yield": 1.0, "annual_yield": 0.05,
+            "epoch_in_days": 1, "years_holding": 1, "reward_lag_epochs": 0}"#);
+        let result = calculate_staked_pool(&pool, &SimulationOptions::default());
+        assert_eq!(result.final_ada_amount, 1051.375242);
+        assert_eq!(result.final_ada_price, 0.5);
+    }
+
+    #[test]
+    fn golden_shortest_holding() {
+        // years_holding has to be above 0 and anything under a day runs no days at all
+        let zero = pool(r#"{"ada": 1000, "initial_price": 0.5, "price_yield": 1.001, "annual_yield": 0.05,
+            "epoch_in_days": 1, "years_holding": 0}"#);
+        assert!(zero.validate().is_err());
+        let shortest = StakedCardanoPool { years_holding: 0.001, ..zero };
+        assert!(shortest.validate().is_ok());
+        let result = calculate_staked_pool(&shortest, &SimulationOptions::default());
+        assert_eq!(result.final_ada_amount, 1000.0);
+        assert_eq!(result.final_ada_price, 0.5);
+        let one_day = StakedCardanoPool { years_holding: 1.0 / 366.0, ..shortest };
+        let result = calculate_staked_pool(&one_day, &SimulationOptions::default());
+        assert_eq!(result.final_ada_price, 0.5 * 1.001);
+    }
+
+    #[test]
+    fn one_year_matches_the_compounding_formula() {
+        // 73 pay days of 1/1461 each only the flooring to whole lovelace keeps it under the formula
+        let pool = pool(r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05,
+            "epoch_in_days": 5, "years_holding": 1, "reward_lag_epochs": 0}"#);
+        let result = calculate_staked_pool(&pool, &SimulationOptions::default());
+        let expected = 1000.0 * (1.0 + 1.0 / 1461.0f64).powi(73);
+        assert!(result.final_ada_amount <= expected && expected - result.final_ada_amount < 73e-6);
+    }
+
+    #[test]
+    fn epoch_length_that_does_not_divide_the_year() {
+        // 366 days hold 52 seven day epochs the two days after the last pay day earn nothing yet
+        let pool = pool(r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05,
+            "epoch_in_days": 7, "years_holding": 1, "reward_lag_epochs": 0}"#);
+        let options = SimulationOptions { record_payouts: true, ..Default::default() };
+        let result = calculate_staked_pool(&pool, &options);
+        assert_eq!(result.reward_payouts.len(), 52);
+        assert_eq!(result.reward_payouts.last().map(|payout| payout.0), Some(364));
+        let expected = 1000.0 * (1.0 + 0.05 / (365.25 / 7.0f64)).powi(52);
+        assert!((result.final_ada_amount - expected).abs() < 52e-6);
+    }
+
+    #[test]
+    fn one_year_runs_every_day_once() {
+        // Day 0 is the starting point and days 1 through 366 are simulated the history holds the start of each of them
+        let pool = pool(r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.001, "annual_yield": 0.05,
+            "epoch_in_days": 1, "years_holding": 1, "reward_lag_epochs": 0}"#);
+        let options = SimulationOptions { record_history: true, record_payouts: true, ..Default::default() };
+        let result = calculate_staked_pool(&pool, &options);
+        assert_eq!(pool.simulated_days(), 366);
+        assert_eq!(result.amount_historical.len(), 366);
+        assert_eq!(result.price_historical[0], 1.0);
+        assert_eq!(result.amount_historical[0], 1000.0);
+        assert_eq!(result.reward_payouts.len(), 366);
+        assert_eq!(result.days_as_float, 367.0);
+        let mut days = Vec::new();
+        calculate_staked_pool_with(&pool, &SimulationOptions::default(), |record| days.push(record.day));
+        assert_eq!(days, (1..=366).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn flat_price_only_grows_the_ada() {
+        let pool = pool(r#"{"ada": 2500, "initial_price": 0.4, "price_yield": 1.0, "annual_yield": 0.05,
+            "epoch_in_days": 5, "years_holding": 3}"#);
+        let options = SimulationOptions { record_history: true, ..Default::default() };
+        let result = calculate_staked_pool(&pool, &options);
+        assert_eq!(result.final_ada_price, 0.4);
+        assert!(result.price_historical.iter().all(|price| *price == 0.4));
+        assert!(result.final_ada_amount > 2500.0);
+        assert_eq!(result.total(), result.final_ada_amount * 0.4);
+    }
+
+    #[test]
+    fn total_and_gain_math() {
+        let pool = pool(r#"{"ada": 1000, "initial_price": 2.0, "price_yield": 1.0, "annual_yield": 0.05,
+            "epoch_in_days": 5, "years_holding": 1}"#);
+        let mut result = StakedCardanoPoolResult::new(1100.0, 3.0, Vec::new(), Vec::new(), 367);
+        assert_eq!(result.total(), 3300.0);
+        assert_eq!(result.value_multiple(&pool), 1.65);
+        assert_eq!(result.yield_as_percentage(&pool), 165.0);
+        assert!((result.net_return_as_percentage(&pool) - 65.0).abs() < 1e-9);
+        assert_eq!(result.hold_total(&pool), 3000.0);
+        // Sold rewards and withdrawals still count towards the total
+        result.usd_balance = 50.0;
+        result.withdrawn_usd = 150.0;
+        assert_eq!(result.total(), 3500.0);
+        assert!((result.net_return_as_percentage(&pool) - 75.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn dates_parse_in_every_accepted_format() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        for value in ["2024-03-04", "03/04/2024", "04.03.2024", " 2024-03-04 "] {
+            assert_eq!(parse_date(value, None), Ok(date), "{}", value);
+        }
+        // A given format wins over the built in ones
+        assert_eq!(parse_date("03/04/2024", Some("%d/%m/%Y")), Ok(NaiveDate::from_ymd_opt(2024, 4, 3).unwrap()));
+        assert_eq!(parse_date("2024-03-04", Some("%d/%m/%Y")), Ok(date));
+        let error = parse_date("31/12/2024", None).unwrap_err();
+        assert!(error.contains("YYYY-MM-DD, MM/DD/YYYY, DD.MM.YYYY"), "{}", error);
+        assert!(parse_date("31/12/2024", Some("%Y")).unwrap_err().contains("%Y, YYYY-MM-DD"));
+    }
+
+    #[test]
+    fn pool_dates_accept_the_other_formats() {
+        let us = pool(r#"{"ada": 1, "initial_price": 1, "annual_yield": 0.05, "years_holding": 1, "start_date": "01/31/2025", "epoch_anchor_date": "13.02.2025"}"#);
+        assert_eq!(us.start_date, NaiveDate::from_ymd_opt(2025, 1, 31));
+        assert_eq!(us.epoch_anchor_date, NaiveDate::from_ymd_opt(2025, 2, 13));
+        let error = serde_json::from_str::<StakedCardanoPool>(r#"{"ada": 1, "initial_price": 1, "annual_yield": 0.05, "years_holding": 1, "start_date": "2025/01/31"}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("start_date 2025/01/31 is not a date"), "{}", error);
+    }
+
+    #[test]
+    fn rounding_modes_at_the_half_way_boundary() {
+        let cases = [
+            // value, half-up, half-even, truncate
+            (2.5, 3.0, 2.0, 2.0),
+            (3.5, 4.0, 4.0, 3.0),
+            (-2.5, -3.0, -2.0, -2.0),
+            (2.675 * 100.0, 268.0, 268.0, 267.0), // A tie that lands at 267.49999999999997
+            (0.29 * 100.0, 29.0, 29.0, 29.0),     // Float noise below a whole number is not truncated away
+            (2.4999, 2.0, 2.0, 2.0),
+        ];
+        for (value, half_up, half_even, truncate) in cases {
+            assert_eq!(RoundingMode::HalfUp.round(value), half_up, "{}", value);
+            assert_eq!(RoundingMode::HalfEven.round(value), half_even, "{}", value);
+            assert_eq!(RoundingMode::Truncate.round(value), truncate, "{}", value);
+        }
+    }
+
+    #[test]
+    fn configured_ada_goes_to_lovelace_with_the_rounding_mode() {
+        let final_ada = |ada: &str, rounding: RoundingMode| {
+            let pool = pool(&format!(r#"{{"ada": {}, "initial_price": 1, "annual_yield": 0.05, "years_holding": 0.001}}"#, ada));
+            calculate_staked_pool(&pool, &SimulationOptions { rounding, ..Default::default() }).final_ada_amount
+        };
+        // Half a lovelace over a whole one and over an odd one
+        assert_eq!(final_ada("1000.0000005", RoundingMode::HalfUp), 1000.000001);
+        assert_eq!(final_ada("1000.0000005", RoundingMode::HalfEven), 1000.0);
+        assert_eq!(final_ada("1000.0000005", RoundingMode::Truncate), 1000.0);
+        assert_eq!(final_ada("1000.0000015", RoundingMode::HalfUp), 1000.000002);
+        assert_eq!(final_ada("1000.0000015", RoundingMode::HalfEven), 1000.000002);
+        assert_eq!(final_ada("1000.0000015", RoundingMode::Truncate), 1000.000001);
+    }
+
+    #[test]
+    fn compound_exact_grows_a_year_by_exactly_annual_yield() {
+        // A 365 day year of five day epochs is 73 whole pay days
+        let pool = pool(r#"{"ada": 10000000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05,
+            "epoch_in_days": 5, "years_holding": 1, "day_count": "365", "reward_lag_epochs": 0}"#);
+        let growth = |compound_exact| {
+            let result = calculate_staked_pool(&pool, &SimulationOptions { compound_exact, ..Default::default() });
+            result.final_ada_amount / pool.ada - 1.0
+        };
+        // Flooring each reward to a whole lovelace loses at most 73 lovelace of the 10 million ADA
+        assert!((growth(true) - 0.05).abs() < 1e-11, "{}", growth(true));
+        assert!((growth(false) - ((1.0 + 0.05 / 73.0f64).powi(73) - 1.0)).abs() < 1e-11, "{}", growth(false));
+    }
+
+    #[test]
+    fn declining_price_reports_a_loss() {
+        let pool = pool(r#"{"ada": 1000, "initial_price": 1.0, "daily_price_growth_pct": -0.2, "annual_yield": 0.05, "years_holding": 1}"#);
+        let result = calculate_staked_pool(&pool, &SimulationOptions::default());
+        assert!(result.final_ada_amount > pool.ada); // Staking still adds ADA
+        assert!(result.final_ada_price < pool.initial_price);
+        let net_return = result.net_return_as_percentage(&pool);
+        assert!((net_return - (result.total() / 1000.0 - 1.0) * 100.0).abs() < 1e-9, "{}", net_return);
+        assert!(net_return < -49.0 && net_return > -50.0, "{}", net_return);
+        assert!(result.value_multiple(&pool) < 1.0 && result.cagr(&pool) < 0.0);
+        assert_eq!(result.break_even_day, None);
+        let (drawdown, peak_day, trough_day) = result.max_drawdown;
+        assert!(drawdown > 0.49 && peak_day == 0 && trough_day == pool.simulated_days(), "{:?}", result.max_drawdown);
+    }
+
+    #[test]
+    fn simulation_is_a_pure_function_of_its_inputs() {
+        let pool = pool(r#"{"ada": 1000, "initial_price": 0.5, "daily_price_growth_pct": 0.1, "annual_yield": 0.05, "years_holding": 2}"#);
+        let options = SimulationOptions { record_history: true, ..Default::default() };
+        let first = calculate_staked_pool(&pool, &options);
+        let second = calculate_staked_pool(&pool, &options);
+        assert_eq!(serde_json::to_string(&first).unwrap(), serde_json::to_string(&second).unwrap());
+        assert_eq!(first.amount_historical.len() as u64, pool.simulated_days());
+        // The history is only kept when asked for and leaves the result otherwise the same
+        let without_history = calculate_staked_pool(&pool, &SimulationOptions::default());
+        assert!(without_history.amount_historical.is_empty());
+        assert_eq!(
+            (without_history.final_ada_amount, without_history.final_ada_price),
+            (first.final_ada_amount, first.final_ada_price)
+        );
+    }
+
+    #[test]
+    fn price_growth_forms_and_the_price_yield_guard() {
+        let pool = |growth: &str| pool(&format!(r#"{{"ada": 1000, "initial_price": 1.0, {}, "annual_yield": 0.05, "years_holding": 1, "day_count": "365.25"}}"#, growth));
+        let annual = pool(r#""annual_price_growth_pct": 20"#);
+        let result = calculate_staked_pool(&annual, &SimulationOptions::default());
+        // 365 of the 365.25 days of a year
+        assert!((result.final_ada_price - 1.2f64.powf(365.0 / 365.25)).abs() < 1e-9, "{}", result.final_ada_price);
+        assert!((result.final_ada_price - 1.2).abs() < 1e-3, "{}", result.final_ada_price);
+        assert_eq!(annual.deprecation_note(), None);
+        let daily = pool(r#""daily_price_growth_pct": 0.1"#);
+        let deprecated = pool(r#""price_yield": 1.001"#);
+        assert_eq!(daily.daily_price_yield(), deprecated.daily_price_yield());
+        assert!(deprecated.deprecation_note().unwrap().contains("\"daily_price_growth_pct\": 0.1000"));
+        assert!(deprecated.validate().is_ok());
+        let error = pool(r#""price_yield": 0.01"#).validate().unwrap_err();
+        assert!(error.contains("price_yield 0.01 is a daily multiplier"), "{}", error);
+        assert!(pool(r#""price_yield": 1.001, "daily_price_growth_pct": 0.1"#).validate().unwrap_err().contains("only use one"));
+    }
+
+    #[test]
+    fn anchored_epochs_pay_on_the_real_boundaries() {
+        // Mainnet epoch 540 started on 2025-02-13 so the first boundary after a 2025-02-15 start is 2025-02-18
+        let pool = pool(r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05, "epoch_in_days": 5,
+            "years_holding": 0.1, "start_date": "2025-02-15", "epoch_anchor_date": "2025-02-13", "reward_lag_epochs": 2}"#);
+        assert_eq!(pool.epoch_phase(), 2);
+        let mut pay_days = Vec::new();
+        calculate_staked_pool_with(&pool, &SimulationOptions::default(), |record| {
+            if let PayDay::Paid { gross, .. } = record.pay_day {
+                pay_days.push((record.day, record.date.to_string(), pool.epoch_number(record.date), gross));
+            }
+        });
+        let days: Vec<(u64, &str, Option<i64>)> = pay_days.iter().map(|(day, date, epoch, _)| (*day, date.as_str(), *epoch)).collect();
+        assert_eq!(days[..3], [(3, "2025-02-18", Some(541)), (8, "2025-02-23", Some(542)), (13, "2025-02-28", Some(543))]);
+        // Three of the five days of epoch 540 were staked and the delay keeps the balance the same for both
+        assert!((pay_days[0].3 / pay_days[1].3 - 0.6).abs() < 1e-9, "{:?}", pay_days);
+        // Without an anchor the pay days stay every epoch_in_days from day 0
+        let unanchored = StakedCardanoPool { epoch_anchor_date: None, ..pool.clone() };
+        assert_eq!(unanchored.epoch_number(unanchored.start()), None);
+        assert!((1..=20).filter(|day| unanchored.is_credit_day(*day)).eq([5, 10, 15, 20]));
+    }
+
+    #[test]
+    fn reserve_rewards_start_near_mainnet_yields_and_decay() {
+        // Roughly the 2025 mainnet reserve and active stake
+        let pool = pool(r#"{"ada": 100000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05, "epoch_in_days": 5,
+            "years_holding": 2, "day_count": "365", "reward_lag_epochs": 0, "reserve_ada": 7500000000, "total_stake_ada": 22000000000}"#);
+        let one_year = StakedCardanoPool { years_holding: 1.0, ..pool.clone() };
+        let first = calculate_staked_pool(&one_year, &SimulationOptions::default());
+        let both = calculate_staked_pool(&pool, &SimulationOptions::default());
+        let first_year = first.final_ada_amount / pool.ada - 1.0;
+        let second_year = both.final_ada_amount / first.final_ada_amount - 1.0;
+        assert!(first_year > 0.04 && first_year < 0.07, "{}", first_year);
+        assert!(second_year < first_year, "{} then {}", first_year, second_year);
+        assert!((pool.reserve_annual_yield(7.5e9) - 7.5e9 * 0.003 * 0.8 / 22e9 * 73.0).abs() < 1e-12);
+        // 73 pay days a year each releasing rho of what is left
+        let reserve = both.final_reserve.unwrap();
+        assert!((reserve / (7.5e9 * 0.997f64.powi(146)) - 1.0).abs() < 1e-9, "{}", reserve);
+    }
+
+    #[test]
+    fn roi_and_cagr_by_hand() {
+        let pool = pool(r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05, "years_holding": 2}"#);
+        // Doubled in two years is +100% and sqrt(2) - 1 a year
+        let result = StakedCardanoPoolResult::new(1000.0, 2.0, Vec::new(), Vec::new(), 731);
+        assert_eq!(result.value_multiple(&pool), 2.0);
+        assert_eq!(result.net_return_as_percentage(&pool), 100.0);
+        assert!((result.cagr(&pool) - (2f64.sqrt() - 1.0)).abs() < 1e-12);
+        // No time held and nothing put in give zeros rather than a division by zero
+        let no_time = StakedCardanoPool { years_holding: 0.0, ..pool.clone() };
+        assert_eq!(result.cagr(&no_time), 0.0);
+        let nothing_put_in = StakedCardanoPool { initial_price: 0.0, ..pool.clone() };
+        assert_eq!((result.value_multiple(&nothing_put_in), result.cagr(&nothing_put_in)), (0.0, 0.0));
+        assert!(result.net_return_as_percentage(&nothing_put_in).is_finite());
+        // A run that ran dry after one year is annualized over that year not the two it was meant to last
+        let mut depleted = StakedCardanoPoolResult::new(1000.0, 2.0, Vec::new(), Vec::new(), 367);
+        depleted.depleted_day = Some(366);
+        assert!((depleted.cagr(&pool) - 1.0).abs() < 1e-12);
+        assert_eq!(depleted.years_held(&pool), 1.0);
+        // Sold off on day 0 leaves no time to annualize over
+        depleted.depleted_day = Some(0);
+        assert_eq!((depleted.cagr(&pool), depleted.ada_cagr(&pool), depleted.real_cagr(&pool)), (0.0, 0.0, 0.0));
+        // Selling more than the rewards empties the stake well before the ten years are up
+        let selling = StakedCardanoPool {
+            years_holding: 10.0,
+            withdrawal_usd_per_interval: 100.0,
+            withdrawal_interval_days: 30,
+            annual_inflation: 0.03,
+            ..pool.clone()
+        };
+        let run = calculate_staked_pool(&selling, &SimulationOptions::default());
+        let day = run.depleted_day.expect("the stake runs out");
+        assert!(day < selling.simulated_days());
+        assert_eq!(run.years_held(&selling), selling.years_on(day));
+        assert_eq!(run.real_total(&selling), run.total() / selling.inflation_factor(day));
+        assert!(run.cagr(&selling).is_finite() && run.ada_cagr(&selling).is_finite());
+    }
+
+    #[test]
+    fn day_count_sets_the_run_length_and_epochs_per_year() {
+        // 2024 is a leap year so actual runs one day longer than 365.25 over a single year
+        for (day_count, one_year, four_years, epochs_per_year) in
+            [("actual", 366, 1461, 73.05), ("365", 365, 1460, 73.0), ("365.25", 365, 1461, 73.05)]
+        {
+            let years = |years: u64| {
+                pool(&format!(r#"{{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05, "epoch_in_days": 5,
+                    "years_holding": {}, "day_count": "{}"}}"#, years, day_count))
+            };
+            assert_eq!(years(1).simulated_days(), one_year, "{}", day_count);
+            assert_eq!(years(4).simulated_days(), four_years, "{}", day_count);
+            assert_eq!(years(1).epochs_per_year(), epochs_per_year, "{}", day_count);
+            // Every simulated day compounds the price once with no stray extra day
+            let growing = StakedCardanoPool { price_yield: 1.001, ..years(1) };
+            let result = calculate_staked_pool(&growing, &SimulationOptions::default());
+            assert!((result.final_ada_price / 1.001f64.powi(one_year as i32) - 1.0).abs() < 1e-12, "{}", day_count);
+        }
+    }
+
+    #[test]
+    fn reward_delay_is_off_unless_asked_for() {
+        let immediate = pool(r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05, "epoch_in_days": 5, "years_holding": 1}"#);
+        assert_eq!(immediate.reward_lag_epochs, 0);
+        let lagged = pool(r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05, "epoch_in_days": 5, "years_holding": 1,
+            "reward_delay_epochs": 2}"#);
+        assert_eq!(lagged.reward_lag_epochs, 2);
+        assert!(StakedCardanoPool::field_names().contains(&"reward_delay_epochs"));
+        let options = SimulationOptions { record_history: true, ..Default::default() };
+        let (lagged_run, immediate_run) = (calculate_staked_pool(&lagged, &options), calculate_staked_pool(&immediate, &options));
+        // The first reward joins the balance on the third pay day instead of the first
+        let first_credit = |result: &StakedCardanoPoolResult| result.amount_historical.iter().position(|ada| *ada > 1000.0);
+        assert_eq!((first_credit(&immediate_run), first_credit(&lagged_run)), (Some(5), Some(15)));
+        assert!(lagged_run.final_ada_amount < immediate_run.final_ada_amount);
+        assert_eq!(immediate_run.in_flight_rewards, 0.0);
+        assert!(lagged_run.in_flight_rewards > 0.0);
+    }
+
+    #[test]
+    fn ada_growth_leaves_out_the_ada_bought() {
+        let buying = pool(r#"{"ada": 1000, "initial_price": 0.5, "daily_price_growth_pct": 0.1, "annual_yield": 0.0, "years_holding": 2,
+            "lump_sums": [[100, 200]], "contribution": {"amount_usd": 25, "cadence_days": 30}}"#);
+        // Without rewards every ADA at the end was bought so the ADA did not grow whatever the price did
+        let result = calculate_staked_pool(&buying, &SimulationOptions::default());
+        assert!(result.final_ada_amount > 1000.0 && result.contributed_ada > 0.0);
+        assert!(result.ada_growth_as_percentage(&buying).abs() < 1e-9);
+        assert!(result.ada_cagr(&buying).abs() < 1e-9);
+        // With rewards the growth is the staking alone still under the growth of the raw ADA count
+        let staking = StakedCardanoPool { annual_yield: 0.05, reward_lag_epochs: 0, ..buying.clone() };
+        let result = calculate_staked_pool(&staking, &SimulationOptions::default());
+        let growth = result.ada_growth_as_percentage(&staking);
+        assert!(growth > 0.0 && growth < (result.final_ada_amount / staking.ada - 1.0) * 100.0);
+        assert!(result.ada_cagr(&staking) > 0.0);
+    }
+
+    #[test]
+    fn contributed_ada_is_not_taken_down_by_slashing_or_withdrawals() {
+        let pool = pool(r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05, "years_holding": 1,
+            "lump_sums": [[10, 500]], "slash_events": [[20, 0.5]], "withdrawal_usd_per_interval": 10, "withdrawal_interval_days": 30}"#);
+        let mut last_start_contributed = 0.0;
+        let result = calculate_staked_pool_with(&pool, &SimulationOptions::default(), |record| {
+            last_start_contributed = record.start_contributed_ada;
+        });
+        // Half the stake was slashed and some sold yet the 500 ADA bought still counts as put in
+        assert_eq!(result.contributed_ada, 500.0);
+        assert_eq!(last_start_contributed, 500.0);
+        assert_eq!(result.held_ada(&pool), 1500.0);
+    }
+
+    #[test]
+    fn solver_and_target_set_the_ada_and_the_horizon() {
+        let pool = pool(r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05, "epoch_in_days": 5, "years_holding": 2}"#);
+        let options = SimulationOptions::default();
+        let solution = analysis::solve_initial_ada(&pool, 5000.0, &options).unwrap();
+        let total = calculate_staked_pool(&StakedCardanoPool { ada: solution.ada, ..pool.clone() }, &options).total();
+        assert!((total - 5000.0).abs() <= analysis::SOLVE_TOLERANCE_USD, "{}", total);
+        assert_eq!(analysis::solve_initial_ada(&pool, 0.0, &options), Err(analysis::SolveError::InvalidTarget(0.0)));
+
+        // 5% a year reaches 1100 ADA in two years the horizon ends on that day
+        let mut reached = pool.clone();
+        let day = analysis::apply_target(&mut reached, Target::Ada(1100.0), 10.0, &options).unwrap();
+        assert_eq!(reached.simulated_days(), day);
+        assert!(calculate_staked_pool(&reached, &options).final_ada_amount >= 1100.0);
+        let mut missed = pool.clone();
+        assert_eq!(analysis::apply_target(&mut missed, Target::Ada(1_000_000.0), 10.0, &options), None);
+        assert_eq!(missed.years_holding, 10.0);
+    }
+
+    #[test]
+    fn bench_times_every_horizon_once_both_paths_agree() {
+        let plain = pool(r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0001, "annual_yield": 0.05, "epoch_in_days": 5, "years_holding": 1}"#);
+        let rows = analysis::bench(&plain, &SimulationOptions::default()).unwrap();
+        assert_eq!(rows.iter().map(|row| row.years).collect::<Vec<_>>(), analysis::BENCH_YEARS.to_vec());
+        // The closed form does not model price events so the bench refuses them instead of timing two different runs
+        let events = pool(r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05, "years_holding": 1,
+            "price_events": [{"day": 3, "kind": "shock", "factor": 0.5}]}"#);
+        assert!(analysis::bench(&events, &SimulationOptions::default()).is_err());
+    }
+
+    #[test]
+    fn sweep_writes_whole_values_of_integer_fields() {
+        let pool = pool(r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05, "epoch_in_days": 5, "years_holding": 1}"#);
+        for value in [1.0, 3.0, 5.0] {
+            assert_eq!(analysis::pool_with_field(&pool, "epoch_in_days", value).unwrap().epoch_in_days, value as u64);
+        }
+        assert_eq!(analysis::pool_with_field(&pool, "reward_lag_epochs", 0.0).unwrap().reward_lag_epochs, 0);
+        assert_eq!(analysis::pool_with_field(&pool, "annual_yield", 0.04).unwrap().annual_yield, 0.04);
+    }
+
+    #[test]
+    fn sweep_rejects_fractional_steps_on_integer_fields() {
+        let pool = pool(r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05, "epoch_in_days": 5, "years_holding": 1}"#);
+        let error = analysis::check_sweep_range(&pool, &analysis::parse_sweep("epoch_in_days=1:5:0.5").unwrap()).unwrap_err();
+        assert!(error.contains("whole number"), "{}", error);
+        assert!(analysis::check_sweep_range(&pool, &analysis::parse_sweep("epoch_in_days=1:5:1").unwrap()).is_ok());
+        assert!(analysis::check_sweep_range(&pool, &analysis::parse_sweep("annual_yield=0.03:0.06:0.005").unwrap()).is_ok());
+    }
+}
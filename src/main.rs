@@ -20,12 +20,47 @@ Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301, USA.
 
 */
 
-use clap::{arg, Command};
-use poloto::prelude::*;
+mod config;
+mod csv;
+mod graph;
+mod price_api;
+mod report;
+mod scenarios;
+mod xlsx;
+
+use config::{
+    apply_date_format, apply_price_history, convert_pool_file, init_pool_file, parse_pool, pool_file_to_json, read_pool_file, read_pool_source,
+    read_price_history, read_reward_history, unknown_pool_keys, validate_pool_file, EXAMPLE_POOL_JSON, STDIN_PATH,
+};
+use csv::{
+    append_ledger, data_header, data_row, default_csv_path, has_pool_fees, open_csv_writer, percentile_name, print_ledger, tax_report_path,
+    write_monte_carlo_csv, write_sweep_csv, write_tax_report_csv, CsvColumns, CsvDialect, CsvGranularity, DEFAULT_LEDGER_PATH, XLSX_DIALECT,
+};
+use graph::{
+    default_graph_path, generate_graph, graph_file_path, graph_from_csv, has_graph_history, render_graph_svg, render_monte_carlo_svg,
+    split_graph_path, write_graph, GraphFormat, GraphLayout, GraphScale, GraphTheme, DEFAULT_GRAPH_MAX_POINTS,
+};
+use scenarios::{parse_scenarios, parse_wallets, run_portfolio, run_scenarios};
+use ada_calc::analysis::{
+    self, break_even_analysis, check_sweep_range, monte_carlo_percentiles, parse_sweep, percentile, sensitivity_rows, BreakEven, CompareMetric,
+    SensitivityRow, SolveError, SweepRange, SOLVE_TOLERANCE_USD,
+};
+use ada_calc::{
+    calculate_closed_form, calculate_staked_pool_with, Compounding, Contribution, DayCount, HistoryStatistics, PayDay, RoundingMode,
+    SimulationOptions, StakedCardanoPool, StakedCardanoPoolResult, Target, YearSummary,
+};
+use chrono::TimeZone;
+use clap::{arg, Command, ValueHint};
+use owo_colors::OwoColorize;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
-use std::fs::{read_to_string, OpenOptions};
-use std::io::Write;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::fs::{read_to_string, File, OpenOptions};
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{BufRead, BufWriter, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::process::ExitCode;
 
 // Used to get a Unix timestamp for file output purposes
 fn get_epoch_ms() -> u128 {
@@ -35,51 +70,164 @@ fn get_epoch_ms() -> u128 {
         .as_millis()
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TimestampFormat {
+    Unix,    // Raw Unix milliseconds the original naming scheme
+    Iso8601, // Local time like 2024-01-15T12-30-00 (colons are not allowed in Windows filenames)
+    Latest,  // Set by --watch every rerun overwrites the same file instead of adding another
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReportFormat {
+    Markdown, // ada_calc_report_<timestamp>.md for notes and GitHub
+    Html,     // ada_calc_report_<timestamp>.html one file with the graph inline
+}
+
+// Timestamp appended to generated file names
+fn get_file_timestamp(format: TimestampFormat) -> String {
+    match format {
+        TimestampFormat::Unix => get_epoch_ms().to_string(),
+        TimestampFormat::Iso8601 => chrono::Local::now().format("%Y-%m-%dT%H-%M-%S").to_string(),
+        TimestampFormat::Latest => String::from("latest"),
+    }
+}
+
+// Roughly the size of the global GDP in USD anything above this is almost certainly a typo in the inputs
+const DEFAULT_EXTREME_THRESHOLD: f64 = 100_000_000_000_000.0;
+
+// Fresh enough for a batch of runs without asking CoinGecko every time
+const DEFAULT_PRICE_CACHE_TTL_MINUTES: u64 = 15;
+
+// Everything needed to share or reproduce a run in one JSON file
+#[derive(Debug, Serialize, Deserialize)]
+struct RunBundle {
+    version: String, // ada_calc version that produced the bundle
+    config: StakedCardanoPool,
+    summary: RunSummary,
+    #[serde(default)]
+    svg: Option<String>, // Embedded graph when --generate_graph was set
+}
+
 #[derive(Debug, Serialize, Deserialize)]
-struct StakedCardanoPool {
-    ada: f64, // Total amount of ADA which uses 6 decimal places can be changed in the source code format options
-    fetch_price_via_api: bool, // Optional fetch price from a trusted API currently not implemented to be used as a realtime pricing over a fixed starting price
-    initial_price: f64,        // Starting price in USD
-    price_yield: f64, // Daily average increase in price of ADA 1% is a good conservative number
-    annual_yield: f64, // Expressed as a fraction for example 5% is 0.05
-    epoch_in_days: u64, // How many days before a payout happens this is fixed by ADA currently 5 days but can be changed for future purposes
-    years_holding: f64, // How many years will it be staked less than 1 one means less than a year for exaple 0.5 means 365.25/2 (roughly since its floating point values)
-}
-
-#[derive(Default)]
-struct StakedCardanoPoolResult {
+struct RunSummary {
     final_ada_amount: f64,
     final_ada_price: f64,
-    amount_historical: Vec<f64>,
-    price_historical: Vec<f64>,
-    days_as_float: f64, // Note: Used by the generate graph option not used otherwise
+    total: f64,
+    yield_as_percentage: f64,
+    days: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    real_total: Option<f64>, // Total in today's dollars when the pool has annual_inflation
 }
 
-impl StakedCardanoPoolResult {
-    fn new(
-        final_ada_amount: f64,
-        final_ada_price: f64,
-        amount_historical: Vec<f64>,
-        price_historical: Vec<f64>,
-        days: u64,
-    ) -> Self {
-        StakedCardanoPoolResult {
-            final_ada_amount: final_ada_amount,
-            final_ada_price: final_ada_price,
-            amount_historical: amount_historical,
-            price_historical: price_historical,
-            days_as_float: days as f64,
+impl RunSummary {
+    fn new(pool: &StakedCardanoPool, result: &StakedCardanoPoolResult) -> Self {
+        RunSummary {
+            final_ada_amount: result.final_ada_amount,
+            final_ada_price: result.final_ada_price,
+            total: result.total(),
+            yield_as_percentage: result.yield_as_percentage(pool),
+            days: result.days_as_float,
+            real_total: Some(result.real_total(pool)).filter(|_| pool.annual_inflation != 0.0),
         }
     }
+}
+
+// One line written by --ndjson either the summary or the reason the config on that line was rejected
+#[derive(Serialize)]
+struct NdjsonResult {
+    line: usize, // 1 based line number of the config on stdin
+    #[serde(flatten)]
+    summary: Option<RunSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
 
-    fn total(&self) -> f64 {
-        self.final_ada_amount * self.final_ada_price
+// Everything --json prints the history arrays are only included with --include-history
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    #[serde(flatten)]
+    summary: RunSummary,
+    pool: &'a StakedCardanoPool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    amount_historical: Option<&'a [f64]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price_historical: Option<&'a [f64]>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    yearly: &'a [YearSummary],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sensitivity: Option<Vec<SensitivityRow>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    break_even: Option<BreakEven>,
+}
+
+// Runs that take longer than this show a progress line on stderr so quick runs never flash one
+const PROGRESS_DELAY_MS: u128 = 500;
+
+// Percent done, elapsed time and ETA on one stderr line checked once per percent so it costs nothing per day
+struct Progress {
+    label: &'static str,
+    enabled: bool,
+    start: Instant,
+    percent: Option<u64>,
+    drawn: bool,
+}
+
+impl Progress {
+    // Only for a person watching a terminal never with --json, --ndjson, bench, HTML, the verbose trace, piped output or --no-progress
+    fn new(label: &'static str, args: &CommandOptions, output_to_stdout: bool) -> Self {
+        Progress {
+            label,
+            enabled: !output_to_stdout
+                && !args.no_progress
+                && !args.verbose
+                && !args.json_output
+                && !args.ndjson
+                && args.bench_file.is_none()
+                && atty::is(atty::Stream::Stdout)
+                && atty::is(atty::Stream::Stderr),
+            start: Instant::now(),
+            percent: None,
+            drawn: false,
+        }
     }
 
-    fn yield_as_percentage(&self, pool_info: &StakedCardanoPool) -> f64 {
-        (self.total() / (pool_info.initial_price * pool_info.ada)) * 100.0
+    fn update(&mut self, done: u64, total: u64, unit: &str) {
+        if !self.enabled {
+            return;
+        }
+        let percent = done * 100 / total.max(1);
+        if self.percent == Some(percent) {
+            return;
+        }
+        self.percent = Some(percent);
+        let elapsed = self.start.elapsed();
+        if elapsed.as_millis() < PROGRESS_DELAY_MS {
+            return;
+        }
+        let eta = if done > 0 {
+            format!("{:.0}s", elapsed.as_secs_f64() * (total.saturating_sub(done)) as f64 / done as f64)
+        } else {
+            String::from("?")
+        };
+        eprint!(
+            "\r{}: {:>3}% ({} {} of {}) Elapsed {:.0}s ETA {}   ",
+            self.label,
+            percent,
+            unit,
+            done,
+            total,
+            elapsed.as_secs_f64(),
+            eta
+        );
+        self.drawn = true;
     }
 
+    // Clears the progress line so the results start on a clean line
+    fn finish(&self) {
+        if self.drawn {
+            eprint!("\r{}\r", " ".repeat(79));
+        }
+    }
 }
 
 // Only used for HTML output purposes
@@ -87,252 +235,4037 @@ fn print_html_newline() {
     println!("<br>");
 }
 
-fn calculate_staked_pool(
-    pool: &StakedCardanoPool,
-    args: &CommandOptions,
-    output_to_stdout : bool
-) -> StakedCardanoPoolResult {
-    let mut ada = pool.ada;
-    let mut price = pool.initial_price;
-    let days = (pool.years_holding * 365.25) as u64 + 1;
-    let epochs_per_year = 365.25 / (pool.epoch_in_days as f64);
-    let mut ada_per_year = ada * pool.annual_yield;
-
-    let mut buffer = String::from("Day,ADA,Price,Total\n");
-    let mut adas: Vec<f64> = Vec::new();
-    let mut prices: Vec<f64> = Vec::new();
-
-    println!(
-        "Initial ADA Per Year (Excluding Compounding Interest): {}",
-        ada_per_year
-    );
+// One line of the results on stdout the HTML page gets a line break after it
+fn print_result_line(text: &str, output_to_stdout: bool) {
+    println!("{}", text);
+    if output_to_stdout {
+        print_html_newline();
+    }
+}
 
+// Warnings and errors go to stderr so piped stdout only carries results the HTML output keeps them in the page
+fn print_diagnostic(text: &str, output_to_stdout: bool) {
     if output_to_stdout {
+        println!("{}", text);
         print_html_newline();
+    } else {
+        eprintln!("{}", text);
     }
+}
 
-    if args.verbose {
-        println!("Day 0: {} ADA @ ${:.2} = ${:.2}", ada, price, ada * price);
-        if output_to_stdout {
-            print_html_newline();
-        }
+// Never replaces an existing file unless --force was passed then the old contents are truncated
+fn open_output_file(path: &str, force: bool) -> std::io::Result<File> {
+    if force {
+        OpenOptions::new().write(true).create(true).truncate(true).open(path)
     } else {
-        println!(
-            "Starting Result: {} ADA @ ${:.2} = ${:.2}",
-            ada,
-            price,
-            ada * price
-        );
-        if output_to_stdout {
-            print_html_newline();
-        }
+        OpenOptions::new().write(true).create_new(true).open(path)
     }
+}
+
+// A failed write stops the run with EXIT_WRITE_ERROR unless --on-write-error warn the reason was printed where it failed
+fn write_result(written: bool, what: &str, args: &CommandOptions) -> Result<(), CliError> {
+    if written || !args.fail_on_write_error {
+        Ok(())
+    } else {
+        Err(CliError::Write(format!("{} could not be written", what)))
+    }
+}
+
+// Exit codes one per kind of failure so a script can tell them apart (listed in --help)
+const EXIT_MISSING_CONFIG: u8 = 1; // The pool file or another input could not be read
+const EXIT_PARSE_ERROR: u8 = 2; // The pool file or the command line could not be parsed (clap also exits 2)
+const EXIT_WRITE_ERROR: u8 = 3; // A CSV, SVG or other output could not be written (unless --on-write-error warn)
+const EXIT_VALIDATION_ERROR: u8 = 4; // The pool parsed but its values are invalid or not supported together
+const EXIT_API_ERROR: u8 = 5; // The live price was needed and could not be fetched or read from the cache
+
+const EXIT_CODES_HELP: &str = "EXIT CODES:
+    0    Success
+    1    The pool file or another input could not be read
+    2    The pool file or the command line could not be parsed
+    3    A CSV, SVG or other output could not be written (unless --on-write-error warn)
+    4    The pool has invalid values or options that cannot be used together
+    5    The live price could not be fetched or read from the cache";
+
+// Why a run failed the message is printed once on stderr by main and the kind picks the exit code
+#[derive(Debug)]
+enum CliError {
+    MissingConfig(String),
+    Parse(String),
+    Validation(String),
+    Write(String), // The details were already printed where the write failed this is the closing line
+    Api(String),
+}
 
-    for day in 1..days {
-        if args.generate_graph {
-            adas.push(ada);
-            prices.push(price);
+impl CliError {
+    fn exit_code(&self) -> u8 {
+        match self {
+            CliError::MissingConfig(_) => EXIT_MISSING_CONFIG,
+            CliError::Parse(_) => EXIT_PARSE_ERROR,
+            CliError::Write(_) => EXIT_WRITE_ERROR,
+            CliError::Validation(_) => EXIT_VALIDATION_ERROR,
+            CliError::Api(_) => EXIT_API_ERROR,
         }
+    }
 
-        if args.generate_csv {
-            buffer += format!("{},{},{},{}\n", day, ada, price, ada * price).as_str();
+    // Same kind of error so the same exit code with the message changed
+    fn map_message(self, change: impl FnOnce(String) -> String) -> Self {
+        match self {
+            CliError::MissingConfig(message) => CliError::MissingConfig(change(message)),
+            CliError::Parse(message) => CliError::Parse(change(message)),
+            CliError::Validation(message) => CliError::Validation(change(message)),
+            CliError::Write(message) => CliError::Write(change(message)),
+            CliError::Api(message) => CliError::Api(change(message)),
         }
+    }
 
-        if day > 0 && (day % pool.epoch_in_days) == 0 {
-            ada += ada_per_year / epochs_per_year;
-            ada_per_year = ada * pool.annual_yield;
-            price *= pool.price_yield; // Increase price by average positive change no point in calculating a downard trend but you may use less than 1
-            if args.verbose {
-                println!(
-                    "Day {}: {} ADA @ ${:.2} = ${:.2} [Pay Day: Yes]",
-                    day,
-                    ada,
-                    price,
-                    ada * price
-                );
-                if output_to_stdout {
-                    print_html_newline();
-                }
-            }
-        } else {
-            price *= pool.price_yield; // Increase price by average positive change no point in calculating a downard trend but you may use less than 1
-            if args.verbose {
-                println!(
-                    "Day {}: {} ADA @ ${:.2} = ${:.2} [Pay Day: No]",
-                    day,
-                    ada,
-                    price,
-                    ada * price
-                );
-                if output_to_stdout {
-                    print_html_newline();
-                }
-            }
+    // The pool validation errors come one per line
+    fn invalid_pool(name: &str, errors: &str) -> Self {
+        let mut message = if name.is_empty() { String::from("Invalid pool") } else { format!("Invalid pool in {}", name) };
+        for line in errors.lines() {
+            message.push_str(&format!("\n  {}", line));
         }
+        CliError::Validation(message)
     }
+}
 
-    if args.generate_csv {
-        let csv_filename = format!("raw_ada_calc_data_{}.csv", get_epoch_ms());
-        if let Ok(file) = OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .open(&csv_filename)
-            .as_mut()
-        {
-            if let Ok(_) = file.write_all(buffer.as_bytes()) {
-                println!("Saved CSV as {} to Disk.", &csv_filename);
-            } else {
-                println!("Error: Failed to Write CSV [{}] to Disk.", &csv_filename);
-            }
-        } else {
-            println!("Error: Failed to Write CSV [{}] to Disk.", &csv_filename);
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::MissingConfig(message)
+            | CliError::Parse(message)
+            | CliError::Validation(message)
+            | CliError::Write(message)
+            | CliError::Api(message) => write!(f, "{}", message),
         }
     }
-    if args.generate_graph {
-        StakedCardanoPoolResult::new(ada, price, adas, prices, days) // These vectors will get moved rather than copied -- take note
-    } else {
-        StakedCardanoPoolResult::new(ada, price, adas, prices, days) // Same as above! -- Rust uses move by default for vector this is intentional
+}
+
+// Longest --target-usd or --target-ada run before giving up on the target
+const DEFAULT_TARGET_MAX_YEARS: f64 = 200.0;
+
+// Rates used by --compare-tax-scenarios when none are given
+const DEFAULT_INCOME_TAX_RATE: f64 = 0.3;
+const DEFAULT_CAPITAL_GAINS_RATE: f64 = 0.15;
+
+// Turns an IO error into something the user can act on while keeping the OS error for reference
+fn describe_write_error(path: &str, e: &std::io::Error) -> String {
+    match e.kind() {
+        std::io::ErrorKind::AlreadyExists => format!(
+            "{} already exists remove it, pass --force or run again for a new timestamp (OS error: {})",
+            path, e
+        ),
+        std::io::ErrorKind::PermissionDenied => format!(
+            "Permission denied writing {} check that the directory is writable (OS error: {})",
+            path, e
+        ),
+        _ => format!("Could not write {} the disk may be full or the path is invalid (OS error: {})", path, e),
     }
 }
 
-#[derive(Debug,Clone)]
-struct CommandOptions {
-    verbose: bool,        // Show all possible output to standard output i.e. terminal
-    generate_csv: bool,   // Generate the data in csv output for data science purposes
-    generate_graph: bool, // Generate a graph svg for data visualization purposes,
-    json_option: Option<String>,
+
+// ADA has 6 decimal places (1 lovelace is 0.000001 ADA)
+const ADA_DECIMALS: usize = 6;
+
+// Every ADA amount shown in the terminal and the CSV goes through here so they always match
+// Rounded to a lovelace with --rounding like the USD figures
+fn format_ada(amount: f64, rounding: RoundingMode) -> String {
+    round_to(amount, ADA_DECIMALS, rounding)
 }
 
-impl CommandOptions {
-    fn new(v: bool, g: bool, gg: bool, json_option: Option<String>) -> Self {
-        CommandOptions {
-            verbose: v,
-            generate_csv: g,
-            generate_graph: gg,
-            json_option: json_option,
+// value rounded to decimals places with rounding and written with exactly that many
+fn round_to(value: f64, decimals: usize, rounding: RoundingMode) -> String {
+    let scale = 10f64.powi(decimals as i32);
+    format!("{:.*}", decimals, rounding.round(value * scale) / scale)
+}
+
+// Fiat the USD figures are shown in with --currency the simulation and JSON always stay in USD
+#[derive(Debug, Clone, Copy)]
+struct Currency {
+    code: &'static str,   // ISO code shown in the graph label with --ascii
+    symbol: &'static str, // Put in front of every figure
+    per_usd: f64,         // Units of this currency one USD buys
+    decimals: usize,      // Decimal places shown without --precision yen has none
+}
+
+const USD: Currency = Currency {
+    code: "USD",
+    symbol: "$",
+    per_usd: 1.0,
+    decimals: 2,
+};
+
+impl Currency {
+    fn convert(&self, usd: f64) -> f64 {
+        usd * self.per_usd
+    }
+
+    // An unrounded figure such as a configured price converted and prefixed with the symbol
+    fn show(&self, usd: f64) -> String {
+        format!("{}{}", self.symbol, self.convert(usd))
+    }
+
+    // CSV column name with the code after it USD keeps the plain name so the default header is unchanged
+    fn column(&self, name: &str) -> String {
+        if self.code == "USD" {
+            String::from(name)
+        } else {
+            format!("{} ({})", name, self.code)
         }
     }
 }
 
-fn get_command_options() -> CommandOptions {
-    let matches = Command::new("ADA Staking CLI Tool")
-    .version(env!("CARGO_PKG_VERSION"))
-    .author("zulrah")
-    .about("ADA Staking Calculator For Data Analysis and Visualization Purposes")
-    .arg(arg!(
-        -v --verbose ... "Show Full Output to Terminal/Output"
-    ))
-    .arg(arg!(
-        -g --generate_csv ... "Generate a CSV file of the calculated data output will be raw_ada_calc_data_<timestamp>.csv"
-    ))
-    .arg(arg!(
-        -G --generate_graph ... "Generate a line graph showing two data points the ada over time and price over time ada_calc_graph_<timestamp>.csv"
-    ))
-    .arg( arg!(
-        -p --pool_json <JSON> "Pass JSON input via command option"
-    ).required(false))
-    .get_matches();
-    let json = if matches.is_present("pool_json") { 
-        Some(String::from(
-            matches.value_of("pool_json").unwrap_or_default(),
-        ))
-    } else {
-        println!("Found none finding in current working directory...");
-        None
-    };
-    CommandOptions::new(
-        matches.is_present("verbose"),
-        matches.is_present("generate_csv"),
-        matches.is_present("generate_graph"),
-        json,
-    )
+// How USD figures are shown --rounding picks the rounding (of the ADA figures too) --precision the decimal places and --currency the fiat
+#[derive(Debug, Clone, Copy)]
+struct UsdFormat {
+    rounding: RoundingMode,
+    decimals: usize,
+    currency: Currency,
+}
+
+// Every USD figure shown rounded with --rounding so the last digit is predictable when reconciling
+fn format_usd(value: f64, format: UsdFormat) -> String {
+    round_to(format.currency.convert(value), format.decimals, format.rounding)
 }
 
-fn generate_graph(optional_path: Option<String>, result: &StakedCardanoPoolResult, output_to_stdout : bool) {
-    let prices = &result.price_historical;
-    let adas = &result.amount_historical;
+// format_usd with the currency symbol in front
+fn format_money(value: f64, format: UsdFormat) -> String {
+    format!("{}{}", format.currency.symbol, format_usd(value, format))
+}
 
-    // Functions
-    let p = |x: f64| prices[x as usize];
-    let a = |x: f64| adas[x as usize];
-    let t = |x: f64| adas[x as usize] * prices[x as usize];
+// "<ADA> ADA @ $<price> = $<total>" shared by the starting line, the verbose per day lines and the baseline
+fn format_holding(ada: f64, price: f64, format: UsdFormat) -> String {
+    Holding { ada, price, format }.to_string()
+}
 
-    let range = poloto::range_iter([0.0, result.days_as_float], prices.len());
-    let line_prices = poloto::build::line("Prices ($)", range.clone().map(|x| [x, p(x)]));
-    let line_adas = poloto::build::line("ADAs (₳)", range.clone().map(|x| [x, a(x)]));
-    let line_total = poloto::build::line("Total ($)", range.clone().map(|x| [x, t(x)]));
+// format_holding written straight into the formatter so the verbose loop does not build three Strings every day
+struct Holding {
+    ada: f64,
+    price: f64,
+    format: UsdFormat,
+}
 
-    let m = poloto::build::origin();
-    let data = poloto::plots!(line_prices, line_adas, line_total, m);
+impl fmt::Display for Holding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = self.format;
+        let scale = 10f64.powi(format.decimals as i32);
+        let money = |value: f64| format.rounding.round(format.currency.convert(value) * scale) / scale;
+        let lovelace = 10f64.powi(ADA_DECIMALS as i32);
+        write!(
+            f,
+            "{:.*} ADA @ {}{:.*} = {}{:.*}",
+            ADA_DECIMALS,
+            format.rounding.round(self.ada * lovelace) / lovelace,
+            format.currency.symbol,
+            format.decimals,
+            money(self.price),
+            format.currency.symbol,
+            format.decimals,
+            money(self.ada * self.price)
+        )
+    }
+}
 
-    let p = poloto::simple_fmt!(data, "Cardano Staking Growth", "Days", "$ ₳");
+fn day_count_name(day_count: DayCount) -> &'static str {
+    match day_count {
+        DayCount::Actual => "actual",
+        DayCount::Days365 => "365",
+        DayCount::Days365_25 => "365.25",
+    }
+}
 
-    let svg = format!("{}", poloto::disp(|w| p.simple_theme(w)));
+fn compounding_name(compounding: Compounding) -> &'static str {
+    match compounding {
+        Compounding::Epoch => "epoch",
+        Compounding::Daily => "daily",
+        Compounding::Monthly => "monthly",
+    }
+}
 
-    if output_to_stdout {
-        println!("<div class='svg' style='width:1in;height:1in'>{}</div>", svg);
-    }
-    else if let Some(path) = optional_path.as_ref() {
-        if let Ok(file) = OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .open(path)
-            .as_mut()
+// Rewards the starting balance earns in a year at the yield on day 1 before any compounding
+fn initial_ada_per_year(pool: &StakedCardanoPool) -> f64 {
+    let annual_yield = match (pool.reserve_ada, pool.pool_share(pool.ada)) {
+        (_, Some((reward, share))) => reward * share / pool.ada * pool.epochs_per_year(),
+        (Some(reserve), None) => pool.reserve_annual_yield(reserve) * pool.saturation_on(1) * pool.stake_saturation(pool.ada),
+        (None, None) => pool.annual_yield_on(1) * pool.saturation_on(1) * pool.stake_saturation(pool.ada),
+    };
+    pool.ada * annual_yield
+}
+
+// Printed before the simulation starts
+fn print_starting_result(pool: &StakedCardanoPool, args: &CommandOptions, output_to_stdout: bool) {
+    print_result_line(
+        &format!(
+            "Initial ADA Per Year (Excluding Compounding Interest): {}",
+            format_ada(initial_ada_per_year(pool), args.usd_format.rounding)
+        ),
+        output_to_stdout,
+    );
+
+    if args.verbose {
+        let epochs_per_year = pool.epochs_per_year();
+        print_diagnostic(
+            &if epochs_per_year.fract() == 0.0 {
+                format!(
+                    "Epochs Per Year: {} ({} days / {} day epochs with the {} day count)",
+                    epochs_per_year,
+                    pool.days_per_year(),
+                    pool.epoch_in_days,
+                    day_count_name(pool.day_count)
+                )
+            } else {
+                format!(
+                    "Epochs Per Year: {} ({} days / {} day epochs is fractional because of the leap day so each pay day credits ADA Per Year / {} rather than a whole number of epochs)",
+                    epochs_per_year,
+                    pool.days_per_year(),
+                    pool.epoch_in_days,
+                    epochs_per_year
+                )
+            },
+            output_to_stdout,
+        );
+        if pool.day_count != DayCount::Actual {
+            print_diagnostic(
+                &format!(
+                    "Day Count: {} ({} years is {} days)",
+                    day_count_name(pool.day_count),
+                    pool.years_holding,
+                    pool.simulated_days()
+                ),
+                output_to_stdout,
+            );
+        }
+        print_diagnostic(
+            &format!(
+                "Compounding: {} (rewards credited {} {:.2} times a year)",
+                compounding_name(pool.compounding),
+                pool.describe_compounding(),
+                pool.credits_per_year()
+            ),
+            output_to_stdout,
+        );
+        print_diagnostic(
+            &format!("Day 0 ({}): {}", pool.start(), format_holding(pool.ada, pool.initial_price, args.usd_format)),
+            output_to_stdout,
+        );
+    } else {
+        print_result_line(
+            &format!(
+                "Starting Result: {}",
+                format_holding(pool.ada, pool.initial_price, args.usd_format)
+            ),
+            output_to_stdout,
+        );
+    }
+}
+
+// Runs the simulation printing the verbose day lines and writing the CSV rows as it goes
+fn run_simulation(
+    pool: &StakedCardanoPool,
+    args: &CommandOptions,
+    output_to_stdout : bool
+) -> StakedCardanoPoolResult {
+    let csv_filename = args.csv_out.clone().unwrap_or_else(|| default_csv_path(args));
+    let mut csv_error: Option<std::io::Error> = None;
+    // Rows go straight to the buffered file as they are made the line a row failed on is kept for the error (the header is line 1)
+    let mut csv_lines: u64 = 1;
+    let mut rewards_since_row = 0.0; // Credited after the previous row for the coarser granularities
+    let mut cumulative_rewards = 0.0; // Every day not only the written rows so a coarser granularity still ends on the summary's Rewards Paid
+    let mut csv_error_line: Option<u64> = None;
+    let mut csv_writer = if args.generate_csv {
+        match open_csv_writer(&csv_filename, args, pool) {
+            Ok(writer) => Some(writer),
+            Err(e) => {
+                csv_error = Some(e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let xlsx_filename = args.xlsx_out.clone().unwrap_or_else(|| default_xlsx_path(args));
+    let mut xlsx_error: Option<xlsx::XlsxError> = None;
+    let mut xlsx_writer = if args.generate_xlsx {
+        match open_output_file(&xlsx_filename, args.force)
+            .map_err(xlsx::XlsxError::IoError)
+            .and_then(|file| xlsx::XlsxWriter::new(file, &data_header(args, pool)))
         {
-            if let Err(e) = file.write_all(svg.as_bytes()) {
-                println!("Error: Failed to Write SVG [{}] to Disk.", path);
-                println!("Reason: {}", e);
+            Ok(writer) => Some(writer),
+            Err(e) => {
+                xlsx_error = Some(e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // One locked buffered handle for the verbose lines instead of a lock and flush per println! nothing else prints inside the loop
+    // The trace is diagnostic so it goes to stderr unless it is part of the HTML output
+    let mut verbose_out: BufWriter<Box<dyn Write>> = BufWriter::new(if output_to_stdout {
+        Box::new(std::io::stdout().lock())
+    } else {
+        Box::new(std::io::stderr().lock())
+    });
+    let mut progress = Progress::new("Simulating", args, output_to_stdout);
+    let mut result = calculate_staked_pool_with(pool, &args.simulation_options(), |record| {
+        let day = record.day;
+        progress.update(day, record.last_day, "Day");
+        let writes_row = args.csv_granularity.writes_row(day, record.last_day, pool.epoch_in_days);
+        cumulative_rewards += record.credited;
+        if let (true, Some(writer)) = (writes_row, csv_writer.as_mut()) {
+            let row = data_row(pool, args, record, args.csv_dialect, cumulative_rewards, rewards_since_row);
+            csv_lines += 1;
+            let row = writeln!(writer, "{}", args.csv_dialect.row(&row));
+            if let Err(e) = row {
+                csv_error = Some(e);
+                csv_error_line = Some(csv_lines);
+                csv_writer = None; // Stop writing rows once the file is broken
+            }
+        }
+        if let (true, Some(writer)) = (writes_row, xlsx_writer.as_mut()) {
+            // Full precision with a decimal point whatever the CSV dialect so every number reads back into a numeric cell
+            let row = data_row(pool, args, record, XLSX_DIALECT, cumulative_rewards, rewards_since_row);
+            if let Err(e) = writer.write_row(&row) {
+                xlsx_error = Some(e);
+                xlsx_writer = None;
+            }
+        }
+        if writes_row {
+            rewards_since_row = 0.0;
+        }
+
+        rewards_since_row += record.credited;
+        // The trace follows --granularity too so -v stays readable over long horizons
+        if args.verbose && writes_row {
+            let pay_day_label = match record.pay_day {
+                PayDay::No => Cow::Borrowed("No"),
+                PayDay::Skipped => Cow::Borrowed("Skipped (Undelegated)"),
+                PayDay::Paid { gross, net, saturation, .. } if saturation < 1.0 => Cow::Owned(format!(
+                    "Yes, Saturation {} Gross {} ADA Net {} ADA", // Shows the saturation penalty on every pay day
+                    saturation, format_ada(gross, args.usd_format.rounding), format_ada(net, args.usd_format.rounding)
+                )),
+                PayDay::Paid { gross, fees, net, .. } if fees > 0.0 => {
+                    Cow::Owned(format!("Yes, Gross {} ADA Net {} ADA", format_ada(gross, args.usd_format.rounding), format_ada(net, args.usd_format.rounding))) // Shows the fee drag on every pay day
+                }
+                PayDay::Paid { .. } => Cow::Borrowed("Yes"),
+            };
+            // Pay days stand out from the days in between on a color terminal
+            let pay_day_label = match record.pay_day {
+                PayDay::Paid { .. } if args.color => Cow::Owned(pay_day_label.cyan().to_string()),
+                _ => pay_day_label,
+            };
+            // Absolute epoch of each pay day with epoch_anchor_date
+            let epoch_label = match (record.pay_day, pool.epoch_number(record.date)) {
+                (PayDay::No, _) | (_, None) => String::new(),
+                (_, Some(epoch)) => format!(" [Epoch {}]", epoch),
+            };
+            // With reward_lag_epochs the reward earned on a pay day is not the one that lands
+            let lag_label = match record.pay_day {
+                PayDay::Paid { net, .. } if pool.reward_lag_epochs > 0 => format!(
+                    " [Earned: {} ADA Credited: {} ADA In Flight: {} ADA]",
+                    format_ada(net, args.usd_format.rounding), format_ada(record.credited, args.usd_format.rounding), format_ada(record.in_flight, args.usd_format.rounding)
+                ),
+                PayDay::Skipped if record.credited > 0.0 => format!(
+                    " [Credited: {} ADA In Flight: {} ADA]",
+                    format_ada(record.credited, args.usd_format.rounding), format_ada(record.in_flight, args.usd_format.rounding)
+                ),
+                _ => String::new(),
+            };
+            // Running totals keep what was put in apart from what staking earned
+            let contribution_label = match record.contribution {
+                Some((usd, bought)) => format!(
+                    " [Contribution: {} {} {} ADA Contributed So Far: {} ADA Rewards So Far: {} ADA]",
+                    args.usd_format.currency.show(usd),
+                    if pool.contribution.is_some_and(|c| c.amount_usd == 0.0) { "Added" } else { "Bought" },
+                    format_ada(bought, args.usd_format.rounding),
+                    format_ada(record.start_contributed_ada + bought, args.usd_format.rounding),
+                    format_ada(record.ada + record.start_withdrawn_ada - pool.ada - record.start_contributed_ada - bought, args.usd_format.rounding)
+                ),
+                None => String::new(),
+            };
+            let price_event_label = if record.price_event {
+                format!(" [{}]", pool.price_event_labels(day, record.price).join(", "))
+            } else {
+                String::new()
+            };
+            // Output errors are ignored like println! would only without the panic on a closed pipe
+            writeln!(
+                verbose_out,
+                "Day {} ({}): {} [Pay Day: {}]{}{}{}{}",
+                day,
+                record.date,
+                Holding { ada: record.ada, price: record.price, format: args.usd_format },
+                pay_day_label,
+                epoch_label,
+                lag_label,
+                contribution_label,
+                price_event_label
+            )
+            .ok();
+            if output_to_stdout {
+                writeln!(verbose_out, "<br>").ok();
+            }
+            if args.unbuffered {
+                verbose_out.flush().ok();
             }
+        }
+    });
+    verbose_out.flush().ok();
+    progress.finish();
+
+    if args.generate_csv {
+        if let Some(writer) = csv_writer.as_mut() {
+            if let Err(e) = writer.flush() {
+                csv_error = Some(e);
+            }
+        }
+        if let Some(e) = &csv_error {
+            let at_line = csv_error_line.map(|line| format!(" At Line {} (Day Row {})", line, line - 1)).unwrap_or_default();
+            print_diagnostic(&format!("Error: Failed to Write CSV [{}] to Disk{}.", &csv_filename, at_line), output_to_stdout);
+            print_diagnostic(&format!("Reason: {}", describe_write_error(&csv_filename, e)), output_to_stdout);
         } else {
-            println!("Error: Failed to Write SVG [{}] to Disk.", path);
-            println!("Reason: Unknown");
+            print_result_line(&format!("Saved CSV as {} to Disk.", absolute_path(&csv_filename)), output_to_stdout);
+            if args.csv_columns == CsvColumns::Full {
+                print_result_line(
+                    &format!("Rewards Paid: {} ADA (The Last Cumulative Rewards Of The CSV)", format_ada(result.credited_rewards, args.usd_format.rounding)),
+                    output_to_stdout,
+                );
+            }
+        }
+    }
+    if args.generate_xlsx {
+        if let Some(writer) = xlsx_writer.take() {
+            let (inputs, results) = xlsx_summary(pool, &result, args);
+            let total_column = data_header(args, pool)
+                .split(',')
+                .position(|column| column == args.usd_format.currency.column("Total"));
+            if let Err(e) = writer.finish(&inputs, &results, total_column) {
+                xlsx_error = Some(e);
+            }
+        }
+        match &xlsx_error {
+            Some(e) => {
+                let reason = match e {
+                    xlsx::XlsxError::IoError(e) => describe_write_error(&xlsx_filename, e),
+                    e => e.to_string(),
+                };
+                print_diagnostic(&format!("Error: Failed to Write Workbook [{}] to Disk.", &xlsx_filename), output_to_stdout);
+                print_diagnostic(&format!("Reason: {}", reason), output_to_stdout);
+            }
+            None => print_result_line(&format!("Saved Workbook as {} to Disk.", absolute_path(&xlsx_filename)), output_to_stdout),
         }
     }
+    result.write_failed = csv_error.is_some() || xlsx_error.is_some();
+    result
 }
 
-fn execute_json(buffer: &String, args: &CommandOptions, output_to_stdout : bool) -> Option<StakedCardanoPoolResult> {
-    let pool_info: StakedCardanoPool = serde_json::from_str(&buffer).unwrap();
-    if output_to_stdout {
-        println!("<div class='output'>"); // Refactor this to a function
+// Rows of the workbook's Summary sheet money is in the --currency figures like the Daily sheet
+fn xlsx_summary(
+    pool: &StakedCardanoPool,
+    result: &StakedCardanoPoolResult,
+    args: &CommandOptions,
+) -> (xlsx::SummaryRows, xlsx::SummaryRows) {
+    use xlsx::Cell;
+    let currency = args.usd_format.currency;
+    let money = |usd: f64| {
+        let decimals = if currency.decimals > 0 { format!(".{}", "0".repeat(currency.decimals)) } else { String::new() };
+        Cell::Number(currency.convert(usd), format!("\"{}\"#,##0{}", currency.symbol, decimals))
+    };
+    let ada = |amount: f64| Cell::Number(amount, String::from("#,##0.000000"));
+    let percent = |fraction: f64| Cell::Number(fraction, String::from("0.00%"));
+    let whole = |value: f64| Cell::Number(value, String::from("General"));
+    let inputs = vec![
+        ("ADA", ada(pool.ada)),
+        ("Initial Price", money(pool.initial_price)),
+        ("Annual Yield", percent(pool.annual_yield)),
+        (
+            "Price Model",
+            Cell::Text(if pool.daily_prices.is_empty() {
+                format!("{} daily multiplier", pool.describe_price_yield())
+            } else {
+                format!("Backtest of {}", pool.describe_price_yield())
+            }),
+        ),
+        ("Epoch Length (Days)", whole(pool.epoch_in_days as f64)),
+        ("Years Holding", whole(pool.years_holding)),
+        ("Start Date", Cell::Date(pool.start())),
+    ];
+    let mut results = vec![
+        ("Final ADA", ada(result.final_ada_amount)),
+        ("Final Price", money(result.final_ada_price)),
+        ("Final Total", money(result.total())),
+        ("Money Put In", money(result.initial_investment + result.contributed_usd)),
+        ("Gainz (ROI)", percent(result.net_return_as_percentage(pool) / 100.0)),
+        ("Value Multiple", Cell::Number(result.value_multiple(pool), String::from("0.00\"x\""))),
+        ("CAGR", percent(result.cagr(pool))),
+        ("ADA CAGR", percent(result.ada_cagr(pool))),
+        ("Rewards Paid (ADA)", ada(result.credited_rewards)),
+        ("Holding Instead Ends At", money(result.hold_total(pool))),
+    ];
+    if pool.annual_inflation != 0.0 {
+        results.push(("Real Total (Today's Dollars)", money(result.real_total(pool))));
+        results.push(("Real CAGR", percent(result.real_cagr(pool))));
     }
-    let result = calculate_staked_pool(&pool_info, &args, output_to_stdout);
-    println!(
-        "Final Result: {} ADA @ ${:.2} = ${:.2} Gainz: {:.2}%",
-        result.final_ada_amount,
-        result.final_ada_price,
-        result.total(),
-        100.0 + result.yield_as_percentage(&pool_info)
-    );
-    if output_to_stdout {
-        println!("<br>"); // Refactor this to a function
-        println!("</div>"); // Refactor this to a function
-        generate_graph(None, &result, output_to_stdout);
+    (inputs, results)
+}
+
+// Pool fields given on the command line they replace the values read from the file
+#[derive(Debug, Clone, Copy, Default)]
+struct PoolOverrides {
+    ada: Option<f64>,
+    initial_price: Option<f64>,
+    annual_yield: Option<f64>,
+    price_yield: Option<f64>,
+    epoch_in_days: Option<u64>,
+    years_holding: Option<f64>,
+}
+
+impl PoolOverrides {
+    fn is_empty(&self) -> bool {
+        self.ada.is_none()
+            && self.initial_price.is_none()
+            && self.annual_yield.is_none()
+            && self.price_yield.is_none()
+            && self.epoch_in_days.is_none()
+            && self.years_holding.is_none()
     }
 
-    if output_to_stdout {
-        None
+    // The run header line with the values the run uses the ones from flags are marked with (flag)
+    // and without a pool file the rest are marked (example)
+    fn describe(&self, pool: &StakedCardanoPool, from_example: bool) -> String {
+        let mark = |overridden: bool| match (overridden, from_example) {
+            (true, _) => " (flag)",
+            (false, true) => " (example)",
+            (false, false) => "",
+        };
+        format!(
+            "Pool: ada {}{} initial_price {}{} annual_yield {}{} price_yield {}{} epoch_in_days {}{} years_holding {}{}",
+            pool.ada,
+            mark(self.ada.is_some()),
+            pool.initial_price,
+            mark(self.initial_price.is_some()),
+            pool.annual_yield,
+            mark(self.annual_yield.is_some()),
+            pool.daily_price_yield(),
+            mark(self.price_yield.is_some()),
+            pool.epoch_in_days,
+            mark(self.epoch_in_days.is_some()),
+            pool.years_holding,
+            mark(self.years_holding.is_some()),
+        )
     }
-    else {
-        Some(result)
+
+    fn apply(&self, pool: &mut StakedCardanoPool) {
+        if let Some(ada) = self.ada {
+            pool.ada = ada;
+        }
+        if let Some(initial_price) = self.initial_price {
+            pool.initial_price = initial_price;
+        }
+        if let Some(annual_yield) = self.annual_yield {
+            pool.annual_yield = annual_yield;
+        }
+        // --price-yield replaces the pool's price growth whichever field it was given in
+        if let Some(price_yield) = self.price_yield {
+            pool.price_yield = price_yield;
+            pool.daily_price_growth_pct = None;
+            pool.annual_price_growth_pct = None;
+        }
+        if let Some(epoch_in_days) = self.epoch_in_days {
+            pool.epoch_in_days = epoch_in_days;
+        }
+        if let Some(years_holding) = self.years_holding {
+            pool.years_holding = years_holding;
+        }
     }
 }
 
-fn main() {
-    let args = &get_command_options();
-    if args.generate_csv {
-        println!("CSV will be saved in current working directory.");
+#[derive(Debug,Clone)]
+struct CommandOptions {
+    verbose: bool,        // Show all possible output to standard output i.e. terminal
+    generate_csv: bool,   // Generate the data in csv output for data science purposes
+    generate_graph: bool, // Generate a graph svg for data visualization purposes,
+    json_option: Option<String>,
+    csv_out: Option<String>, // CSV path used instead of raw_ada_calc_data_<timestamp>.csv
+    generate_xlsx: bool,     // Also write the run as an Excel workbook with a Summary and a Daily sheet
+    xlsx_out: Option<String>, // Workbook path used instead of ada_calc_data_<timestamp>.xlsx
+    output_dir: Option<String>, // Directory the default file names and relative --csv-out and --svg-out paths are written under
+    svg_out: Option<String>, // SVG path used instead of ada_growth_graph_<timestamp>.svg
+    graph_format: GraphFormat, // File format of the written graph the HTML output always embeds the SVG
+    report: Option<ReportFormat>, // Report written after the run next to the CSV and graph
+    graph_width: Option<u32>, // Width of the PNG in pixels the height keeps the aspect ratio
+    graph_size: Option<(u32, u32)>, // Width and height of the PNG the graph is fitted and centered inside
+    graph_theme: GraphTheme,
+    graph_layout: GraphLayout,
+    graph_scale: GraphScale,
+    graph_title: Option<String>, // Replaces the default graph title
+    graph_axis: (Option<String>, Option<String>), // X and Y axis labels from --graph-axis None keeps the default
+    force: bool,            // Overwrite existing CSV and SVG files instead of failing
+    input_path: String,     // Pool JSON read when no -p JSON is given
+    input_paths: Vec<String>, // Every -i given the main run goes through them in order
+    output_stem: Option<String>, // File stem of the pool added to the default CSV and graph names
+    pool_overrides: PoolOverrides, // Pool fields from --ada, --initial-price and the like
+    pool_from_args: bool,   // No pool.json -i or -p was given so the pool is EXAMPLE_POOL_JSON with the overrides applied
+    allow_extreme: bool,     // Suppress the unrealistic result warning
+    extreme_threshold: f64, // Final total in USD above which the result is considered unrealistic
+    color: bool,            // Colorize the human readable terminal output never used for HTML or CSV
+    pretty: bool,           // Print the final result as a boxed table of the inputs and results with thousands separators
+    compound_exact: bool,   // Use the compounding-equivalent per epoch rate instead of a simple slice of the annual yield
+    explain_fees: bool,     // Print the gross rewards, pool fees and net rewards breakdown
+    summary_table: bool,    // Print the year by year table after the final result
+    timestamp_format: TimestampFormat, // How the timestamp in generated file names is written
+    date_format: Option<String>, // chrono format the pool's dates are written in tried before the built in DATE_FORMATS
+    compare_metric: Option<CompareMetric>, // Rank the scenario and wallet tables by this best first (input order when not given)
+    compare_simple: bool,   // Also run the simulation without reinvesting rewards and report the difference
+    simple_interest: bool,  // Internal toggle rewards are not reinvested (set for the comparison run not a command option)
+    validate_file: Option<String>, // Set by the validate subcommand only check this file and exit
+    convert_files: Option<(String, String)>, // Set by the convert subcommand input and output config paths
+    summary_only: bool,     // Skip the day by day loop and only print the final result using the closed form
+    skip_epochs: u64,       // Epochs compounded before reporting starts the state after them becomes the baseline
+    denominate_in: Option<(String, f64)>, // Reference asset unit and its USD price the final total is also reported in
+    fail_on_write_error: bool, // Exit with EXIT_WRITE_ERROR when a CSV or SVG could not be written
+    price_cache_ttl: u64,   // Seconds a cached live price is reused before fetching again
+    refresh_price: bool,    // Fetch the live price even when the cache is fresh
+    offline: bool,          // Only use the cached live price never the network
+    min_yield: Option<f64>, // Lower bound applied to annual_yield
+    max_yield: Option<f64>, // Upper bound applied to annual_yield
+    bundle_path: Option<String>, // Write the config, result summary and graph to this JSON bundle
+    dry_run: bool,          // Validate and print the plan then stop before simulating or writing anything
+    open_bundle_file: Option<String>, // Set by the open-bundle subcommand display this bundle and exit
+    bench_file: Option<String>, // Set by the bench subcommand time the daily loop against the closed form for this pool and exit
+    init_file: Option<(String, bool, bool)>, // Set by the init subcommand file to create whether to ask for each value and --force
+    graph_csv: Option<(String, Option<String>)>, // Set by the graph subcommand CSV to read and the graph path from --out
+    fetch_price: Option<String>, // Set by the fetch-price subcommand fiat code to quote next to USD
+    ndjson: bool,           // Read one pool JSON per stdin line and write one JSON result per line
+    json_output: bool,      // Print the result as one JSON object instead of the human readable output
+    include_history: bool,  // Add the per day ADA and price history to the --json output
+    graph_breakeven_line: bool, // Draw the initial investment as a flat line on the graph
+    graph_include_real: bool,   // Draw the total in today's dollars when the pool has annual_inflation
+    graph_wallets: bool,        // Draw each wallet's total on the portfolio graph
+    graph_annotations: bool,    // Payout dots on the total, year ticks, the final value and the inputs in the title
+    graph_max_points: usize,    // Most points drawn per graph line longer histories keep each bucket's lowest and highest value
+    graph_overlay_events: bool, // Draw a labelled vertical line at each lump sum, slash, delegation change and the price cap
+    csv_granularity: CsvGranularity, // How often a CSV row is written
+    csv_columns: CsvColumns,        // Every column or only Day,ADA,Price,Total
+    csv_dialect: CsvDialect,
+    sanity_report: bool,    // Collect every warning into one section at the end of the run
+    no_footer: bool,        // Skip the assumptions footer after the result
+    no_progress: bool,      // Never draw the progress line even on a terminal
+    watch: Option<std::time::Duration>, // Set by --watch how often the pool file is checked for changes
+    ascii: bool,            // Plain ADA/USD/!! text instead of the Unicode symbols
+    warn_negative_yield: bool, // Warn with the cause and max drawdown when the run ends below cost
+    unbuffered: bool,       // Flush every verbose line as it is written
+    reward_history: Option<String>, // CSV of past (epoch, reward_ada) rows added to the starting ADA
+    price_history: Option<String>, // CSV of date,price rows replayed instead of the price growth
+    use_realized_yield: bool, // Replace annual_yield with the yield realized in the reward history
+    compare_tax: Option<(f64, f64)>, // Income tax rate on rewards and capital gains rate for the tax scenario table
+    sensitivity: Option<f64>, // Percent each input is moved up and down for the sensitivity table
+    break_even: Option<f64>,  // Percent the price drops on day 1 for the break-even analysis
+    tax_report: bool, // Write every reward payout with its price (the cost basis) to a CSV and show the reward income per year
+    dump_series: Option<String>, // Write the ADA, price and total history as JSON arrays to this file
+    record_path: Option<String>, // Ledger a summary of the run is appended to set by --record
+    history_file: Option<String>, // Set by the history subcommand ledger to print as a table
+    completions: Option<clap_complete::Shell>, // Set by the completions subcommand shell to print the completion script for
+    usd_format: UsdFormat,  // How USD figures are rounded and how many decimals are shown
+    graph_decimals: Option<usize>, // Decimals on the graph value axis when --precision is given (poloto picks otherwise)
+    monte_carlo: Option<u32>, // Number of random price runs to summarize
+    confidence: f64,          // Percent of the Monte Carlo runs between the outer percentiles reported and drawn
+    sweep: Vec<String>,       // FIELD=START:END:STEP ranges from --sweep one or two of them
+    max_sweep_runs: usize,    // Refuse grids with more combinations than this
+    seed: Option<u64>,      // Seed for the Monte Carlo runs
+    target: Option<Target>, // Run until the total or ADA reaches this instead of for years_holding
+    target_max_years: f64,  // Give up on the target after this many years
+    solve_initial_ada: Option<f64>, // Find the starting ADA that ends years_holding at this total in USD
+}
+
+impl CommandOptions {
+    // The per day history is only kept when something consumes it
+    fn records_history(&self) -> bool {
+        self.generate_graph || self.dump_series.is_some() || self.include_history || self.report == Some(ReportFormat::Html)
     }
-    if let Some(buffer) = &args.json_option {
-        execute_json(buffer, args, true);
-    } else if let Ok(buffer) = &read_to_string("pool.json") {
-        let result = execute_json(buffer, args, false).unwrap();
-        if args.generate_graph {
-            generate_graph(Some(format!("ada_growth_graph_{}.svg", get_epoch_ms())), &result, false);
-            println!("Generated Graph in SVG Format Under ada_growth_graph_<timestamp>.svg");
+
+    fn simulation_options(&self) -> SimulationOptions {
+        SimulationOptions {
+            compound_exact: self.compound_exact,
+            simple_interest: self.simple_interest,
+            skip_epochs: self.skip_epochs,
+            record_history: self.records_history(),
+            record_payouts: self.tax_report,
+            target: None, // Resolved into years_holding before the run see describe_target
+            rounding: self.usd_format.rounding,
         }
-    } else {
-        println!(
-            "Failed to find pool.json in current working directory or through command option!"
-        );
+    }
+}
+
+// Every option and subcommand shared by the parser and the completions subcommand
+fn build_command() -> Command<'static> {
+    run_options(
+        Command::new("ADA Staking CLI Tool")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author("zulrah")
+            .about("ADA Staking Calculator For Data Analysis and Visualization Purposes")
+            .after_help(EXIT_CODES_HELP),
+    )
+    .subcommand(run_options(
+        Command::new("simulate")
+            .about("Run the pool JSON and print the results (running without a subcommand is deprecated)")
+            .after_help(EXIT_CODES_HELP),
+    ))
+    .subcommand(
+        Command::new("graph")
+            .about("Draw the growth graph from a CSV written by --generate_csv without simulating again (graph options go before graph)")
+            .arg(arg!(--"from-csv" <FILE> "CSV written by --generate_csv").value_hint(ValueHint::FilePath))
+            .arg(
+                arg!(-o --out <PATH> "Graph file to write instead of --svg-out or ada_growth_graph_<timestamp>.svg")
+                    .required(false)
+                    .value_hint(ValueHint::FilePath),
+            ),
+    )
+    .subcommand(
+        Command::new("fetch-price")
+            .about("Print the current ADA price from CoinGecko (cached for --price-cache-ttl minutes honoring --offline and --refresh-price)")
+            .arg(arg!([CODE] "Fiat code to quote next to USD like eur (default usd)")),
+    )
+    .subcommand(
+        Command::new("history")
+            .about("Print the runs --record appended to the history ledger as a table")
+            .arg(arg!([FILE] "Ledger to read (default is --record-file or ada_calc_history.csv)").value_hint(ValueHint::FilePath)),
+    )
+    .subcommand(
+        Command::new("validate")
+            .about("Check a pool JSON file without running the simulation exits 0 when valid and prints nothing")
+            .arg(arg!(<FILE> "Pool JSON file to validate").value_hint(ValueHint::FilePath)),
+    )
+    .subcommand(
+        Command::new("convert")
+            .about("Convert a pool config between JSON, TOML and YAML (format picked from the file extension)")
+            .arg(arg!(<IN> "Pool config to read").value_hint(ValueHint::FilePath))
+            .arg(arg!(<OUT> "Pool config to write").value_hint(ValueHint::FilePath)),
+    )
+    .subcommand(
+        Command::new("open-bundle")
+            .about("Show the config and results stored in a bundle written by --bundle")
+            .arg(arg!(<FILE> "Bundle JSON file").value_hint(ValueHint::FilePath)),
+    )
+    .subcommand(
+        Command::new("init")
+            .about("Write a starter pool JSON, TOML or YAML file (format picked from the extension)")
+            .arg(arg!([FILE] "File to create (default is pool.json)").value_hint(ValueHint::FilePath))
+            .arg(arg!(-i --interactive "Ask for each value with an explanation instead of writing the example"))
+            .arg(arg!(--defaults "Write the example without asking (what init does without --interactive)").conflicts_with("interactive"))
+            .arg(arg!(--force "Replace the file when it already exists")),
+    )
+    .subcommand(
+        Command::new("bench")
+            .about("Time the day by day loop against the closed form over several horizons and print the speedup")
+            .arg(arg!([FILE] "Pool JSON file to benchmark (default is pool.json)").value_hint(ValueHint::FilePath)),
+    )
+    .subcommand(
+        Command::new("completions")
+            .about("Print the completion script for bash, zsh, fish or powershell to stdout")
+            .arg(arg!(<SHELL> "Shell to write the script for").possible_values(["bash", "zsh", "fish", "powershell"])),
+    )
+}
+
+// Options of a pool run shared by simulate and the deprecated run without a subcommand
+fn run_options(command: Command<'static>) -> Command<'static> {
+    command
+    .arg(arg!(
+        -v --verbose ... "Show Full Output to Terminal/Output"
+    ))
+    .arg(arg!(
+        -g --generate_csv ... "Generate a CSV file of the calculated data output will be raw_ada_calc_data_<timestamp>.csv"
+    ).visible_alias("generate-csv"))
+    .arg(arg!(
+        -G --generate_graph ... "Generate a line graph showing two data points the ada over time and price over time ada_growth_graph_<timestamp>.svg"
+    ).visible_alias("generate-graph"))
+    .arg( arg!(
+        -p --pool_json <JSON> "Pass JSON input via command option"
+    ).required(false).visible_alias("pool-json"))
+    .arg(arg!(
+        -i --input <PATH> "Pool JSON file to read when -p is not given (default is pool.json in the current directory) repeat it to run several pools or pass - to read the JSON from stdin"
+    ).required(false).multiple_occurrences(true).visible_alias("pool").value_hint(ValueHint::FilePath))
+    .arg(arg!(
+        --ada <ADA> "ADA staked overrides the pool file (with no -i or -p the other fields default to the init example)"
+    ).required(false).validator(is_number))
+    .arg(arg!(
+        --"initial-price" <USD> "Starting price in USD overrides the pool file"
+    ).required(false).validator(is_number))
+    .arg(arg!(
+        --"annual-yield" <FRACTION> "Yearly staking reward as a fraction (0.05 is 5%) overrides the pool file"
+    ).required(false).validator(is_number))
+    .arg(arg!(
+        --"price-yield" <MULTIPLIER> "Daily price multiplier (1.0 keeps the price flat) overrides the pool file's price growth"
+    ).required(false).validator(is_number))
+    .arg(arg!(
+        --"epoch-in-days" <DAYS> "Days between pay days overrides the pool file"
+    ).required(false).validator(is_whole_number::<u64>).alias("epoch-days"))
+    .arg(arg!(
+        --"years-holding" <YEARS> "Years staked overrides the pool file"
+    ).required(false).validator(is_number).alias("years"))
+    .arg(arg!(
+        --"csv-out" <PATH> "Write the CSV to this path instead of raw_ada_calc_data_<timestamp>.csv"
+    ).required(false).requires("generate_csv").alias("csv-name").value_hint(ValueHint::FilePath))
+    .arg(arg!(
+        --"generate-xlsx" "Write an Excel workbook of the run with a Summary sheet, a chart and the CSV rows on a Daily sheet ada_calc_data_<timestamp>.xlsx"
+    ).alias("xlsx"))
+    .arg(arg!(
+        --"xlsx-out" <PATH> "Write the workbook to this path instead of ada_calc_data_<timestamp>.xlsx"
+    ).required(false).requires("generate-xlsx").value_hint(ValueHint::FilePath))
+    .arg(arg!(
+        --"svg-out" <PATH> "Write the graph to this path instead of ada_growth_graph_<timestamp>.svg"
+    ).required(false).alias("svg-name").value_hint(ValueHint::FilePath))
+    .arg(arg!(
+        --"output-dir" <DIR> "Write the CSV, graph, workbook and reports under this directory (created if missing) relative --csv-out, --svg-out and --xlsx-out paths too"
+    ).required(false).value_hint(ValueHint::DirPath))
+    .arg(arg!(
+        --"graph-format" <FORMAT> "File format of the graph svg (default), png or both"
+    ).required(false).possible_values(["svg", "png", "both"]))
+    .arg(arg!(
+        --report <FORMAT> "Also write a report of the inputs, results and yearly breakdown md links the graph and html embeds it"
+    ).required(false).possible_values(["md", "html"]))
+    .arg(arg!(
+        --"graph-width" <PIXELS> "Width of the PNG graph in pixels the height keeps the aspect ratio"
+    ).required(false).validator(is_count::<u32>))
+    .arg(arg!(
+        --"graph-size" <SIZE> "Size of the PNG graph as WIDTHxHEIGHT (default 1280x720)"
+    ).required(false).validator(is_graph_size).conflicts_with("graph-width"))
+    .arg(arg!(
+        --theme <THEME> "Color theme of the graph light (default) or dark"
+    ).required(false).possible_values(["light", "dark"]))
+    .arg(arg!(
+        --"graph-layout" <LAYOUT> "indexed (default) draws each line indexed to 100 at the start, combined the absolute values on one scale, split one graph per line"
+    ).required(false).possible_values(["indexed", "combined", "split"]))
+    .arg(arg!(
+        --"graph-scale" <SCALE> "linear (default) or log to draw the growth graph on a log10 value axis"
+    ).required(false).possible_values(["linear", "log"]))
+    .arg(arg!(
+        --"graph-title" <TITLE> "Title of the graph instead of Cardano Staking Growth"
+    ).required(false))
+    .arg(arg!(
+        --"graph-axis" <LABELS> "Axis labels of the graph separated by a comma either side may be left empty to keep the default"
+    ).required(false))
+    .arg(arg!(
+        --force "Overwrite the CSV and SVG if they already exist"
+    ))
+    .arg(arg!(
+        --pretty "Show the final result as an aligned table of the inputs and results with thousands separators"
+    ))
+    .arg(arg!(
+        --"allow-extreme" "Do not warn when the final total is unrealistically large"
+    ))
+    .arg(arg!(
+        --"extreme-threshold" <USD> "Final total in USD above which the result is flagged as unrealistic (default is roughly global GDP)"
+    ).required(false).validator(is_number))
+    .arg(arg!(
+        --color "Force colored terminal output even when stdout is not a terminal"
+    ))
+    .arg(arg!(
+        --"no-color" "Disable colored terminal output (the NO_COLOR environment variable is also honored)"
+    ).conflicts_with("color"))
+    .arg(arg!(
+        --"explain-fees" "Show gross rewards, fees paid to the pool operator (margin + fixed fee) and net rewards"
+    ))
+    .arg(arg!(
+        --"no-summary-table" "Leave out the year by year table printed after the final result"
+    ))
+    .arg(arg!(
+        --"timestamp-format" <FORMAT> "Timestamp used in generated file names unix milliseconds (default) or iso8601"
+    ).required(false).possible_values(["unix", "iso8601"]))
+    .arg(arg!(
+        --"date-format" <FORMAT> "chrono format like %d/%m/%Y the pool's start_date and epoch_anchor_date are written in (YYYY-MM-DD, MM/DD/YYYY and DD.MM.YYYY always work)"
+    ).required(false))
+    .arg(arg!(
+        --"compare-metric" <METRIC> "Rank the scenario and wallet comparison tables best first by total, yield (gain against the money put in) or cagr"
+    ).required(false).possible_values(["total", "yield", "cagr"]))
+    .arg(arg!(
+        --"compare-compounding-vs-simple" "Also compute the result without reinvesting rewards and show how much compounding adds"
+    ))
+    .arg(arg!(
+        --"compound-exact" "Pay each epoch (1 + annual_yield)^(1/epochs_per_year) - 1 so a full year compounds to exactly annual_yield"
+    ))
+    .arg(arg!(
+        --"skip-epochs" <N> "Compound through the first N epochs before reporting and use the state after them as the baseline"
+    ).required(false).validator(is_whole_number::<u64>))
+    .arg(arg!(
+        --"denominate-in" <ASSET> "Also report the final total in units of a reference asset usd (default), btc or gold (troy ounces)"
+    ).required(false).possible_values(["usd", "btc", "gold"]))
+    .arg(arg!(
+        --"reference-price" <USD> "USD price of one unit of the --denominate-in asset"
+    ).required(false).validator(is_number))
+    .arg(arg!(
+        --currency <CODE> "Show every USD figure in another fiat usd (default), eur, gbp, jpy, chf, cad or aud (the JSON stays in USD)"
+    ).required(false).possible_values(["usd", "eur", "gbp", "jpy", "chf", "cad", "aud"]))
+    .arg(arg!(
+        --"fx-rate" <RATE> "Units of the --currency one USD buys instead of looking the rate up"
+    ).required(false).validator(is_number).requires("currency"))
+    .arg(arg!(
+        --"price-cache-ttl" <MINUTES> "Minutes a cached live price is reused before fetching again (default is 15)"
+    ).required(false).validator(is_whole_number::<u64>))
+    .arg(arg!(
+        --"refresh-price" "Fetch the live price even when the cached one is still fresh"
+    ))
+    .arg(arg!(
+        --offline "Use the cached live price whatever its age and fail if there is none"
+    ).conflicts_with("refresh-price"))
+    .arg(arg!(
+        --"on-write-error" <MODE> "fail (default) exits with code 3 when a CSV or SVG cannot be written warn only prints the error"
+    ).required(false).possible_values(["fail", "warn"]))
+    .arg(arg!(
+        --"min-yield" <FRACTION> "Clamp annual_yield to at least this value (a warning is printed when clamping)"
+    ).required(false).validator(is_number))
+    .arg(arg!(
+        --"max-yield" <FRACTION> "Clamp annual_yield to at most this value (a warning is printed when clamping)"
+    ).required(false).validator(is_number))
+    .arg(arg!(
+        --bundle <FILE> "Write the effective config, result summary and graph (with --generate_graph) to one JSON file"
+    ).required(false).value_hint(ValueHint::FilePath))
+    .arg(arg!(
+        --"dump-series" <FILE> "Write the per day ADA, price and total history as a JSON object of arrays"
+    ).required(false).value_hint(ValueHint::FilePath))
+    .arg(arg!(
+        --record "Append a one line summary of this run to the history ledger (see ada_calc history)"
+    ))
+    .arg(arg!(
+        --"record-file" <PATH> "Ledger --record appends to and history reads (default is ada_calc_history.csv in the current directory)"
+    ).required(false).value_hint(ValueHint::FilePath))
+    .arg(arg!(
+        --"graph-breakeven-line" "Add a flat line at the initial investment to the graph"
+    ))
+    .arg(arg!(
+        --"graph-include-real" "Add the inflation adjusted total to the graph (needs annual_inflation in the pool)"
+    ))
+    .arg(arg!(
+        --"graph-annotations" "Mark payouts on the total line and year boundaries, label the final value and put the main inputs in the graph title"
+    ))
+    .arg(arg!(
+        --"graph-max-points" <N> "Most points drawn per graph line longer runs keep the low and high of each stretch so the SVG stays small (default 2000)"
+    ).required(false).validator(is_count::<usize>))
+    .arg(arg!(
+        --"graph-wallets" "With a wallets pool also draw each wallet's total under the portfolio total"
+    ))
+    .arg(arg!(
+        --"graph-overlay-events" "Mark lump sums, slash events, delegation changes, the price cap and pool saturation on the graph with labelled vertical lines"
+    ))
+    .arg(arg!(
+        --"csv-granularity" <PERIOD> "How often a CSV row and -v line is written day (default), epoch (every pay day), week, month or year (daily and yearly also work)"
+    ).required(false).possible_values(["day", "daily", "epoch", "week", "month", "year", "yearly"]).alias("granularity"))
+    .arg(arg!(
+        --"csv-columns" <SET> "full (default) writes every column basic only Day,ADA,Price,Total"
+    ).required(false).possible_values(["full", "basic"]))
+    .arg(arg!(
+        --"csv-delimiter" <CHAR> "Character between CSV fields (default ,) for example ; for spreadsheets that use a decimal comma or tab"
+    ).required(false))
+    .arg(arg!(
+        --"csv-decimal-comma" "Write CSV numbers with a decimal comma needs a --csv-delimiter other than ,"
+    ))
+    .arg(arg!(
+        --"csv-precision" <N> "Decimals of the CSV price and USD columns (default every digit)"
+    ).required(false).validator(is_whole_number::<usize>))
+    .arg(arg!(
+        --"csv-ada-precision" <N> "Decimals of the CSV ADA columns (default 6 one lovelace)"
+    ).required(false).validator(is_whole_number::<usize>))
+    .arg(arg!(
+        --"sanity-report" "Collect all warnings about the inputs and result into a Sanity Report at the end"
+    ))
+    .arg(arg!(
+        --"no-footer" "Do not print the assumptions footer after the result"
+    ))
+    .arg(arg!(
+        --"no-progress" "Never show the progress line on stderr (it already hides itself when piped, with -v and with --json)"
+    ))
+    .arg(arg!(
+        --"reward-history" <CSV> "CSV of past epoch,reward_ada rows the rewards are added to ada before projecting forward"
+    ).required(false).value_hint(ValueHint::FilePath))
+    .arg(arg!(
+        --"price-history" <CSV> "Backtest with the date,price rows of this CSV instead of the price growth the run covers the dates in the file"
+    ).required(false).value_hint(ValueHint::FilePath))
+    .arg(arg!(
+        --"use-realized-yield" "Project with the yield realized in --reward-history instead of annual_yield"
+    ).requires("reward-history"))
+    .arg(arg!(
+        --sensitivity "After the run move ada, initial_price, annual_yield and the price growth up and down one at a time and show how far the total moves"
+    ))
+    .arg(arg!(
+        --"sensitivity-pct" <PERCENT> "How far --sensitivity moves each value in percent (default 10)"
+    ).required(false).validator(is_number).requires("sensitivity"))
+    .arg(arg!(
+        --"break-even" "After the run show the day the rewards win back the money put in after an --assume-drop price fall and the yearly price decline the yield makes up for"
+    ))
+    .arg(arg!(
+        --"assume-drop" <PERCENT> "Percent the price falls on day 1 for --break-even after that it follows the pool's price growth (default 0)"
+    ).required(false).validator(is_number).requires("break-even"))
+    .arg(arg!(
+        --"compare-tax-scenarios" "Show the final total after tax with no tax, capital gains only and income tax on rewards side by side"
+    ))
+    .arg(arg!(
+        --"income-tax-rate" <FRACTION> "Income tax rate on rewards for --compare-tax-scenarios (default 0.3)"
+    ).required(false).validator(is_number).requires("compare-tax-scenarios"))
+    .arg(arg!(
+        --"capital-gains-rate" <FRACTION> "Capital gains rate for --compare-tax-scenarios (default 0.15)"
+    ).required(false).validator(is_number).requires("compare-tax-scenarios"))
+    .arg(arg!(
+        --"tax-report" "Write every reward payout with its price (the cost basis) to tax_report_<timestamp>.csv and show the reward income per year"
+    ))
+    .arg(arg!(
+        --rounding <MODE> "How USD figures are rounded to cents and ADA to lovelace half-even (default), half-up or truncate"
+    ).required(false).possible_values(["half-up", "half-even", "truncate"]))
+    .arg(arg!(
+        --precision <N> "Decimal places for USD figures in the output and on the graph value axis (default 2 ADA always shows 6)"
+    ).required(false).validator(is_whole_number::<usize>))
+    .arg(arg!(
+        --"warn-negative-yield" "Warn when the final total is below the money put in and show the cause and max drawdown"
+    ))
+    .arg(arg!(
+        --unbuffered "Flush each verbose line right away (slower but shows progress when piping into another program)"
+    ))
+    .arg(arg!(
+        --ascii "Write ADA, USD and !! instead of the Unicode symbols (picked automatically on consoles that cannot show them)"
+    ))
+    .arg(arg!(
+        --ndjson "Read one pool JSON per line from stdin and write one result (or error) JSON per line to stdout"
+    ).conflicts_with_all(&["pool_json", "generate_csv", "generate-xlsx", "generate_graph", "verbose", "bundle", "dump-series"]))
+    .arg(arg!(
+        --json "Print the result and the pool it was run with as one JSON object and nothing else"
+    ).conflicts_with_all(&[
+        "ndjson",
+        "generate_csv",
+        "generate-xlsx",
+        "generate_graph",
+        "verbose",
+        "bundle",
+        "dump-series",
+        "tax-report",
+        "target-usd",
+        "target-ada",
+        "solve-initial-ada",
+    ]))
+    .arg(arg!(
+        --watch "Keep running and run the pool again every time the pool file is saved (files are overwritten each run Ctrl-C stops it)"
+    ).conflicts_with_all(&["ndjson", "json", "pool_json"]))
+    .arg(arg!(
+        --"watch-interval" <MS> "How often --watch checks the pool file in milliseconds (default 500)"
+    ).required(false).validator(is_whole_number::<u64>).requires("watch"))
+    .arg(arg!(
+        --"dry-run" "Validate the pool and print the days, epochs, starting rewards and files that would be written then stop without writing anything"
+    ).conflicts_with_all(&["ndjson", "json"]))
+    .arg(arg!(
+        --"include-history" "Add the per day amount_historical and price_historical arrays to the --json output"
+    ).requires("json").conflicts_with("summary-only").alias("json-include-history"))
+    .arg(arg!(
+        --"monte-carlo" <N> "Also run the pool N times with random daily price shocks (std-dev price_volatility) and report the spread of totals"
+    ).required(false).validator(is_count::<u32>))
+    .arg(arg!(
+        --"target-usd" <AMOUNT> "Run until the total first reaches AMOUNT USD instead of for years_holding and report the day"
+    ).required(false).validator(is_number).conflicts_with("target-ada"))
+    .arg(arg!(
+        --"target-ada" <AMOUNT> "Run until the ADA first reaches AMOUNT instead of for years_holding and report the day"
+    ).required(false).validator(is_number))
+    .arg(arg!(
+        --"max-years" <YEARS> "Longest run for --target-usd or --target-ada before saying the target was not reached (default 200)"
+    ).required(false).validator(is_number))
+    .arg(arg!(
+        --"solve-initial-ada" <TARGET_USD> "Find the starting ADA needed to end years_holding with a total of TARGET_USD and run the pool with it"
+    ).required(false).validator(is_number).conflicts_with_all(&["target-usd", "target-ada"]))
+    .arg(arg!(
+        --sweep <SPEC> "Run the pool for every value of a field FIELD=START:END:STEP (like annual_yield=0.03:0.06:0.005) repeat once for a grid of two fields"
+    ).required(false).multiple_occurrences(true).max_occurrences(2).conflicts_with_all(&[
+        "monte-carlo",
+        "json",
+        "ndjson",
+        "target-usd",
+        "target-ada",
+        "solve-initial-ada",
+    ]))
+    .arg(arg!(
+        --"max-sweep-runs" <N> "Largest grid --sweep runs before refusing (default 10000)"
+    ).required(false).validator(is_whole_number::<usize>).requires("sweep"))
+    .arg(arg!(
+        --seed <SEED> "Seed for --monte-carlo so the same seed always gives the same numbers"
+    ).required(false).validator(is_whole_number::<u64>).requires("monte-carlo"))
+    .arg(arg!(
+        --confidence <PCT> "Percent of the --monte-carlo runs between the outer percentiles reported and drawn 1 to 99 (default 90 the 5th and 95th)"
+    ).required(false).validator(is_confidence).requires("monte-carlo"))
+    .arg(arg!(
+        --"summary-only" "Only print the final ADA, price and total using the closed form (fastest no per day output)"
+    ).conflicts_with_all(&[
+        "verbose",
+        "generate_csv",
+        "generate-xlsx",
+        "generate_graph",
+        "explain-fees",
+        "compare-compounding-vs-simple",
+        "dump-series",
+        "monte-carlo",
+        "sensitivity",
+        "break-even",
+        "compare-tax-scenarios",
+        "tax-report",
+        "warn-negative-yield",
+        "sanity-report",
+        "skip-epochs",
+        "target-usd",
+        "target-ada",
+        "solve-initial-ada",
+        "denominate-in",
+        "pretty",
+        "report",
+        "bundle",
+        "record",
+    ]))
+}
+
+fn get_command_options() -> Result<CommandOptions, CliError> {
+    let matches = build_command().get_matches();
+    if matches.subcommand().is_none() {
+        eprintln!("Note: running without a subcommand is deprecated use ada_calc simulate [OPTIONS]");
+    }
+    command_options(&matches)
+}
+
+// Everything but reading the process arguments so the options of any argument list can be built
+// Option values clap cannot check on its own come back as CliError::Parse
+fn command_options(matches: &clap::ArgMatches) -> Result<CommandOptions, CliError> {
+    let graph_csv = matches.subcommand_matches("graph").map(|m| {
+        (
+            String::from(m.value_of("from-csv").unwrap_or_default()),
+            m.value_of("out").map(String::from),
+        )
+    });
+    let ledger_path = String::from(matches.value_of("record-file").unwrap_or(DEFAULT_LEDGER_PATH));
+    let history_file = matches
+        .subcommand_matches("history")
+        .map(|m| String::from(m.value_of("FILE").unwrap_or(&ledger_path)));
+    let fetch_price = matches
+        .subcommand_matches("fetch-price")
+        .map(|m| String::from(m.value_of("CODE").unwrap_or("usd")));
+    let validate_file = matches
+        .subcommand_matches("validate")
+        .and_then(|m| m.value_of("FILE"))
+        .map(String::from);
+    let convert_files = matches.subcommand_matches("convert").map(|m| {
+        (
+            String::from(m.value_of("IN").unwrap_or_default()),
+            String::from(m.value_of("OUT").unwrap_or_default()),
+        )
+    });
+    let open_bundle_file = matches
+        .subcommand_matches("open-bundle")
+        .and_then(|m| m.value_of("FILE"))
+        .map(String::from);
+    let init_file = matches.subcommand_matches("init").map(|m| {
+        (
+            String::from(m.value_of("FILE").unwrap_or("pool.json")),
+            m.is_present("interactive"),
+            m.is_present("force"),
+        )
+    });
+    let completions = matches.subcommand_matches("completions").and_then(|m| match m.value_of("SHELL") {
+        Some("bash") => Some(clap_complete::Shell::Bash),
+        Some("zsh") => Some(clap_complete::Shell::Zsh),
+        Some("fish") => Some(clap_complete::Shell::Fish),
+        Some("powershell") => Some(clap_complete::Shell::PowerShell),
+        _ => None,
+    });
+    let bench_file = matches.subcommand_matches("bench").map(|m| {
+        String::from(m.value_of("FILE").unwrap_or("pool.json"))
+    });
+    // simulate carries every run option so a simulate run reads them from its own matches
+    let simulate = matches.subcommand_matches("simulate");
+    if simulate.is_some() && matches.args_present() {
+        return Err(CliError::Parse(String::from("Options of a run go after simulate")));
+    }
+    let matches = simulate.unwrap_or(matches);
+    // The graph subcommand always draws a graph so only a run needs --generate_graph for the graph file options
+    if graph_csv.is_none() && !matches.is_present("generate_graph") {
+        if let Some(name) = ["svg-out", "graph-format", "graph-width", "graph-size"].into_iter().find(|name| matches.is_present(name)) {
+            return Err(CliError::Parse(format!("--{} needs --generate_graph", name)));
+        }
+    }
+    let precision = matches.value_of("precision").and_then(|v| v.parse::<usize>().ok());
+    let rounding = match matches.value_of("rounding") {
+        Some("half-up") => RoundingMode::HalfUp,
+        Some("truncate") => RoundingMode::Truncate,
+        _ => RoundingMode::HalfEven,
+    };
+    let currency = get_currency(matches)?;
+    let parse_f64 = |name: &str| matches.value_of(name).and_then(|v| v.parse::<f64>().ok());
+    let pool_overrides = PoolOverrides {
+        ada: parse_f64("ada"),
+        initial_price: parse_f64("initial-price"),
+        annual_yield: parse_f64("annual-yield"),
+        price_yield: parse_f64("price-yield"),
+        epoch_in_days: matches.value_of("epoch-in-days").and_then(|v| v.parse::<u64>().ok()),
+        years_holding: parse_f64("years-holding"),
+    };
+    // With no pool file the flags run on top of the example pool so a full set of flags needs no file at all
+    let pool_from_args = !pool_overrides.is_empty()
+        && !matches.is_present("input")
+        && !matches.is_present("pool_json")
+        && !std::path::Path::new("pool.json").exists();
+    let json = if matches.is_present("pool_json") { 
+        Some(String::from(
+            matches.value_of("pool_json").unwrap_or_default(),
+        ))
+    } else {
+        if matches.subcommand().is_none() && !matches.is_present("ndjson") && !matches.is_present("json") && !pool_from_args && !matches.is_present("input") {
+            eprintln!("Found none finding in current working directory...");
+        }
+        None
+    };
+    Ok(CommandOptions {
+        verbose: matches.is_present("verbose"),
+        generate_csv: matches.is_present("generate_csv"),
+        generate_graph: matches.is_present("generate_graph"),
+        json_option: json,
+        csv_out: matches.value_of("csv-out").map(|path| in_output_dir(matches.value_of("output-dir"), path)),
+        generate_xlsx: matches.is_present("generate-xlsx"),
+        xlsx_out: matches.value_of("xlsx-out").map(|path| in_output_dir(matches.value_of("output-dir"), path)),
+        svg_out: matches.value_of("svg-out").map(|path| in_output_dir(matches.value_of("output-dir"), path)),
+        output_dir: matches.value_of("output-dir").map(String::from),
+        report: match matches.value_of("report") {
+            Some("md") => Some(ReportFormat::Markdown),
+            Some("html") => Some(ReportFormat::Html),
+            _ => None,
+        },
+        graph_format: match matches.value_of("graph-format") {
+            Some("png") => GraphFormat::Png,
+            Some("both") => GraphFormat::Both,
+            _ => GraphFormat::Svg,
+        },
+        graph_width: matches
+            .value_of("graph-width")
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|width| *width > 0),
+        graph_size: matches.value_of("graph-size").and_then(parse_graph_size),
+        graph_theme: match matches.value_of("theme") {
+            Some("dark") => GraphTheme::Dark,
+            _ => GraphTheme::Light,
+        },
+        graph_layout: match matches.value_of("graph-layout") {
+            Some("combined") => GraphLayout::Combined,
+            Some("split") => GraphLayout::Split,
+            _ => GraphLayout::Indexed,
+        },
+        graph_scale: match matches.value_of("graph-scale") {
+            Some("log") => GraphScale::Log,
+            _ => GraphScale::Linear,
+        },
+        graph_title: matches.value_of("graph-title").map(String::from),
+        graph_axis: matches.value_of("graph-axis").map(parse_graph_axis).unwrap_or((None, None)),
+        force: matches.is_present("force"),
+        input_path: String::from(matches.value_of("input").unwrap_or("pool.json")),
+        input_paths: matches
+            .values_of("input")
+            .map(|paths| paths.map(String::from).collect())
+            .unwrap_or_else(|| vec![String::from("pool.json")]),
+        output_stem: matches
+            .value_of("input")
+            .filter(|_| !pool_from_args)
+            .and_then(file_stem),
+        pool_overrides,
+        pool_from_args,
+        allow_extreme: matches.is_present("allow-extreme"),
+        pretty: matches.is_present("pretty"),
+        extreme_threshold: matches
+            .value_of("extreme-threshold")
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or(DEFAULT_EXTREME_THRESHOLD),
+        color: use_color(matches.is_present("color"), matches.is_present("no-color")),
+        compound_exact: matches.is_present("compound-exact"),
+        explain_fees: matches.is_present("explain-fees"),
+        summary_table: !matches.is_present("no-summary-table"),
+        timestamp_format: match matches.value_of("timestamp-format") {
+            Some("iso8601") => TimestampFormat::Iso8601,
+            _ => TimestampFormat::Unix,
+        },
+        date_format: matches.value_of("date-format").map(String::from),
+        compare_metric: match matches.value_of("compare-metric") {
+            Some("total") => Some(CompareMetric::Total),
+            Some("yield") => Some(CompareMetric::Yield),
+            Some("cagr") => Some(CompareMetric::Cagr),
+            _ => None,
+        },
+        compare_simple: matches.is_present("compare-compounding-vs-simple"),
+        simple_interest: false,
+        validate_file,
+        convert_files,
+        summary_only: matches.is_present("summary-only"),
+        skip_epochs: matches
+            .value_of("skip-epochs")
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or_default(),
+        denominate_in: get_denomination(matches)?,
+        fail_on_write_error: matches.value_of("on-write-error") != Some("warn"),
+        price_cache_ttl: matches
+            .value_of("price-cache-ttl")
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_PRICE_CACHE_TTL_MINUTES)
+            * 60,
+        refresh_price: matches.is_present("refresh-price"),
+        offline: matches.is_present("offline"),
+        min_yield: matches
+            .value_of("min-yield")
+            .and_then(|v| v.parse::<f64>().ok()),
+        max_yield: matches
+            .value_of("max-yield")
+            .and_then(|v| v.parse::<f64>().ok()),
+        bundle_path: matches.value_of("bundle").map(String::from),
+        dry_run: matches.is_present("dry-run"),
+        dump_series: matches.value_of("dump-series").map(String::from),
+        record_path: Some(ledger_path.clone()).filter(|_| matches.is_present("record")),
+        history_file,
+        completions,
+        usd_format: UsdFormat {
+            rounding,
+            decimals: precision.unwrap_or(currency.decimals),
+            currency,
+        },
+        graph_decimals: precision,
+        sweep: matches
+            .values_of("sweep")
+            .map(|specs| specs.map(String::from).collect())
+            .unwrap_or_default(),
+        max_sweep_runs: matches
+            .value_of("max-sweep-runs")
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_SWEEP_RUNS),
+        monte_carlo: matches
+            .value_of("monte-carlo")
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|runs| *runs > 0),
+        confidence: matches
+            .value_of("confidence")
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or(DEFAULT_MONTE_CARLO_CONFIDENCE),
+        seed: matches.value_of("seed").and_then(|v| v.parse::<u64>().ok()),
+        target: match (
+            matches.value_of("target-usd").and_then(|v| v.parse::<f64>().ok()),
+            matches.value_of("target-ada").and_then(|v| v.parse::<f64>().ok()),
+        ) {
+            (Some(usd), _) => Some(Target::Usd(usd)),
+            (None, Some(ada)) => Some(Target::Ada(ada)),
+            (None, None) => None,
+        },
+        solve_initial_ada: matches.value_of("solve-initial-ada").and_then(|v| v.parse::<f64>().ok()),
+        target_max_years: matches
+            .value_of("max-years")
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or(DEFAULT_TARGET_MAX_YEARS),
+        open_bundle_file,
+        bench_file,
+        init_file,
+        graph_csv,
+        fetch_price,
+        ndjson: matches.is_present("ndjson"),
+        json_output: matches.is_present("json"),
+        include_history: matches.is_present("include-history"),
+        graph_breakeven_line: matches.is_present("graph-breakeven-line"),
+        graph_include_real: matches.is_present("graph-include-real"),
+        graph_wallets: matches.is_present("graph-wallets"),
+        graph_annotations: matches.is_present("graph-annotations"),
+        graph_max_points: matches
+            .value_of("graph-max-points")
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|points| *points > 0)
+            .unwrap_or(DEFAULT_GRAPH_MAX_POINTS),
+        graph_overlay_events: matches.is_present("graph-overlay-events"),
+        csv_granularity: match matches.value_of("csv-granularity") {
+            Some("epoch") => CsvGranularity::Epoch,
+            Some("week") => CsvGranularity::Week,
+            Some("month") => CsvGranularity::Month,
+            Some("year" | "yearly") => CsvGranularity::Year,
+            _ => CsvGranularity::Day,
+        },
+        csv_columns: match matches.value_of("csv-columns") {
+            Some("basic") => CsvColumns::Basic,
+            _ => CsvColumns::Full,
+        },
+        csv_dialect: CsvDialect {
+            delimiter: match matches.value_of("csv-delimiter") {
+                Some("tab" | "\\t") => '\t',
+                Some(delimiter) if delimiter.chars().count() == 1 => delimiter.chars().next().unwrap_or(','),
+                _ => ',',
+            },
+            decimal_comma: matches.is_present("csv-decimal-comma"),
+            ada_precision: matches
+                .value_of("csv-ada-precision")
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(ADA_DECIMALS),
+            value_precision: matches.value_of("csv-precision").and_then(|v| v.parse::<usize>().ok()),
+            rounding,
+        },
+        sanity_report: matches.is_present("sanity-report"),
+        no_footer: matches.is_present("no-footer"),
+        no_progress: matches.is_present("no-progress"),
+        watch: Some(std::time::Duration::from_millis(
+            matches
+                .value_of("watch-interval")
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(DEFAULT_WATCH_INTERVAL_MS),
+        ))
+        .filter(|_| matches.is_present("watch")),
+        ascii: use_ascii(matches.is_present("ascii")),
+        warn_negative_yield: matches.is_present("warn-negative-yield"),
+        unbuffered: matches.is_present("unbuffered"),
+        reward_history: matches.value_of("reward-history").map(String::from),
+        price_history: matches.value_of("price-history").map(String::from),
+        use_realized_yield: matches.is_present("use-realized-yield"),
+        tax_report: matches.is_present("tax-report"),
+        sensitivity: if matches.is_present("sensitivity") {
+            Some(
+                matches
+                    .value_of("sensitivity-pct")
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .unwrap_or(DEFAULT_SENSITIVITY_PCT),
+            )
+        } else {
+            None
+        },
+        break_even: if matches.is_present("break-even") {
+            Some(
+                matches
+                    .value_of("assume-drop")
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .unwrap_or_default(),
+            )
+        } else {
+            None
+        },
+        compare_tax: if matches.is_present("compare-tax-scenarios") {
+            Some((
+                matches
+                    .value_of("income-tax-rate")
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .unwrap_or(DEFAULT_INCOME_TAX_RATE),
+                matches
+                    .value_of("capital-gains-rate")
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .unwrap_or(DEFAULT_CAPITAL_GAINS_RATE),
+            ))
+        } else {
+            None
+        },
+    })
+}
+
+// --currency uses --fx-rate when given otherwise the rate is looked up and a failed lookup stays in USD
+fn get_currency(matches: &clap::ArgMatches) -> Result<Currency, CliError> {
+    let ascii = matches.is_present("ascii");
+    let (code, symbol, decimals) = match matches.value_of("currency") {
+        Some("eur") => ("EUR", if ascii { "EUR " } else { "€" }, 2),
+        Some("gbp") => ("GBP", if ascii { "GBP " } else { "£" }, 2),
+        Some("jpy") => ("JPY", if ascii { "JPY " } else { "¥" }, 0),
+        Some("chf") => ("CHF", "CHF ", 2),
+        Some("cad") => ("CAD", "C$", 2),
+        Some("aud") => ("AUD", "A$", 2),
+        _ => return Ok(USD),
+    };
+    let per_usd = match matches.value_of("fx-rate") {
+        Some(rate) => match rate.parse::<f64>() {
+            Ok(rate) if rate > 0.0 => rate,
+            _ => {
+                return Err(CliError::Parse(format!(
+                    "--fx-rate {} needs to be a positive number of {} per USD",
+                    rate, code
+                )))
+            }
+        },
+        None => match price_api::fetch_usd_exchange_rate(code) {
+            Ok(rate) => rate,
+            Err(e) => {
+                eprintln!(
+                    "Warning: Could not fetch the USD to {} rate ({}) showing USD instead pass --fx-rate to set it",
+                    code, e
+                );
+                return Ok(USD);
+            }
+        },
+    };
+    Ok(Currency { code, symbol, per_usd, decimals })
+}
+
+// --denominate-in needs a --reference-price for anything other than USD
+fn get_denomination(matches: &clap::ArgMatches) -> Result<Option<(String, f64)>, CliError> {
+    let unit = match matches.value_of("denominate-in") {
+        Some("btc") => "BTC",
+        Some("gold") => "oz Gold",
+        _ => return Ok(None),
+    };
+    match matches
+        .value_of("reference-price")
+        .and_then(|v| v.parse::<f64>().ok())
+    {
+        Some(price) if price > 0.0 => Ok(Some((String::from(unit), price))),
+        _ => Err(CliError::Parse(format!(
+            "--denominate-in {} needs a positive --reference-price in USD",
+            matches.value_of("denominate-in").unwrap_or_default()
+        ))),
+    }
+}
+
+// Color is on by default only for an interactive terminal see https://no-color.org for the NO_COLOR convention
+fn use_color(force_color: bool, no_color: bool) -> bool {
+    if no_color {
+        false
+    } else if force_color {
+        true
+    } else {
+        std::env::var_os("NO_COLOR").is_none() && atty::is(atty::Stream::Stdout)
+    }
+}
+
+// Consoles that cannot render Unicode (older Windows consoles or a non UTF-8 locale) get plain text instead of the symbols
+fn use_ascii(force_ascii: bool) -> bool {
+    if force_ascii {
+        return true;
+    }
+    if cfg!(windows) {
+        // Windows Terminal and VS Code render Unicode the legacy conhost does not
+        return std::env::var_os("WT_SESSION").is_none() && std::env::var_os("TERM_PROGRAM").is_none();
+    }
+    match ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|v| !v.is_empty()))
+    {
+        Some(locale) => {
+            let locale = locale.to_lowercase();
+            !(locale.contains("utf-8") || locale.contains("utf8"))
+        }
+        None => false,
+    }
+}
+
+fn ada_symbol(args: &CommandOptions) -> &'static str {
+    if args.ascii {
+        "ADA"
+    } else {
+        "₳"
+    }
+}
+
+fn usd_symbol(args: &CommandOptions) -> &'static str {
+    let currency = args.usd_format.currency;
+    if args.ascii {
+        currency.code
+    } else {
+        currency.symbol.trim_end()
+    }
+}
+
+fn warning_sign(args: &CommandOptions) -> &'static str {
+    if args.ascii {
+        "!!"
+    } else {
+        "⚠️"
+    }
+}
+
+// Green for gains and red for losses when color is enabled otherwise the text is returned untouched
+fn paint_gain(text: String, gain: bool, args: &CommandOptions) -> String {
+    if !args.color {
+        text
+    } else if gain {
+        text.green().to_string()
+    } else {
+        text.red().to_string()
+    }
+}
+
+fn paint_warning(text: String, args: &CommandOptions) -> String {
+    if args.color {
+        text.yellow().to_string()
+    } else {
+        text
+    }
+}
+
+// Warn when the inputs compound into something nobody will ever see (e.g. more than the world economy) and point at the likely culprit
+fn check_extreme_result(
+    pool: &StakedCardanoPool,
+    result: &StakedCardanoPoolResult,
+    args: &CommandOptions,
+    output_to_stdout: bool,
+    warnings: &mut Vec<String>,
+) {
+    if args.allow_extreme || result.total() <= args.extreme_threshold {
+        return;
+    }
+    let currency = args.usd_format.currency;
+    let price_growth = result.final_ada_price / pool.initial_price;
+    let ada_growth = result.final_ada_amount / pool.ada;
+    let driver = if price_growth >= ada_growth {
+        format!(
+            "the daily price multiplier ({}) compounded over {} years grew the price {:.2e}x",
+            pool.describe_price_yield(), pool.years_holding, price_growth
+        )
+    } else {
+        format!(
+            "annual_yield ({}) compounded over {} years grew the ADA amount {:.2e}x",
+            pool.annual_yield, pool.years_holding, ada_growth
+        )
+    };
+    if args.sanity_report {
+        warnings.push(format!(
+            "Final total {}{:.2e} exceeds {}{:.2e} the inputs are likely unrealistic likely cause: {}",
+            currency.symbol,
+            currency.convert(result.total()),
+            currency.symbol,
+            currency.convert(args.extreme_threshold),
+            driver
+        ));
+        return;
+    }
+    print_diagnostic(
+        &paint_warning(
+            format!(
+                "{} WARNING: Final total {}{:.2e} exceeds {}{:.2e} the inputs are likely unrealistic {}",
+                warning_sign(args),
+                currency.symbol,
+                currency.convert(result.total()),
+                currency.symbol,
+                currency.convert(args.extreme_threshold),
+                warning_sign(args)
+            ),
+            args,
+        ),
+        output_to_stdout,
+    );
+    print_diagnostic(
+        &format!("Likely cause: {} (check the price growth and years_holding for typos, use --allow-extreme to silence)", driver),
+        output_to_stdout,
+    );
+}
+
+// One aligned row per year of the run the last one is marked when the horizon ends mid year
+fn print_yearly_summary(result: &StakedCardanoPoolResult, args: &CommandOptions, output_to_stdout: bool) {
+    if result.yearly.is_empty() {
+        return;
+    }
+    let mut rows = vec![[
+        String::from("Year"),
+        String::from("End Date"),
+        String::from("ADA"),
+        String::from("Price"),
+        String::from("Total"),
+        String::from("Rewards ADA"),
+        String::from("Rewards Value"),
+        String::from("Gainz"),
+    ]];
+    rows.extend(result.yearly.iter().map(|row| {
+        [
+            if row.partial { format!("{} (Partial)", row.year) } else { row.year.to_string() },
+            row.end_date.to_string(),
+            format_ada(row.ada, args.usd_format.rounding),
+            format_money(row.price, args.usd_format),
+            format_money(row.total, args.usd_format),
+            format_ada(row.rewards_ada, args.usd_format.rounding),
+            format_money(row.rewards_usd, args.usd_format),
+            format!("{:+.2}%", row.gain_pct),
+        ]
+    }));
+    print_table("Yearly Summary:", &rows, output_to_stdout);
+}
+
+// Commas every three digits of the whole part so "-1234567.50" becomes "-1,234,567.50"
+// Anything that is not a plain decimal like inf or NaN is returned as it is
+fn group_thousands(number: &str) -> String {
+    let (sign, unsigned) = match number.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", number),
+    };
+    let (whole, fraction) = match unsigned.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (unsigned, None),
+    };
+    if whole.is_empty() || !whole.chars().all(|c| c.is_ascii_digit()) {
+        return String::from(number);
+    }
+    let mut grouped = String::from(sign);
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        grouped.push('.');
+        grouped.push_str(fraction);
+    }
+    grouped
+}
+
+// format_ada with thousands separators
+fn pretty_ada(amount: f64, rounding: RoundingMode) -> String {
+    group_thousands(&format_ada(amount, rounding))
+}
+
+// format_money with thousands separators and the minus in front of the symbol
+fn pretty_money(value: f64, format: UsdFormat) -> String {
+    let text = group_thousands(&format_usd(value, format));
+    match text.strip_prefix('-') {
+        Some(rest) => format!("-{}{}", format.currency.symbol, rest),
+        None => format!("{}{}", format.currency.symbol, text),
+    }
+}
+
+// --pretty final result the inputs on the left and the results on the right
+// Cells are padded before the Gainz is painted so the escape codes do not throw the columns off
+fn print_summary_box(pool: &StakedCardanoPool, result: &StakedCardanoPoolResult, break_even: &str, args: &CommandOptions) {
+    let format = args.usd_format;
+    let inputs = [
+        ("ADA", pretty_ada(pool.ada, format.rounding)),
+        ("Initial Price", pretty_money(pool.initial_price, format)),
+        ("Annual Yield", format!("{:.2}%", pool.annual_yield * 100.0)),
+        ("Price Growth", pool.describe_price_yield()),
+        ("Epoch Length", format!("{} Days", pool.epoch_in_days)),
+        ("Years Holding", pool.years_holding.to_string()),
+        ("Start Date", pool.start().to_string()),
+    ];
+    let gainz = result.net_return_as_percentage(pool);
+    let results = [
+        ("Final ADA", pretty_ada(result.final_ada_amount, format.rounding)),
+        ("Final Price", pretty_money(result.final_ada_price, format)),
+        ("Final Total", pretty_money(result.total(), format)),
+        ("Money Put In", pretty_money(result.initial_investment + result.contributed_usd, format)),
+        ("Gainz", format!("{:+.2}% ({:.2}x)", gainz, result.value_multiple(pool))),
+        ("CAGR", format!("{:+.2}%", result.cagr(pool) * 100.0)),
+        ("ADA CAGR", format!("{:.2}%", result.ada_cagr(pool) * 100.0)),
+        ("Holding Instead", pretty_money(result.hold_total(pool), format)),
+        ("Break-Even", String::from(break_even)),
+    ];
+    let width = |rows: &[(&str, String)]| {
+        (
+            rows.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0),
+            rows.iter().map(|(_, value)| value.chars().count()).max().unwrap_or(0),
+        )
+    };
+    let (input_label, input_value) = width(&inputs);
+    let (result_label, result_value) = width(&results);
+    let left = (input_label + 2 + input_value).max("Inputs".len());
+    let right = (result_label + 2 + result_value).max("Results".len());
+    let [top_left, top, top_middle, top_right, side, middle_left, middle, middle_right, bottom_left, bottom_middle, bottom_right] = if args.ascii {
+        ["+", "-", "+", "+", "|", "+", "+", "+", "+", "+", "+"]
+    } else {
+        ["┌", "─", "┬", "┐", "│", "├", "┼", "┤", "└", "┴", "┘"]
+    };
+    let rule = |start: &str, join: &str, end: &str| {
+        format!("{}{}{}{}{}", start, top.repeat(left + 2), join, top.repeat(right + 2), end)
+    };
+    println!("{}", rule(top_left, top_middle, top_right));
+    println!("{} {:<left$} {} {:<right$} {}", side, "Inputs", side, "Results", side, left = left, right = right);
+    println!("{}", rule(middle_left, middle, middle_right));
+    for row in 0..inputs.len().max(results.len()) {
+        let input = match inputs.get(row) {
+            Some((label, value)) => format!("{:<input_label$}  {:>input_value$}", label, value, input_label = input_label, input_value = input_value),
+            None => String::new(),
+        };
+        let output = match results.get(row) {
+            Some((label, value)) => {
+                let value = format!("{:>result_value$}", value, result_value = result_value);
+                let value = if *label == "Gainz" { paint_gain(value, gainz >= 0.0, args) } else { value };
+                format!("{:<result_label$}  {}", label, value, result_label = result_label)
+            }
+            None => " ".repeat(result_label + 2 + result_value),
+        };
+        // The painted cell has escape codes in it so the right side is padded on the visible width
+        let padding = right - (result_label + 2 + result_value).min(right);
+        println!(
+            "{} {:<left$} {} {}{} {}",
+            side,
+            input,
+            side,
+            output,
+            " ".repeat(padding),
+            side,
+            left = left
+        );
+    }
+    println!("{}", rule(bottom_left, bottom_middle, bottom_right));
+}
+
+// Prints the title and the rows the first row being the header with every column padded to its widest cell
+fn print_table<R: AsRef<[String]>>(title: &str, rows: &[R], output_to_stdout: bool) {
+    let columns = rows.iter().map(|row| row.as_ref().len()).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.as_ref().get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    if output_to_stdout {
+        println!("<div class='output'>");
+    }
+    print_result_line(title, output_to_stdout);
+    for row in rows.iter() {
+        // The year is left aligned and the numbers right aligned so the decimals line up
+        let cells: Vec<String> = row
+            .as_ref()
+            .iter()
+            .zip(widths.iter())
+            .enumerate()
+            .map(|(column, (cell, width))| if column == 0 { format!("{:<width$}", cell) } else { format!("{:>width$}", cell) })
+            .collect();
+        print_result_line(&format!("  {}", cells.join("  ").trim_end()), output_to_stdout);
+    }
+    if output_to_stdout {
+        println!("</div>");
+    }
+}
+
+// Spell out a loss and where it came from instead of leaving a small negative Gainz to be missed
+fn check_negative_result(
+    pool: &StakedCardanoPool,
+    result: &StakedCardanoPoolResult,
+    args: &CommandOptions,
+    output_to_stdout: bool,
+    warnings: &mut Vec<String>,
+) {
+    let net_return = result.net_return_as_percentage(pool);
+    if net_return >= 0.0 {
+        return;
+    }
+    let cost = pool.ada * pool.initial_price + result.contributed_usd;
+    let price_change = (result.final_ada_price / pool.initial_price - 1.0) * 100.0;
+    let ada_change = result.ada_growth_as_percentage(pool);
+    let (drawdown, peak_day, trough_day) = result.max_drawdown;
+    let lines = [
+        format!(
+            "Net loss of {} ({:.2}%) on {} put in",
+            format_money(cost - result.total(), args.usd_format),
+            net_return,
+            format_money(cost, args.usd_format)
+        ),
+        format!(
+            "Cause: the price moved {:+.2}% (daily multiplier {}) while staking changed the ADA amount {:+.2}%",
+            price_change, pool.describe_price_yield(), ada_change
+        ),
+        format!(
+            "Max Drawdown: {:.2}% from day {} to day {}",
+            drawdown * 100.0,
+            peak_day,
+            trough_day
+        ),
+    ];
+    if args.sanity_report {
+        warnings.push(lines.join(" "));
+        return;
+    }
+    print_diagnostic(
+        &paint_warning(
+            format!("{} WARNING: {} {}", warning_sign(args), lines[0], warning_sign(args)),
+            args,
+        ),
+        output_to_stdout,
+    );
+    for line in lines[1..].iter() {
+        print_diagnostic(line, output_to_stdout);
+    }
+}
+
+// Validators of the numeric flags so a value that does not parse is a usage error (exit 2) naming the flag instead of being ignored
+fn is_number(value: &str) -> Result<(), String> {
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok(()),
+        _ => Err(String::from("expected a number")),
+    }
+}
+
+fn is_whole_number<T: std::str::FromStr>(value: &str) -> Result<(), String> {
+    value.parse::<T>().map(|_| ()).map_err(|_| String::from("expected a whole number"))
+}
+
+fn is_count<T: std::str::FromStr + Default + PartialEq>(value: &str) -> Result<(), String> {
+    match value.parse::<T>() {
+        Ok(count) if count != T::default() => Ok(()),
+        _ => Err(String::from("expected a whole number above zero")),
+    }
+}
+
+fn is_confidence(value: &str) -> Result<(), String> {
+    match value.parse::<f64>() {
+        Ok(pct) if (1.0..=99.0).contains(&pct) => Ok(()),
+        _ => Err(String::from("expected a percent from 1 to 99")),
+    }
+}
+
+fn is_graph_size(value: &str) -> Result<(), String> {
+    parse_graph_size(value).map(|_| ()).ok_or_else(|| String::from("expected WIDTHxHEIGHT like 1280x720"))
+}
+
+// Parses --graph-size like 1280x720 both sides must be above zero
+fn parse_graph_size(value: &str) -> Option<(u32, u32)> {
+    let (width, height) = value.to_lowercase().split_once('x').map(|(w, h)| (w.trim().parse::<u32>(), h.trim().parse::<u32>()))?;
+    match (width, height) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Some((width, height)),
+        _ => None,
+    }
+}
+
+// Splits --graph-axis on the first comma no comma only replaces the x label
+fn parse_graph_axis(value: &str) -> (Option<String>, Option<String>) {
+    let label = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+    match value.split_once(',') {
+        Some((x, y)) => (label(x), label(y)),
+        None => (label(value), None),
+    }
+}
+
+// Relative paths go under --output-dir when it was given absolute ones are kept as they are
+fn in_output_dir(output_dir: Option<&str>, path: &str) -> String {
+    match output_dir {
+        Some(dir) => std::path::Path::new(dir).join(path).display().to_string(),
+        None => String::from(path),
+    }
+}
+
+// Full path of a file that was just written so it can be copied from the output the path as given when that fails
+fn absolute_path(path: &str) -> String {
+    std::fs::canonicalize(path)
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| String::from(path))
+}
+
+// Pool file stem for default file names only when -i was given so pool.json keeps the original names
+fn default_xlsx_path(args: &CommandOptions) -> String {
+    in_output_dir(
+        args.output_dir.as_deref(),
+        &format!("ada_calc_data_{}{}.xlsx", stem_prefix(args), get_file_timestamp(args.timestamp_format)),
+    )
+}
+
+fn stem_prefix(args: &CommandOptions) -> String {
+    args.output_stem.as_ref().map(|stem| format!("{}_", stem)).unwrap_or_default()
+}
+
+fn file_stem(path: &str) -> Option<String> {
+    if path == STDIN_PATH {
+        return None; // Nothing to name the files after so the default names are kept
+    }
+    std::path::Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+}
+
+// Runs the same pool again without reinvesting rewards no output files or verbose lines are produced by the second run
+fn compare_compounding_vs_simple(
+    pool: &StakedCardanoPool,
+    result: &StakedCardanoPoolResult,
+    args: &CommandOptions,
+    output_to_stdout: bool,
+) {
+    let simple_args = CommandOptions {
+        verbose: false,
+        generate_csv: false,
+        generate_xlsx: false,
+        generate_graph: false,
+        dump_series: None,
+        simple_interest: true,
+        ..args.clone()
+    };
+    let simple = run_simulation(pool, &simple_args, output_to_stdout);
+    let difference = result.total() - simple.total();
+    print_result_line(
+        &format!(
+            "Compounding: {} Simple (Rewards Not Reinvested): {} Compounding Adds: {} ({:.2}%)",
+            format_money(result.total(), args.usd_format),
+            format_money(simple.total(), args.usd_format),
+            format_money(difference, args.usd_format),
+            (difference / simple.total()) * 100.0
+        ),
+        output_to_stdout,
+    );
+}
+
+// Points per run kept for the Monte Carlo graph enough for a smooth envelope without holding every day of every run
+const MONTE_CARLO_GRAPH_POINTS: usize = 400;
+
+// --confidence when it is not given the 5th and 95th percentiles
+const DEFAULT_MONTE_CARLO_CONFIDENCE: f64 = 90.0;
+
+// Runs the pool again runs times with random daily price shocks the seed is always printed so a run can be repeated with --seed
+// With -g every run's final values go to one CSV and with -G the graph is the percentile envelope instead of the single run
+fn report_monte_carlo(pool: &StakedCardanoPool, runs: u32, args: &CommandOptions, output_to_stdout: bool) -> Result<(), CliError> {
+    let seed = args.seed.unwrap_or(get_epoch_ms() as u64);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut progress = Progress::new("Monte Carlo", args, output_to_stdout);
+    let graph_points = args.generate_graph.then_some(MONTE_CARLO_GRAPH_POINTS);
+    let runs_done = analysis::monte_carlo(pool, runs, graph_points, &args.simulation_options(), &mut rng, |run| {
+        progress.update(run as u64, runs as u64, "Run")
+    })
+    .map_err(CliError::Validation)?;
+    progress.finish();
+    let finals = &runs_done.finals;
+    let mut totals: Vec<f64> = finals.iter().map(|(_, _, total)| *total).collect();
+    let mut adas: Vec<f64> = finals.iter().map(|(ada, _, _)| *ada).collect();
+    totals.sort_by(|a, b| a.total_cmp(b));
+    adas.sort_by(|a, b| a.total_cmp(b));
+    let mean = totals.iter().sum::<f64>() / totals.len() as f64;
+    let mut lines = vec![
+        format!(
+            "Monte Carlo ({} Runs, Seed {}, Daily Price Volatility {}):",
+            runs, seed, pool.price_volatility
+        ),
+        format!("  Mean Total: {}", format_money(mean, args.usd_format)),
+    ];
+    for p in monte_carlo_percentiles(args.confidence) {
+        lines.push(format!(
+            "  {} Total: {} ADA: {} {}",
+            percentile_name(p),
+            format_money(percentile(&totals, p), args.usd_format),
+            format_ada(percentile(&adas, p), args.usd_format.rounding),
+            ada_symbol(args)
+        ));
+    }
+    for line in lines.iter() {
+        print_result_line(line, output_to_stdout);
+    }
+    if pool.price_volatility == 0.0 {
+        print_diagnostic("Note: price_volatility is 0 so every run follows the price growth exactly", output_to_stdout);
+    }
+    if args.generate_csv && !output_to_stdout {
+        write_result(write_monte_carlo_csv(finals, args), "The Monte Carlo CSV", args)?;
+    }
+    if args.generate_graph && !runs_done.paths.is_empty() {
+        let path = args.svg_out.clone().unwrap_or_else(|| default_graph_path(args));
+        let svg = render_monte_carlo_svg(&runs_done.path_days, &runs_done.paths, args);
+        write_result(write_graph(Some(path), &svg, args, output_to_stdout), "The Monte Carlo graph", args)?;
+    }
+    Ok(())
+}
+
+// How far --sensitivity moves each input when --sensitivity-pct is not given
+const DEFAULT_SENSITIVITY_PCT: f64 = 10.0;
+
+// Widest bar in the sensitivity table the input that moves the total most gets all of it
+const SENSITIVITY_BAR_WIDTH: usize = 30;
+
+// Tornado style table the bar shows each input's biggest move against the biggest move of any input
+fn print_sensitivity(rows: &[SensitivityRow], pct: f64, args: &CommandOptions, output_to_stdout: bool) {
+    if rows.is_empty() {
+        return;
+    }
+    let widest = rows
+        .iter()
+        .map(|row| row.low_change_pct.abs().max(row.high_change_pct.abs()))
+        .fold(0.0, f64::max);
+    let mut table = vec![vec![
+        String::from("Input"),
+        format!("-{}% Total", pct),
+        String::from("Change"),
+        format!("+{}% Total", pct),
+        String::from("Change"),
+        String::new(),
+    ]];
+    for row in rows {
+        let impact = row.low_change_pct.abs().max(row.high_change_pct.abs());
+        let bar = if widest > 0.0 { (impact / widest * SENSITIVITY_BAR_WIDTH as f64).round() as usize } else { 0 };
+        table.push(vec![
+            String::from(row.parameter),
+            format_money(row.low_total, args.usd_format),
+            format!("{:+.2}%", row.low_change_pct),
+            format_money(row.high_total, args.usd_format),
+            format!("{:+.2}%", row.high_change_pct),
+            format!("{:<width$}", "#".repeat(bar.max(1)), width = SENSITIVITY_BAR_WIDTH),
+        ]);
+    }
+    print_table(&format!("Sensitivity (Each Input Moved -/+{}%):", pct), &table, output_to_stdout);
+}
+
+fn print_break_even(pool: &StakedCardanoPool, analysis: &BreakEven, args: &CommandOptions, output_to_stdout: bool) {
+    let invested = pool.ada * pool.initial_price;
+    let heading = if analysis.drop_pct == 0.0 {
+        String::from("Break-Even (No Price Drop)")
+    } else {
+        format!("Break-Even (Price Drops {}% On Day 1)", analysis.drop_pct)
+    };
+    let answer = match (analysis.day, analysis.date) {
+        (Some(day), Some(date)) => format!("Day {} ({}) the total is back above the {} put in", day, date, format_money(invested, args.usd_format)),
+        _ => format!(
+            "Never within the {} year horizon the total stays below the {} put in",
+            pool.years_holding,
+            format_money(invested, args.usd_format)
+        ),
+    };
+    let mut lines = vec![format!("{}: {}", heading, answer)];
+    if analysis.drop_pct > 0.0 {
+        lines.push(match analysis.years_needed_flat {
+            Some(years) => format!("With A Flat Price After The Drop Staking Needs {:.2} Years To Win It Back", years),
+            None => String::from("With A Flat Price After The Drop It Is Never Won Back (The ADA Does Not Grow)"),
+        });
+    }
+    lines.push(format!(
+        "Max Sustained Price Decline: {:.2}% A Year Is Made Up For By The ADA Growth",
+        analysis.max_annual_decline_pct
+    ));
+    for line in lines {
+        print_result_line(&line, output_to_stdout);
+    }
+}
+
+// Grids bigger than this need --max-sweep-runs so a typo in a step cannot start a million runs
+const DEFAULT_MAX_SWEEP_RUNS: usize = 10_000;
+
+// Runs every combination of the --sweep ranges spread over all cores and writes one CSV row per combination
+// Only the ADA, total and Gainz are kept for each run so no per run CSV or graph is written
+fn report_sweep(pool: &StakedCardanoPool, args: &CommandOptions, output_to_stdout: bool) -> Result<(), CliError> {
+    let ranges: Vec<SweepRange> = args.sweep.iter().map(|spec| parse_sweep(spec)).collect::<Result<_, _>>().map_err(CliError::Parse)?;
+    for range in ranges.iter() {
+        check_sweep_range(pool, range).map_err(CliError::Parse)?;
+    }
+    let runs = ranges.iter().map(|range| range.values.len()).product::<usize>();
+    if runs > args.max_sweep_runs {
+        return Err(CliError::Parse(format!(
+            "--sweep would run {} combinations raise --max-sweep-runs (now {}) to allow it",
+            runs, args.max_sweep_runs
+        )));
+    }
+    // Every combination is built and checked up front so a bad value fails before anything runs
+    let grid = analysis::sweep_grid(pool, &ranges).map_err(CliError::Validation)?;
+    let options = SimulationOptions {
+        record_history: false,
+        record_payouts: false,
+        ..args.simulation_options()
+    };
+    let mut progress = Progress::new("Sweep", args, output_to_stdout);
+    let finals = analysis::run_sweep(&grid, &options, |done| progress.update(done, runs as u64, "Run"));
+    progress.finish();
+    let fields: Vec<&str> = ranges.iter().map(|range| range.field.as_str()).collect();
+    print_result_line(&format!("Sweep ({} Runs Over {}):", runs, fields.join(" x ")), output_to_stdout);
+    let mut order: Vec<usize> = (0..runs).collect();
+    order.sort_by(|a, b| finals[*a].1.total_cmp(&finals[*b].1));
+    let mut rows = vec![[vec![String::new()], fields.iter().map(|field| field.to_string()).collect(), vec![
+        String::from("Final ADA"),
+        String::from("Total"),
+        String::from("Gainz"),
+    ]]
+    .concat()];
+    for (label, index) in [("Lowest", order[0]), ("Median", order[runs / 2]), ("Highest", order[runs - 1])] {
+        let (ada, total, gainz) = finals[index];
+        rows.push(
+            [vec![String::from(label)], grid[index].0.iter().map(|v| v.to_string()).collect(), vec![
+                format_ada(ada, args.usd_format.rounding),
+                format_money(total, args.usd_format),
+                format!("{:+.2}%", gainz),
+            ]]
+            .concat(),
+        );
+    }
+    print_table("  Totals Across The Grid:", &rows, output_to_stdout);
+    if !output_to_stdout {
+        write_result(write_sweep_csv(&fields, &grid, &finals, args), "The sweep CSV", args)?;
+    }
+    Ok(())
+}
+
+// After tax totals of analysis::tax_scenarios with the drag of each against the untaxed total
+fn compare_tax_scenarios(
+    pool: &StakedCardanoPool,
+    result: &StakedCardanoPoolResult,
+    income_rate: f64,
+    gains_rate: f64,
+    args: &CommandOptions,
+    output_to_stdout: bool,
+) {
+    let total = result.total();
+    let scenarios = analysis::tax_scenarios(pool, result, income_rate, gains_rate);
+    print_result_line(
+        &format!(
+            "Tax Scenarios (Income Tax {:.0}%, Capital Gains {:.0}%, Settled At The End):",
+            income_rate * 100.0,
+            gains_rate * 100.0
+        ),
+        output_to_stdout,
+    );
+    for (name, tax) in scenarios.iter() {
+        print_result_line(
+            &format!(
+                "  {}: {} After Tax (Tax Drag: {} {:.2}%)",
+                name,
+                format_money(total - tax, args.usd_format),
+                format_money(*tax, args.usd_format),
+                tax / total * 100.0
+            ),
+            output_to_stdout,
+        );
+    }
+}
+
+// Reward income per simulated year (365.25 days from the start) only rewards count as income not contributions or withdrawals
+fn report_tax_income(
+    pool: &StakedCardanoPool,
+    result: &StakedCardanoPoolResult,
+    args: &CommandOptions,
+    output_to_stdout: bool,
+) -> Result<(), CliError> {
+    let written = write_tax_report_csv(pool, result, args);
+    let mut years: Vec<(f64, f64)> = Vec::new(); // ADA and USD income per year
+    for (day, ada, price) in result.reward_payouts.iter() {
+        let year = (day.saturating_sub(1) as f64 / pool.days_per_year()) as usize;
+        if years.len() <= year {
+            years.resize(year + 1, (0.0, 0.0));
+        }
+        years[year].0 += ada;
+        years[year].1 += ada * price;
+    }
+    print_result_line("Reward Income By Year:", output_to_stdout);
+    for (year, (ada, usd)) in years.iter().enumerate() {
+        let tax = if pool.reward_tax_rate > 0.0 {
+            format!(
+                " Tax Owed: {} After Tax: {}",
+                format_money(usd * pool.reward_tax_rate, args.usd_format),
+                format_money(usd * (1.0 - pool.reward_tax_rate), args.usd_format)
+            )
+        } else {
+            String::new()
+        };
+        print_result_line(
+            &format!(
+                "  Year {}: {} ADA Income: {}{}",
+                year + 1,
+                format_ada(*ada, args.usd_format.rounding),
+                format_money(*usd, args.usd_format),
+                tax
+            ),
+            output_to_stdout,
+        );
+    }
+    write_result(written, "The tax report", args)
+}
+
+// Compares against the same pool without the lump sums to show what the extra buys added to the final total
+fn report_lump_sums(
+    pool: &StakedCardanoPool,
+    result: &StakedCardanoPoolResult,
+    args: &CommandOptions,
+    output_to_stdout: bool,
+) {
+    let without_pool = StakedCardanoPool {
+        lump_sums: Vec::new(),
+        ..pool.clone()
+    };
+    let without_args = CommandOptions {
+        verbose: false,
+        generate_csv: false,
+        generate_xlsx: false,
+        generate_graph: false,
+        dump_series: None,
+        ..args.clone()
+    };
+    let without = run_simulation(&without_pool, &without_args, output_to_stdout);
+    print_result_line(
+        &format!(
+            "Lump Sums: {} Bought {} ADA Effect On Final Total: +{} (Cost Basis {})",
+            format_money(result.contributed_usd - result.periodic_usd, args.usd_format),
+            format_ada(result.contributed_ada - result.periodic_ada, args.usd_format.rounding),
+            format_money(result.total() - without.total(), args.usd_format),
+            format_money(pool.ada * pool.initial_price + result.contributed_usd, args.usd_format)
+        ),
+        output_to_stdout,
+    );
+}
+
+// --dry-run output every path is worked out the same way as a real run but nothing is opened
+fn print_dry_run(pools: &[(String, StakedCardanoPool)], args: &CommandOptions, output_to_stdout: bool) {
+    let mut lines = vec![String::from("Dry Run (Nothing Simulated Or Written):")];
+    for (name, pool) in pools.iter() {
+        let indent = if name.is_empty() { "  " } else { "    " };
+        if !name.is_empty() {
+            lines.push(format!("  {}:", name));
+        }
+        let days = pool.simulated_days();
+        let credits = (1..=days).filter(|day| pool.is_credit_day(*day)).count();
+        lines.push(format!("{}Days: {} ({} Years)", indent, days, pool.years_holding));
+        lines.push(format!("{}Dates: {} To {}", indent, pool.start(), pool.date_on(days)));
+        if let Some(first) = (1..=days).find(|day| pool.is_credit_day(*day)).filter(|_| pool.epoch_anchor_date.is_some()) {
+            lines.push(format!(
+                "{}First Pay Day: Day {} ({}) Epoch {} ({:.0}% Of A Full Epoch)",
+                indent,
+                first,
+                pool.date_on(first),
+                pool.epoch_number(pool.date_on(first)).unwrap_or_default(),
+                pool.epoch_share(first) * 100.0
+            ));
+        }
+        lines.push(format!(
+            "{}Epochs Per Year: {} ({} Day Epochs)",
+            indent,
+            pool.epochs_per_year(),
+            pool.epoch_in_days
+        ));
+        lines.push(format!(
+            "{}Reward Credits: {} (Paid {})",
+            indent,
+            credits,
+            pool.describe_compounding()
+        ));
+        lines.push(format!("{}Initial ADA Per Year: {}", indent, format_ada(initial_ada_per_year(pool), args.usd_format.rounding)));
+    }
+    // Scenario runs write one shared CSV and the overlaid graph
+    let scenarios = pools.len() > 1;
+    // Each file with what happens when it is already there the series is always overwritten and the bundle never is
+    let force_hint = if args.force { None } else { Some("pass --force to overwrite") };
+    let mut files: Vec<(&str, String, Option<&str>)> = Vec::new();
+    if args.generate_csv {
+        files.push(("CSV", args.csv_out.clone().unwrap_or_else(|| default_csv_path(args)), force_hint));
+    }
+    if args.generate_xlsx && !scenarios {
+        files.push(("Workbook", args.xlsx_out.clone().unwrap_or_else(|| default_xlsx_path(args)), force_hint));
+    }
+    if args.generate_graph && !output_to_stdout {
+        let path = args.svg_out.clone().unwrap_or_else(|| default_graph_path(args));
+        let paths = if args.graph_layout == GraphLayout::Split && !scenarios {
+            ["price", "ada", "total"].iter().map(|suffix| split_graph_path(&path, suffix)).collect()
+        } else {
+            vec![path]
+        };
+        for path in paths.iter() {
+            if args.graph_format == GraphFormat::Both {
+                files.push(("Graph", graph_file_path(path, "svg", args), force_hint));
+                files.push(("Graph", graph_file_path(path, "png", args), force_hint));
+            } else {
+                files.push(("Graph", path.clone(), force_hint));
+            }
+        }
+    }
+    if args.tax_report && !scenarios {
+        files.push(("Tax Report", tax_report_path(args), force_hint));
+    }
+    if let (Some(path), false) = (&args.dump_series, scenarios) {
+        files.push(("Series", path.clone(), None));
+    }
+    if let (Some(path), false) = (&args.bundle_path, scenarios) {
+        files.push(("Bundle", path.clone(), Some("remove it first")));
+    }
+    lines.push(String::from("Files That Would Be Written:"));
+    if files.is_empty() {
+        lines.push(String::from("  None"));
+    }
+    for (label, path, hint) in files.iter() {
+        match hint {
+            Some(hint) if std::path::Path::new(path).exists() => {
+                lines.push(format!("  {}: {} (already exists {})", label, path, hint))
+            }
+            _ => lines.push(format!("  {}: {}", label, path)),
+        }
+    }
+    for line in lines.iter() {
+        print_result_line(line, output_to_stdout);
+    }
+}
+
+// Only printed when the history was recorded (--generate_graph, --dump-series or --include-history)
+fn print_statistics(stats: &HistoryStatistics, args: &CommandOptions, output_to_stdout: bool) {
+    let lines = [
+        String::from("Statistics:"),
+        format!("  Max Drawdown: {:.2}%", stats.max_drawdown * 100.0),
+        format!("  Best Day: {:+.4}% (Day {})", stats.best_day.1 * 100.0, stats.best_day.0),
+        format!("  Worst Day: {:+.4}% (Day {})", stats.worst_day.1 * 100.0, stats.worst_day.0),
+        format!("  Peak Total: {} (Day {})", format_money(stats.peak.1, args.usd_format), stats.peak.0),
+    ];
+    for line in lines.iter() {
+        print_result_line(line, output_to_stdout);
+    }
+}
+
+fn print_fee_breakdown(result: &StakedCardanoPoolResult, args: &CommandOptions, output_to_stdout: bool) {
+    let lines = [
+        format!(
+            "Gross Rewards: {} ADA ({})",
+            format_ada(result.gross_rewards, args.usd_format.rounding),
+            format_money(result.gross_rewards_usd, args.usd_format)
+        ),
+        format!(
+            "Pool Fees Paid: {} ADA ({})",
+            format_ada(result.fees_paid, args.usd_format.rounding),
+            format_money(result.fees_paid_usd, args.usd_format)
+        ),
+        format!(
+            "Net Rewards: {} ADA ({})",
+            format_ada(result.gross_rewards - result.fees_paid, args.usd_format.rounding),
+            format_money(result.gross_rewards_usd - result.fees_paid_usd, args.usd_format)
+        ),
+    ];
+    for line in lines.iter() {
+        print_result_line(line, output_to_stdout);
+    }
+}
+
+// Caveats behind the numbers so a result is never read without them (see the disclaimer)
+fn print_assumptions_footer(pool: &StakedCardanoPool, args: &CommandOptions, output_to_stdout: bool) {
+    let rewards = match (pool.reserve_ada, pool.pool_epoch_reward, pool.pool_active_stake) {
+        (_, Some(reward), Some(stake)) => format!(
+            "Pool stats {} ADA per epoch split over {} ADA active stake",
+            reward, stake
+        ),
+        (Some(reserve), _, _) => format!(
+            "Reserve emission starting at {} ADA with rho {} tau {} and {} ADA staked network wide",
+            reserve, pool.rho, pool.tau, pool.total_stake_ada
+        ),
+        _ if pool.has_yield_schedule() => format!("annual_yield of {} on day 1 then annual_yield_schedule and annual_yield_decay", pool.annual_yield_on(1)),
+        _ => format!("Flat annual_yield of {}", pool.annual_yield),
+    };
+    let rewards = match pool.saturation_limit_ada {
+        Some(limit) if pool.auto_redelegate => format!(
+            "{} moving to a fresh pool once the pool passes {} ADA",
+            rewards, limit
+        ),
+        Some(limit) => format!("{} scaled down once the pool passes {} ADA", rewards, limit),
+        None => rewards,
+    };
+    let credit = if pool.compounding == Compounding::Epoch { "epoch" } else { "credit" };
+    let compounding = if !pool.compound {
+        String::from("Rewards withdrawn as they are paid (not restaked)")
+    } else if args.simple_interest {
+        String::from("Simple interest (rewards not reinvested)")
+    } else if pool.reserve_ada.is_some() || pool.pool_epoch_reward.is_some() {
+        String::from("Rewards restaked as they are paid")
+    } else if args.compound_exact {
+        format!("Exact per {} rate compounding to annual_yield", credit)
+    } else {
+        format!("Per {} slice of annual_yield (annual_yield / {}s per year)", credit, credit)
+    };
+    let fees = if !has_pool_fees(pool) {
+        String::from("No pool fees applied")
+    } else if let (None, Some(stake)) = (pool.pool_epoch_reward, pool.pool_active_stake) {
+        format!(
+            "Pool margin {} and fixed fee {} ADA per epoch taken from the whole pool ({} ADA active stake) and shared by stake",
+            pool.pool_margin, pool.pool_fixed_fee, stake
+        )
+    } else {
+        format!(
+            "Pool margin {} and fixed fee {} ADA per epoch applied",
+            pool.pool_margin, pool.pool_fixed_fee
+        )
+    };
+    let lines = [
+        String::from("Assumptions (Not Investment Advice):"),
+        format!(
+            "  Price Model: {} starting at {}",
+            if !pool.daily_prices.is_empty() {
+                format!("Backtest replaying {}", pool.describe_price_yield())
+            } else {
+                format!(
+                    "{} daily multiplier of {}",
+                    if pool.price_yields.is_empty() { "Constant" } else { "Yearly" },
+                    pool.describe_price_yield()
+                )
+            },
+            args.usd_format.currency.show(pool.initial_price)
+        ),
+        format!(
+            "  Compounding: {} {} paid {} {}s after they are earned",
+            compounding,
+            pool.describe_compounding(),
+            pool.reward_lag_epochs,
+            credit
+        ),
+        format!("  Rewards: {}", rewards),
+        format!("  Fees: {}", fees),
+        if pool.reward_tax_rate > 0.0 {
+            format!(
+                "  Taxes: {}% income tax on rewards valued on the day received only in the Reward Tax line (all other figures are before tax)",
+                pool.reward_tax_rate * 100.0
+            )
+        } else if args.compare_tax.is_some() {
+            String::from("  Taxes: Only in the Tax Scenarios above (all other figures are before tax)")
+        } else {
+            String::from("  Taxes: Not modeled")
+        },
+        format!("  Horizon: {} years", (pool.years_holding * 100.0).round() / 100.0), // A --target-usd horizon is not a round number
+    ];
+    for line in lines.iter() {
+        print_result_line(line, output_to_stdout);
+    }
+}
+
+fn print_sanity_report(warnings: &[String], output_to_stdout: bool) {
+    print_result_line("Sanity Report:", output_to_stdout);
+    if warnings.is_empty() {
+        print_result_line("  No issues detected", output_to_stdout);
+    }
+    for warning in warnings {
+        print_result_line(&format!("  - {}", warning), output_to_stdout);
+    }
+}
+
+// Guard rail for scripted sweeps keeps annual_yield inside the --min-yield/--max-yield bounds
+fn clamp_annual_yield(pool: &mut StakedCardanoPool, args: &CommandOptions, warnings: &mut Vec<String>) {
+    let mut clamped = pool.annual_yield;
+    if let Some(min_yield) = args.min_yield {
+        clamped = clamped.max(min_yield);
+    }
+    if let Some(max_yield) = args.max_yield {
+        clamped = clamped.min(max_yield);
+    }
+    if clamped != pool.annual_yield {
+        let warning = format!(
+            "annual_yield {} is outside the allowed range clamped to {}",
+            pool.annual_yield, clamped
+        );
+        if args.sanity_report {
+            warnings.push(warning);
+        } else {
+            eprintln!("Warning: {}", warning);
+        }
+        pool.annual_yield = clamped;
+    }
+}
+
+#[derive(Serialize)]
+struct SeriesDump<'a> {
+    ada: &'a [f64],
+    price: &'a [f64],
+    total: Vec<f64>,
+}
+
+// Writes the collected history for plotting elsewhere the same vectors the graph is drawn from
+fn write_series(path: &str, result: &StakedCardanoPoolResult) -> bool {
+    if result.amount_historical.is_empty() {
+        eprintln!(
+            "Error: No History Was Collected For [{}] (every day was skipped by --skip-epochs).",
+            path
+        );
+        return false;
+    }
+    let dump = SeriesDump {
+        ada: &result.amount_historical,
+        price: &result.price_historical,
+        total: result
+            .amount_historical
+            .iter()
+            .zip(result.price_historical.iter())
+            .map(|(ada, price)| ada * price)
+            .collect(),
+    };
+    let written = serde_json::to_string(&dump)
+        .map_err(std::io::Error::from)
+        .and_then(|text| std::fs::write(path, text));
+    match written {
+        Ok(()) => {
+            println!("Saved Series as {} to Disk.", absolute_path(path));
+            true
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to Write Series [{}] to Disk.", path);
+            eprintln!("Reason: {}", describe_write_error(path, &e));
+            false
+        }
+    }
+}
+
+// Writes the effective config, the summary and the graph (when generating one) as one JSON file
+fn write_bundle(
+    path: &str,
+    pool: &StakedCardanoPool,
+    result: &StakedCardanoPoolResult,
+    args: &CommandOptions,
+) -> bool {
+    let bundle = RunBundle {
+        version: String::from(env!("CARGO_PKG_VERSION")),
+        config: pool.clone(),
+        summary: RunSummary::new(pool, result),
+        svg: if args.generate_graph && has_graph_history(result) {
+            Some(render_graph_svg(Some(pool), result, args))
+        } else {
+            None
+        },
+    };
+    let written = serde_json::to_string_pretty(&bundle)
+        .map_err(std::io::Error::from)
+        .and_then(|text| {
+            OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(text.as_bytes()))
+        });
+    match written {
+        Ok(()) => {
+            println!("Saved Bundle as {} to Disk.", absolute_path(path));
+            true
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to Write Bundle [{}] to Disk.", path);
+            eprintln!("Reason: {}", describe_write_error(path, &e));
+            false
+        }
+    }
+}
+
+// The open-bundle subcommand prints the config and summary stored in a bundle
+fn open_bundle_file(path: &str, usd_format: UsdFormat) -> Result<(), CliError> {
+    let bundle: RunBundle = read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|buffer| serde_json::from_str(&buffer).map_err(|e| e.to_string()))
+        .map_err(|e| CliError::MissingConfig(format!("{}: {}", path, e)))?;
+    println!("Bundle: {} (ada_calc {})", path, bundle.version);
+    println!(
+        "Config:\n{}",
+        serde_json::to_string_pretty(&bundle.config).unwrap_or_default()
+    );
+    println!(
+        "Final Result: {} ADA @ {} = {} Yield: {:.2}% Over {} Days",
+        format_ada(bundle.summary.final_ada_amount, usd_format.rounding),
+        format_money(bundle.summary.final_ada_price, usd_format),
+        format_money(bundle.summary.total, usd_format),
+        bundle.summary.yield_as_percentage,
+        bundle.summary.days
+    );
+    match &bundle.svg {
+        Some(svg) => println!("Embedded Graph: SVG ({} bytes)", svg.len()),
+        None => println!("Embedded Graph: None"),
+    }
+    Ok(())
+}
+
+// The cached quote while it is fresh (or any cached one with --offline) otherwise a live one that is then cached
+// Only a missing cache with --offline is an error the inner Err is a failed request the caller decides how bad that is
+fn current_quote(code: &str, args: &CommandOptions, now: i64) -> Result<Result<(price_api::CachedPrice, &'static str), String>, CliError> {
+    let cached = price_api::read_cache(code);
+    let fresh = cached
+        .as_ref()
+        .filter(|cached| !args.refresh_price && (now - cached.fetched_at) < args.price_cache_ttl as i64);
+    Ok(match (args.offline, fresh) {
+        (true, _) => match cached.clone() {
+            Some(cached) => Ok((cached, "cached")),
+            None => {
+                return Err(CliError::Api(format!(
+                    "--offline was given but there is no cached {} price to use",
+                    code.to_ascii_uppercase()
+                )))
+            }
+        },
+        (false, Some(fresh)) => Ok((fresh.clone(), "cached")),
+        (false, None) => price_api::fetch_ada_price_in(code).map(|(usd, fiat)| {
+            let fetched = price_api::CachedPrice { currency: String::from(code), usd, fiat, fetched_at: now };
+            price_api::write_cache(&fetched);
+            (fetched, "live")
+        }),
+    })
+}
+
+// "CoinGecko live price $0.45 (0.41 EUR) fetched <time>" shared by the Price Source line and fetch-price
+fn describe_quote(quote: &price_api::CachedPrice, origin: &str, now: i64) -> String {
+    let quoted = if quote.currency == "usd" {
+        String::new()
+    } else {
+        format!(" ({} {})", quote.fiat, quote.currency.to_ascii_uppercase())
+    };
+    let age = if origin == "cached" {
+        format!(" {} ago", describe_age(now - quote.fetched_at))
+    } else {
+        String::new()
+    };
+    let fetched_at = chrono::Local
+        .timestamp(quote.fetched_at, 0)
+        .format("%Y-%m-%d %H:%M:%S %:z");
+    format!(
+        "{} {} price ${}{} fetched {}{}",
+        price_api::SOURCE_NAME,
+        origin,
+        quote.usd,
+        quoted,
+        fetched_at,
+        age
+    )
+}
+
+// Replaces initial_price with the live quote keeping the configured price when the request fails returns the Price Source line
+fn seed_live_price(pool: &mut StakedCardanoPool, args: &CommandOptions, warnings: &mut Vec<String>) -> Result<String, CliError> {
+    let code = pool.price_api_currency.clone().unwrap_or_else(|| String::from("usd")).to_ascii_lowercase();
+    let now = chrono::Local::now().timestamp();
+    let (quote, origin) = match current_quote(&code, args, now)? {
+        Ok(quote) => quote,
+        Err(e) => {
+            warnings.push(format!("fetch_price_via_api is set but the live price could not be fetched: {}", e));
+            return Ok(format!(
+                "Price Source: initial_price ${} from the config (live price unavailable: {})",
+                pool.initial_price, e
+            ));
+        }
+    };
+    let source = format!(
+        "Price Source: {} (initial_price {} ignored)",
+        describe_quote(&quote, origin, now),
+        pool.initial_price
+    );
+    pool.initial_price = quote.usd;
+    Ok(source)
+}
+
+// fetch-price prints the ADA price (honoring the cache, --offline and --refresh-price) and exits
+fn print_current_price(code: &str, args: &CommandOptions) -> Result<(), CliError> {
+    let code = code.to_ascii_lowercase();
+    let now = chrono::Local::now().timestamp();
+    let (quote, origin) = current_quote(&code, args, now)?.map_err(|e| CliError::Api(format!("Could not fetch the ADA price: {}", e)))?;
+    println!("ADA Price: {}", describe_quote(&quote, origin, now));
+    Ok(())
+}
+
+// Rough age of a cached price like 12 minutes or 3 hours
+fn describe_age(seconds: i64) -> String {
+    let (amount, unit) = match seconds.max(0) {
+        s if s < 60 => (s, "second"),
+        s if s < 3600 => (s / 60, "minute"),
+        s if s < 86400 => (s / 3600, "hour"),
+        s => (s / 86400, "day"),
+    };
+    format!("{} {}{}", amount, unit, if amount == 1 { "" } else { "s" })
+}
+
+// The line saying whether the target was reached analysis::apply_target has made that day the horizon when it was
+fn describe_target(pool: &mut StakedCardanoPool, target: Target, args: &CommandOptions) -> String {
+    let described = match target {
+        Target::Usd(usd) => format_money(usd, args.usd_format),
+        Target::Ada(ada) => format!("{} ADA", format_ada(ada, args.usd_format.rounding)),
+    };
+    match analysis::apply_target(pool, target, args.target_max_years, &args.simulation_options()) {
+        Some(day) => format!(
+            "Target {} Reached: Day {} (Year {:.2}) The Results Below Are For That Day",
+            described,
+            day,
+            day as f64 / pool.days_per_year()
+        ),
+        None => format!(
+            "Target {} Not Reached Within {} Years The Results Below Are For The Whole {} Years",
+            described, args.target_max_years, args.target_max_years
+        ),
+    }
+}
+
+// Sets the pool's ADA to what reaches the target only the solved pool is run with the CSV, graph and verbose trace
+fn describe_initial_ada(pool: &mut StakedCardanoPool, target: f64, args: &CommandOptions) -> Result<Vec<String>, String> {
+    let solution = analysis::solve_initial_ada(pool, target, &args.simulation_options()).map_err(|e| match e {
+        SolveError::InvalidTarget(target) => format!("--solve-initial-ada must be greater than 0 (found {})", target),
+        SolveError::NotANumber(ada) => format!("The total with {} ADA is not a number check the pool inputs", ada),
+        SolveError::Unreachable(high) => format!(
+            "No starting ADA up to {} reaches {} the pool may be losing value",
+            high,
+            format_money(target, args.usd_format)
+        ),
+    })?;
+    pool.ada = solution.ada;
+    let mut lines = vec![format!(
+        "Initial ADA Needed For {} After {} Years: {} ADA Costing {} At {}",
+        format_money(target, args.usd_format),
+        pool.years_holding,
+        format_ada(solution.ada, args.usd_format.rounding),
+        format_money(solution.ada * pool.initial_price, args.usd_format),
+        args.usd_format.currency.show(pool.initial_price)
+    )];
+    if args.verbose {
+        lines.push(format!(
+            "  Solver: {} Iterations Residual {:+.6} USD{}",
+            solution.iterations,
+            solution.residual,
+            if solution.residual.abs() > SOLVE_TOLERANCE_USD { " (Stopped At The Iteration Limit)" } else { "" }
+        ));
+    }
+    Ok(lines)
+}
+
+// Whether the periodic withdrawals ran the ADA out and when the withdrawn money is spent so it is not in the final total
+fn report_withdrawals(pool: &StakedCardanoPool, result: &StakedCardanoPoolResult, args: &CommandOptions, output_to_stdout: bool) {
+    let mut lines = vec![format!(
+        "Withdrawals: {} Every {} Days Sold {} ADA For {}",
+        args.usd_format.currency.show(pool.withdrawal_usd_per_interval),
+        pool.withdrawal_interval_days,
+        format_ada(result.periodic_withdrawn_ada, args.usd_format.rounding),
+        format_money(result.periodic_withdrawn_usd, args.usd_format)
+    )];
+    match result.depleted_day {
+        Some(day) => lines.push(format!(
+            "  Funds Exhausted On Day {} (Year {:.1}) The Simulation Stopped There",
+            day,
+            day as f64 / pool.days_per_year()
+        )),
+        None => lines.push(format!("  Funds Lasted All {} Days", pool.simulated_days())),
+    }
+    for line in lines.iter() {
+        print_result_line(line, output_to_stdout);
+    }
+}
+
+// $100 or 50 ADA or both for a contribution that does both
+fn describe_contribution(contribution: &Contribution, args: &CommandOptions) -> String {
+    let ada = format!("{} {}", contribution.amount_ada, ada_symbol(args));
+    match (contribution.amount_usd > 0.0, contribution.amount_ada > 0.0) {
+        (true, true) => format!("{} + {}", args.usd_format.currency.show(contribution.amount_usd), ada),
+        (false, true) => ada,
+        _ => args.usd_format.currency.show(contribution.amount_usd),
+    }
+}
+
+// The pool as it was run (after overrides and backtest prices) comes back with the result for the graph and report
+// Overrides and the price history go on the pool before it is checked the notes about it are printed once it passes
+fn prepare_pool(pool_info: &mut StakedCardanoPool, args: &CommandOptions, output_to_stdout: bool) -> Result<(), CliError> {
+    args.pool_overrides.apply(pool_info);
+    let price_history = match &args.price_history {
+        Some(path) => Some((path, read_price_history(path).map_err(CliError::Parse)?)),
+        None => None,
+    };
+    if let Some((_, history)) = &price_history {
+        apply_price_history(pool_info, history);
+    }
+    // Checked before anything runs an epoch_in_days of 0 would otherwise panic on the pay day modulo
+    pool_info.validate().map_err(|e| CliError::invalid_pool("", &e))?;
+    if let Some((path, history)) = &price_history {
+        print_diagnostic(
+            &format!(
+                "Price History: {} Days From {} To {} Read From {}",
+                history.prices.len(),
+                history.start,
+                pool_info.date_on(history.prices.len() as u64 - 1),
+                path
+            ),
+            output_to_stdout,
+        );
+        if history.filled > 0 {
+            print_diagnostic(
+                &format!("Warning: {} days were missing from {} they use the price of the day before", history.filled, path),
+                output_to_stdout,
+            );
+        }
+    }
+    if args.graph_include_real && pool_info.annual_inflation == 0.0 {
+        print_diagnostic("Note: --graph-include-real needs annual_inflation in the pool the graph has no real total line", output_to_stdout);
+    }
+    for warning in pool_info.price_event_warnings() {
+        print_diagnostic(&format!("Warning: {}", warning), output_to_stdout);
+    }
+    if let Some(note) = pool_info.deprecation_note() {
+        print_diagnostic(&format!("Note: {}", note), output_to_stdout);
+    }
+    if !args.pool_overrides.is_empty() {
+        print_diagnostic(&args.pool_overrides.describe(pool_info, args.pool_from_args), output_to_stdout);
+    }
+    Ok(())
+}
+
+// Final Result and the lines right under it the yearly table follows when asked for
+fn print_final_result(pool_info: &StakedCardanoPool, result: &StakedCardanoPoolResult, args: &CommandOptions, output_to_stdout: bool) {
+    let gainz = result.net_return_as_percentage(pool_info);
+    let break_even = match result.break_even_day {
+        Some(day) => format!("Day {} ({})", day, pool_info.date_on(day)),
+        None => String::from("Never"),
+    };
+    // The HTML page keeps the plain lines the box is only for the terminal
+    if args.pretty && !output_to_stdout {
+        print_summary_box(pool_info, result, &break_even, args);
+    } else {
+        let gainz_text = format!("Gainz: {:+.2}% ({:.2}x)", gainz, result.value_multiple(pool_info));
+        print_result_line(
+            &format!(
+                "Final Result: {} ADA @ {} = {} {}",
+                format_ada(result.final_ada_amount, args.usd_format.rounding),
+                format_money(result.final_ada_price, args.usd_format),
+                format_money(result.total(), args.usd_format),
+                paint_gain(gainz_text, gainz >= 0.0, args)
+            ),
+            output_to_stdout,
+        );
+        print_result_line(
+            &format!(
+                "ADA Growth (Price Independent): {:+.2}% ADA CAGR: {:.2}%",
+                result.ada_growth_as_percentage(pool_info),
+                result.ada_cagr(pool_info) * 100.0
+            ),
+            output_to_stdout,
+        );
+        print_result_line(
+            &format!(
+                "CAGR: {:+.2}% Break-Even: {}",
+                result.cagr(pool_info) * 100.0,
+                break_even
+            ),
+            output_to_stdout,
+        );
+        // The same ADA held without staking the price moves either way so the gap is what staking earned
+        let held_ada = result.held_ada(pool_info);
+        print_result_line(
+            &format!(
+                "Staked Vs Held: Holding {} ADA Ends At {} Staking Ends At {} Staking Adds {} ({} ADA) {:+.2}% Over Holding",
+                format_ada(held_ada, args.usd_format.rounding),
+                format_money(result.hold_total(pool_info), args.usd_format),
+                format_money(result.total(), args.usd_format),
+                format_money(result.total() - result.hold_total(pool_info), args.usd_format),
+                format_ada(result.final_ada_amount + result.withdrawn_ada - held_ada, args.usd_format.rounding),
+                result.staking_advantage_as_percentage(pool_info)
+            ),
+            output_to_stdout,
+        );
+    }
+    if args.summary_table {
+        print_yearly_summary(result, args, output_to_stdout);
+    }
+}
+
+// One line per pool feature that changed the result only the ones the pool uses are printed
+fn print_run_notes(pool_info: &StakedCardanoPool, result: &StakedCardanoPoolResult, args: &CommandOptions, output_to_stdout: bool) {
+    if pool_info.annual_inflation != 0.0 {
+        print_result_line(
+            &format!(
+                "Inflation Adjusted ({}% Per Year): Nominal Total: {} Real Total (Today's Dollars): {} Real Gainz: {:+.2}% Real CAGR: {:+.2}%",
+                pool_info.annual_inflation * 100.0,
+                format_money(result.total(), args.usd_format),
+                format_money(result.real_total(pool_info), args.usd_format),
+                result.real_return_as_percentage(pool_info),
+                result.real_cagr(pool_info) * 100.0
+            ),
+            output_to_stdout,
+        );
+    }
+    if result.undelegated_days > 0 {
+        print_result_line(
+            &format!(
+                "Undelegated: {} Days Forgone Rewards: {} ADA ({} at final price)",
+                result.undelegated_days,
+                format_ada(result.forgone_rewards, args.usd_format.rounding),
+                format_money(result.forgone_rewards * result.final_ada_price, args.usd_format)
+            ),
+            output_to_stdout,
+        );
+    }
+    if result.in_flight_rewards > 0.0 {
+        print_result_line(
+            &format!(
+                "In Flight Rewards: {} ADA ({} at final price) Earned In The Last {} Epochs Are Paid After The Horizon (Not In The Final Total)",
+                format_ada(result.in_flight_rewards, args.usd_format.rounding),
+                format_money(result.in_flight_rewards * result.final_ada_price, args.usd_format),
+                pool_info.reward_lag_epochs
+            ),
+            output_to_stdout,
+        );
+    }
+    if result.transactions > 0 {
+        print_result_line(
+            &format!(
+                "Transaction Fees: {} Transactions Cost {} ADA ({} When Paid)",
+                result.transactions,
+                format_ada(result.tx_fees_ada, args.usd_format.rounding),
+                format_money(result.tx_fees_usd, args.usd_format)
+            ),
+            output_to_stdout,
+        );
+    }
+    if !pool_info.slash_events.is_empty() {
+        print_result_line(
+            &format!(
+                "Slashing: {} Events Lost {} ADA ({} at final price)",
+                pool_info.slash_events.len(),
+                format_ada(result.slashed_ada, args.usd_format.rounding),
+                format_money(result.slashed_ada * result.final_ada_price, args.usd_format)
+            ),
+            output_to_stdout,
+        );
+    }
+    if !pool_info.price_events.is_empty() {
+        let described: Vec<String> = pool_info
+            .price_events
+            .iter()
+            .map(|event| match pool_info.price_event_day(event) {
+                Some(day) => format!("{} (Day {})", event.describe(), day),
+                None => format!("{} (Whole Run)", event.describe()),
+            })
+            .collect();
+        print_result_line(&format!("Price Events: {}", described.join(", ")), output_to_stdout);
+    }
+    if pool_info.has_yield_schedule() && pool_info.reserve_ada.is_none() && pool_info.pool_share(pool_info.ada).is_none() {
+        print_result_line(
+            &format!(
+                "Yield Schedule: Annual Yield Went From {:.2}% To {:.2}% (Before Fees)",
+                pool_info.annual_yield_on(1) * 100.0,
+                result.final_annual_yield * 100.0
+            ),
+            output_to_stdout,
+        );
+    }
+    if let (Some(start), Some(end)) = (pool_info.reserve_ada, result.final_reserve) {
+        print_result_line(
+            &format!(
+                "Reserve Emission: {:.0} ADA Left Of {:.0} ADA Yield Declined From {:.2}% To {:.2}% (Before Fees)",
+                end,
+                start,
+                pool_info.reserve_annual_yield(start) * 100.0,
+                pool_info.reserve_annual_yield(end) * 100.0
+            ),
+            output_to_stdout,
+        );
+    }
+    if pool_info.restake_every_n_epochs > 1 {
+        print_result_line(
+            &format!(
+                "Pending Rewards (Not Yet Restaked, Restaking Every {} Epochs): {} ADA",
+                pool_info.restake_every_n_epochs, format_ada(result.pending_rewards, args.usd_format.rounding)
+            ),
+            output_to_stdout,
+        );
+    }
+    if pool_info.reward_ada_fraction != 1.0 {
+        print_result_line(
+            &format!(
+                "Reward Split: {} ADA ({}) + {} {} = {}",
+                format_ada(result.final_ada_amount, args.usd_format.rounding),
+                format_money(result.final_ada_amount * result.final_ada_price, args.usd_format),
+                format_money(result.usd_balance, args.usd_format),
+                args.usd_format.currency.code,
+                format_money(result.total(), args.usd_format)
+            ),
+            output_to_stdout,
+        );
+    }
+    if !pool_info.compound {
+        print_result_line(
+            &format!(
+                "Harvested Rewards (Not Restaked): Principal {} ADA ({}) + Harvested {} ADA ({} At The Payout Prices) = {}",
+                format_ada(result.final_ada_amount, args.usd_format.rounding),
+                format_money(result.staked_total(), args.usd_format),
+                format_ada(result.withdrawn_ada, args.usd_format.rounding),
+                format_money(result.withdrawn_usd, args.usd_format),
+                format_money(result.total(), args.usd_format)
+            ),
+            output_to_stdout,
+        );
+    }
+    if let Some((unit, reference_price)) = &args.denominate_in {
+        print_result_line(
+            &format!(
+                "Final Total In {}: {:.6} {} (@ {} Per {})",
+                unit,
+                result.total() / reference_price,
+                unit,
+                format_money(*reference_price, args.usd_format),
+                unit
+            ),
+            output_to_stdout,
+        );
+    }
+    if let Some((day, ada, price)) = result.baseline {
+        print_result_line(
+            &format!(
+                "Baseline After {} Epochs (Day {}): {} Gainz Since Baseline: {:.2}%",
+                args.skip_epochs,
+                day,
+                format_holding(ada, price, args.usd_format),
+                (result.total() / (ada * price) - 1.0) * 100.0
+            ),
+            output_to_stdout,
+        );
+    }
+}
+
+// Breakdowns and comparisons asked for on the command line along with the reward tax
+fn report_analyses(pool_info: &StakedCardanoPool, result: &StakedCardanoPoolResult, args: &CommandOptions, output_to_stdout: bool) -> Result<(), CliError> {
+    if args.explain_fees {
+        print_fee_breakdown(result, args, output_to_stdout);
+    }
+    if args.compare_simple {
+        compare_compounding_vs_simple(pool_info, result, args, output_to_stdout);
+    }
+    if let Some((income_rate, gains_rate)) = args.compare_tax {
+        compare_tax_scenarios(pool_info, result, income_rate, gains_rate, args, output_to_stdout);
+    }
+    if let Some(runs) = args.monte_carlo {
+        report_monte_carlo(pool_info, runs, args, output_to_stdout)?;
+    }
+    if let Some(pct) = args.sensitivity {
+        print_sensitivity(&sensitivity_rows(pool_info, result, pct, &args.simulation_options()), pct, args, output_to_stdout);
+    }
+    if let Some(drop_pct) = args.break_even {
+        let analysis = break_even_analysis(pool_info, drop_pct, &args.simulation_options()).map_err(CliError::Validation)?;
+        print_break_even(pool_info, &analysis, args, output_to_stdout);
+    }
+    if pool_info.reward_tax_rate > 0.0 {
+        print_result_line(
+            &format!(
+                "Reward Tax ({}%): Gross Total: {} Reward Income: {} Tax Owed: {} After Tax Total: {}",
+                pool_info.reward_tax_rate * 100.0,
+                format_money(result.total(), args.usd_format),
+                format_money(result.reward_income_usd, args.usd_format),
+                format_money(result.tax_owed(pool_info), args.usd_format),
+                format_money(result.after_tax_total(pool_info), args.usd_format)
+            ),
+            output_to_stdout,
+        );
+    }
+    if args.tax_report {
+        report_tax_income(pool_info, result, args, output_to_stdout)?;
+    }
+    Ok(())
+}
+
+// Money put in and taken out over the run plus the caps that stopped the balance
+fn print_cash_flows(pool_info: &StakedCardanoPool, result: &StakedCardanoPoolResult, args: &CommandOptions, output_to_stdout: bool) {
+    if let Some(contribution) = &pool_info.contribution {
+        print_result_line(
+            &format!(
+                "Contributions: {} Every {} Days Put In {} Bought {} ADA (Gainz Are Measured Against {} Total Put In)",
+                describe_contribution(contribution, args),
+                contribution.cadence_days,
+                format_money(result.periodic_usd, args.usd_format),
+                format_ada(result.periodic_ada, args.usd_format.rounding),
+                format_money(pool_info.ada * pool_info.initial_price + result.contributed_usd, args.usd_format)
+            ),
+            output_to_stdout,
+        );
+    }
+    if pool_info.withdrawal_usd_per_interval > 0.0 {
+        report_withdrawals(pool_info, result, args, output_to_stdout);
+    }
+    if !pool_info.lump_sums.is_empty() {
+        report_lump_sums(pool_info, result, args, output_to_stdout);
+    }
+    if result.slippage_usd > 0.0 {
+        print_result_line(&format!("Slippage Cost: {}", format_money(result.slippage_usd, args.usd_format)), output_to_stdout);
+    }
+    if let Some(day) = result.price_cap_day {
+        print_result_line(
+            &format!(
+                "Price Cap: {} reached on day {}",
+                format_money(pool_info.max_price.unwrap_or_default(), args.usd_format),
+                day
+            ),
+            output_to_stdout,
+        );
+    }
+    if let (Some(day), Some(limit)) = (result.saturated_day, pool_info.saturation_limit_ada) {
+        let line = if pool_info.auto_redelegate {
+            format!(
+                "Pool Saturated: Day {} (Year {:.1}) Passed {} ADA Redelegated To A Fresh Pool So Rewards Were Not Scaled Down",
+                day,
+                day as f64 / pool_info.days_per_year(),
+                limit
+            )
+        } else {
+            format!(
+                "Pool Saturated: Day {} (Year {:.1}) Passed {} ADA Rewards Scaled By {:.4} At The End",
+                day,
+                day as f64 / pool_info.days_per_year(),
+                limit,
+                pool_info.stake_saturation(result.final_ada_amount)
+            )
+        };
+        print_result_line(&line, output_to_stdout);
+    }
+}
+
+// Files written after the run the CSV or workbook was streamed during it so only its failure is checked here
+fn write_run_files(pool_info: &StakedCardanoPool, result: &StakedCardanoPoolResult, args: &CommandOptions) -> Result<(), CliError> {
+    if let Some(path) = &args.dump_series {
+        write_result(write_series(path, result), "The series", args)?;
+    }
+    if let Some(path) = &args.bundle_path {
+        write_result(write_bundle(path, pool_info, result, args), "The bundle", args)?;
+    }
+    if let Some(path) = &args.record_path {
+        write_result(append_ledger(path, pool_info, result), "The history ledger", args)?;
+    }
+    write_result(!result.write_failed, "The CSV or workbook", args)
+}
+
+fn execute_json(buffer: &str, args: &CommandOptions, output_to_stdout : bool) -> Result<Option<(StakedCardanoPool, StakedCardanoPoolResult)>, CliError> {
+    let buffer = &*apply_date_format(buffer, args.date_format.as_deref()).map_err(CliError::Parse)?;
+    for warning in unknown_pool_keys(buffer) {
+        print_diagnostic(&warning, output_to_stdout);
+    }
+    if let Some(wallets) = parse_wallets(buffer).map_err(CliError::Parse)? {
+        let wallets = wallets
+            .into_iter()
+            .map(|wallet| {
+                let mut pool = wallet.pool;
+                args.pool_overrides.apply(&mut pool);
+                pool.validate().map_err(|e| CliError::invalid_pool(&wallet.name, &e))?;
+                Ok((wallet.name, pool))
+            })
+            .collect::<Result<Vec<_>, CliError>>()?;
+        if args.dry_run {
+            print_dry_run(&wallets, args, output_to_stdout);
+        } else {
+            run_portfolio(wallets, args, output_to_stdout)?;
+        }
+        return Ok(None);
+    }
+    let mut pool_info = match parse_scenarios(buffer) {
+        Ok(scenarios) if scenarios.len() > 1 && args.dry_run => {
+            let pools = scenarios
+                .into_iter()
+                .enumerate()
+                .map(|(index, scenario)| {
+                    let name = if scenario.name.is_empty() {
+                        format!("Scenario {}", index + 1)
+                    } else {
+                        scenario.name
+                    };
+                    let mut pool = scenario.pool;
+                    args.pool_overrides.apply(&mut pool);
+                    pool.validate().map_err(|e| CliError::invalid_pool(&name, &e))?;
+                    Ok((name, pool))
+                })
+                .collect::<Result<Vec<_>, CliError>>()?;
+            print_dry_run(&pools, args, output_to_stdout);
+            return Ok(None);
+        }
+        Ok(scenarios) if scenarios.len() > 1 => {
+            run_scenarios(scenarios, args, output_to_stdout)?;
+            return Ok(None);
+        }
+        Ok(mut scenarios) => scenarios.remove(0).pool,
+        Err(e) => return Err(CliError::Parse(e)), // serde reports the line and column of the problem
+    };
+    prepare_pool(&mut pool_info, args, output_to_stdout)?;
+    let mut warnings: Vec<String> = Vec::new();
+    let price_source = if pool_info.fetch_price_via_api {
+        Some(seed_live_price(&mut pool_info, args, &mut warnings)?)
+    } else {
+        None
+    };
+    if !args.sweep.is_empty() {
+        report_sweep(&pool_info, args, output_to_stdout)?;
+        return Ok(None);
+    }
+    let configured_ada = pool_info.ada;
+    let history = match &args.reward_history {
+        Some(path) => Some(read_reward_history(path, &pool_info).map_err(CliError::Parse)?),
+        None => None,
+    };
+    if let Some(history) = &history {
+        pool_info.ada += history.total_reward;
+        if args.use_realized_yield {
+            pool_info.annual_yield = history.realized_yield;
+        }
+    }
+    clamp_annual_yield(&mut pool_info, args, &mut warnings);
+    if args.dry_run {
+        print_dry_run(&[(String::new(), pool_info)], args, output_to_stdout);
+        return Ok(None);
+    }
+    let target_line = args.target.map(|target| describe_target(&mut pool_info, target, args));
+    let solve_lines = match args.solve_initial_ada {
+        Some(target) => Some(describe_initial_ada(&mut pool_info, target, args).map_err(CliError::Validation)?),
+        None => None,
+    };
+    if args.summary_only {
+        let result = calculate_closed_form(&pool_info, &args.simulation_options()).map_err(CliError::Validation)?;
+        println!(
+            "Final Result: {} ADA @ {} = {}",
+            format_ada(result.final_ada_amount, args.usd_format.rounding),
+            format_money(result.final_ada_price, args.usd_format),
+            format_money(result.total(), args.usd_format)
+        );
+        return Ok(None);
+    }
+    if output_to_stdout {
+        println!("<div class='output'>"); // Refactor this to a function
+    }
+    if let Some(source) = &price_source {
+        print_result_line(source, output_to_stdout);
+    }
+    if let Some(history) = &history {
+        print_result_line(
+            &format!(
+                "Reward History: {} Epochs Seeded Starting Balance: {} ADA ({} Configured + {} From Rewards) Realized Yield: {:.2}%{}",
+                history.epochs,
+                format_ada(pool_info.ada, args.usd_format.rounding),
+                format_ada(configured_ada, args.usd_format.rounding),
+                format_ada(history.total_reward, args.usd_format.rounding),
+                history.realized_yield * 100.0,
+                if args.use_realized_yield { " (Used For Projection)" } else { "" }
+            ),
+            output_to_stdout,
+        );
+    }
+    for line in target_line.iter().chain(solve_lines.iter().flatten()) {
+        print_result_line(line, output_to_stdout);
+    }
+    print_starting_result(&pool_info, args, output_to_stdout);
+    let result = run_simulation(&pool_info, args, output_to_stdout);
+    print_final_result(&pool_info, &result, args, output_to_stdout);
+    print_run_notes(&pool_info, &result, args, output_to_stdout);
+    report_analyses(&pool_info, &result, args, output_to_stdout)?;
+    print_cash_flows(&pool_info, &result, args, output_to_stdout);
+    if let Some(stats) = result.history_statistics() {
+        print_statistics(&stats, args, output_to_stdout);
+    }
+    check_extreme_result(&pool_info, &result, args, output_to_stdout, &mut warnings);
+    if args.warn_negative_yield {
+        check_negative_result(&pool_info, &result, args, output_to_stdout, &mut warnings);
+    }
+    if args.sanity_report {
+        warnings.extend(pool_info.sanity_warnings());
+        print_sanity_report(&warnings, output_to_stdout);
+    }
+    if !args.no_footer {
+        print_assumptions_footer(&pool_info, args, output_to_stdout);
+    }
+    write_run_files(&pool_info, &result, args)?;
+    if output_to_stdout {
+        println!("<br>"); // Refactor this to a function
+        println!("</div>"); // Refactor this to a function
+        if args.monte_carlo.is_none() {
+            generate_graph(None, Some(&pool_info), &result, args, output_to_stdout);
+        }
+    }
+
+    if output_to_stdout {
+        Ok(None)
+    }
+    else {
+        Ok(Some((pool_info, result)))
+    }
+}
+
+// The bench subcommand the timings come from analysis::bench
+fn bench_pool_file(path: &str, args: &CommandOptions) -> Result<(), CliError> {
+    let pool = read_pool_file(path, args.date_format.as_deref()).map_err(|e| e.map_message(|message| format!("{}: {}", path, message)))?;
+    // Nothing is recorded while timing
+    let quiet_args = CommandOptions {
+        generate_graph: false,
+        dump_series: None,
+        skip_epochs: 0,
+        ..args.clone()
+    };
+    let rows = analysis::bench(&pool, &quiet_args.simulation_options()).map_err(CliError::Validation)?;
+    println!(
+        "{:>6} {:>14} {:>18} {:>10}",
+        "Years", "Loop (ms)", "Closed Form (ms)", "Speedup"
+    );
+    for row in rows.iter() {
+        println!(
+            "{:>6} {:>14.4} {:>18.6} {:>9.0}x",
+            row.years,
+            row.loop_ms,
+            row.closed_form_ms,
+            row.loop_ms / row.closed_form_ms.max(f64::EPSILON)
+        );
+    }
+    Ok(())
+}
+
+// --ndjson a bad line gets an error object and the stream carries on so it only fails when stdin breaks
+fn run_ndjson(args: &CommandOptions) -> Result<(), CliError> {
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    for (index, line) in stdin.lock().lines().enumerate() {
+        let line = line.map_err(|e| CliError::MissingConfig(format!("Failed to read stdin: {}", e)))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        for warning in unknown_pool_keys(&line) {
+            eprintln!("Line {}: {}", index + 1, warning);
+        }
+        let outcome = apply_date_format(&line, args.date_format.as_deref())
+            .and_then(|line| parse_pool(&line))
+            .map(|mut pool| {
+                args.pool_overrides.apply(&mut pool);
+                pool
+            })
+            .and_then(|pool| pool.validate().map(|_| pool))
+            .and_then(|mut pool| {
+                clamp_annual_yield(&mut pool, args, &mut Vec::new());
+                let result = if args.summary_only {
+                    calculate_closed_form(&pool, &args.simulation_options())?
+                } else {
+                    run_simulation(&pool, args, false)
+                };
+                Ok(RunSummary::new(&pool, &result))
+            });
+        let (summary, error) = match outcome {
+            Ok(summary) => (Some(summary), None),
+            Err(e) => (None, Some(e)),
+        };
+        let record = NdjsonResult {
+            line: index + 1,
+            summary,
+            error,
+        };
+        let written = serde_json::to_string(&record)
+            .map_err(std::io::Error::from)
+            .and_then(|text| writeln!(out, "{}", text))
+            .and_then(|_| out.flush()); // Downstream readers see each result as soon as it is ready
+        if written.is_err() {
+            return Ok(()); // The reader went away (for example head) nothing left to do
+        }
+    }
+    Ok(())
+}
+
+// --json errors go to stderr so stdout only ever holds the JSON object
+fn run_json_report(args: &CommandOptions) -> Result<(), CliError> {
+    let buffer = match &args.json_option {
+        Some(buffer) => buffer.clone(),
+        None if args.pool_from_args => String::from(EXAMPLE_POOL_JSON),
+        None => {
+            let buffer = read_pool_source(&args.input_path)
+                .map_err(|e| CliError::MissingConfig(format!("{}: {}", args.input_path, e)))?;
+            pool_file_to_json(&args.input_path, buffer).map_err(CliError::Parse)?
+        }
+    };
+    for warning in unknown_pool_keys(&buffer) {
+        eprintln!("{}", warning);
+    }
+    let mut pool = apply_date_format(&buffer, args.date_format.as_deref())
+        .and_then(|buffer| parse_pool(&buffer))
+        .map_err(CliError::Parse)?;
+    args.pool_overrides.apply(&mut pool);
+    if let Some(path) = &args.price_history {
+        apply_price_history(&mut pool, &read_price_history(path).map_err(CliError::Parse)?);
+    }
+    pool.validate().map_err(CliError::Validation)?;
+    clamp_annual_yield(&mut pool, args, &mut Vec::new());
+    let result = if args.summary_only {
+        calculate_closed_form(&pool, &args.simulation_options()).map_err(CliError::Validation)?
+    } else {
+        run_simulation(&pool, args, false)
+    };
+    let report = JsonReport {
+        summary: RunSummary::new(&pool, &result),
+        pool: &pool,
+        amount_historical: args.include_history.then_some(result.amount_historical.as_slice()),
+        price_historical: args.include_history.then_some(result.price_historical.as_slice()),
+        yearly: if args.summary_table { result.yearly.as_slice() } else { &[] },
+        sensitivity: args.sensitivity.map(|pct| sensitivity_rows(&pool, &result, pct, &args.simulation_options())),
+        break_even: args
+            .break_even
+            .map(|drop_pct| break_even_analysis(&pool, drop_pct, &args.simulation_options()))
+            .transpose()
+            .map_err(CliError::Validation)?,
+    };
+    let text = serde_json::to_string_pretty(&report).map_err(|e| CliError::Write(e.to_string()))?;
+    println!("{}", text);
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    // Every failure comes back up here so this is the one place the process exits with its code
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+fn run() -> Result<(), CliError> {
+    let args = &get_command_options()?;
+    if let Some((path, interactive, force)) = &args.init_file {
+        return init_pool_file(path, *interactive, *force);
+    }
+    if args.ndjson {
+        return run_ndjson(args);
+    }
+    if args.json_output {
+        return run_json_report(args);
+    }
+    if let Some(path) = &args.bench_file {
+        return bench_pool_file(path, args);
+    }
+    if let Some(shell) = args.completions {
+        clap_complete::generate(shell, &mut build_command(), "ada_calc", &mut std::io::stdout());
+        return Ok(());
+    }
+    if let Some(path) = &args.validate_file {
+        return validate_pool_file(path, args.date_format.as_deref());
+    }
+    if let Some(path) = &args.open_bundle_file {
+        return open_bundle_file(path, args.usd_format);
+    }
+    if let Some((csv_path, out)) = &args.graph_csv {
+        return graph_from_csv(csv_path, out.as_deref(), args);
+    }
+    if let Some(code) = &args.fetch_price {
+        return print_current_price(code, args);
+    }
+    if let Some(path) = &args.history_file {
+        return print_ledger(path);
+    }
+    if let Some((input, output)) = &args.convert_files {
+        return convert_pool_file(input, output);
+    }
+    if let Some(interval) = args.watch {
+        return watch_pool_file(args, interval);
+    }
+    run_pools(args)
+}
+
+// Default --watch-interval
+const DEFAULT_WATCH_INTERVAL_MS: u64 = 500;
+
+// --watch only returns when the pool file cannot be watched otherwise it runs until Ctrl-C
+// A failed run is reported and the next save is waited for so a typo in the editor does not end the session
+fn watch_pool_file(args: &CommandOptions, interval: std::time::Duration) -> Result<(), CliError> {
+    let path = &args.input_path;
+    if args.pool_from_args || args.input_paths.len() > 1 || path == STDIN_PATH {
+        return Err(CliError::Parse(String::from(
+            "--watch needs exactly one pool file given with -i (or pool.json in the current directory)",
+        )));
+    }
+    // Stable names with --force so each run replaces the last run's files
+    let watch_args = CommandOptions {
+        force: true,
+        timestamp_format: TimestampFormat::Latest,
+        ..args.clone()
+    };
+    let modified = || std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    let mut last_run = None;
+    loop {
+        let current = modified();
+        if current != last_run {
+            // Editors can write a file in several steps so it has to stay the same for one interval first
+            std::thread::sleep(interval);
+            if modified() != current {
+                continue;
+            }
+            last_run = current;
+            println!();
+            println!("===== {} {} =====", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), path);
+            match current {
+                Some(_) => {
+                    if let Err(e) = run_pools(&watch_args) {
+                        eprintln!("Error: {}", e);
+                    }
+                }
+                None => eprintln!("Error: {} could not be read waiting for it to be saved", path),
+            }
+            println!("Watching {} for changes (Ctrl-C to stop)", path);
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+fn run_pools(args: &CommandOptions) -> Result<(), CliError> {
+    args.csv_dialect.check().map_err(CliError::Validation)?;
+    if args.generate_csv && !args.dry_run {
+        match &args.output_dir {
+            Some(dir) => println!("CSV will be saved in {}.", dir),
+            None => println!("CSV will be saved in current working directory."),
+        }
+    }
+    if let (Some(dir), false) = (&args.output_dir, args.dry_run) {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| CliError::Write(format!("Could not create the output directory {} ({})", dir, e)))?;
+    }
+    if let Some(buffer) = &args.json_option {
+        let html_args = &CommandOptions {
+            color: false, // HTML output never gets terminal escape codes
+            ..args.clone()
+        };
+        execute_json(buffer, html_args, true)?;
+    } else if args.pool_from_args {
+        run_pool_file(String::from(EXAMPLE_POOL_JSON), args)?;
+    } else {
+        if args.input_paths.len() > 1 && (args.csv_out.is_some() || args.svg_out.is_some() || args.xlsx_out.is_some()) {
+            return Err(CliError::Parse(String::from(
+                "--csv-out, --svg-out and --xlsx-out take one path and cannot be used with more than one --input",
+            )));
+        }
+        if args.input_paths.len() > 1 && args.input_paths.iter().any(|path| path == STDIN_PATH) {
+            return Err(CliError::Parse(String::from(
+                "--input - reads one pool from stdin and cannot be combined with other --input files",
+            )));
+        }
+        // Every pool is run even after one fails the first failure decides the exit code
+        let mut failed: Option<CliError> = None;
+        let mut failures = 0;
+        for (index, path) in args.input_paths.iter().enumerate() {
+            let pool_args = &CommandOptions {
+                input_path: path.clone(),
+                output_stem: args.output_stem.as_ref().and_then(|_| file_stem(path)),
+                ..args.clone()
+            };
+            if args.input_paths.len() > 1 {
+                if index > 0 {
+                    println!();
+                }
+                println!("Results for {}:", path);
+            }
+            let run = match read_pool_source(path) {
+                Ok(buffer) => pool_file_to_json(path, buffer)
+                    .map_err(CliError::Parse)
+                    .and_then(|buffer| run_pool_file(buffer, pool_args)),
+                Err(e) => Err(CliError::MissingConfig(format!(
+                    "Failed to find {} or a pool passed through command option!\nReason: {}",
+                    path, e
+                ))),
+            };
+            match (run, args.input_paths.len() > 1) {
+                (Ok(()), _) => {}
+                (Err(e), true) => {
+                    eprintln!("Error: {}", e);
+                    failures += 1;
+                    failed.get_or_insert(e);
+                }
+                (Err(e), false) => return Err(e),
+            }
+        }
+        if let Some(e) = failed {
+            // Each failure was already printed under its pool so only the count is left to report
+            return Err(e.map_message(|_| format!("{} of {} pools failed", failures, args.input_paths.len())));
+        }
+    }
+    Ok(())
+}
+
+// Markdown or HTML report next to the CSV the Markdown graph link is relative when both are in the same directory
+// The HTML report ignores graph since every section carries its own chart
+fn write_report(format: ReportFormat, sections: &[report::Section], graph: Option<&str>, args: &CommandOptions) -> bool {
+    let extension = match format {
+        ReportFormat::Markdown => "md",
+        ReportFormat::Html => "html",
+    };
+    let path = in_output_dir(
+        args.output_dir.as_deref(),
+        &format!("ada_calc_report_{}{}.{}", stem_prefix(args), get_file_timestamp(args.timestamp_format), extension),
+    );
+    let parent = |path: &str| std::path::Path::new(path).parent().map(|dir| dir.to_path_buf());
+    let graph = graph.map(|graph| match parent(graph) == parent(&path) {
+        true => std::path::Path::new(graph)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from(graph)),
+        false => absolute_path(graph),
+    });
+    let generated = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+    let text = match format {
+        ReportFormat::Markdown => report::markdown(sections, &generated, graph.as_deref()),
+        ReportFormat::Html => report::html(sections, &generated),
+    };
+    match open_output_file(&path, args.force).and_then(|mut file| file.write_all(text.as_bytes())) {
+        Ok(()) => {
+            println!("Saved Report as {} to Disk.", absolute_path(&path));
+            true
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to Write Report [{}] to Disk.", path);
+            eprintln!("Reason: {}", describe_write_error(&path, &e));
+            false
+        }
+    }
+}
+
+// Main run for one pool the graph is written after the text output
+fn run_pool_file(buffer: String, args: &CommandOptions) -> Result<(), CliError> {
+    let result = execute_json(&buffer, args, false)?;
+    let graph_path = args.svg_out.clone().unwrap_or_else(|| default_graph_path(args));
+    let mut graph_written = false;
+    // The Monte Carlo envelope was already written in its place
+    if let (true, None, Some(result)) = (args.generate_graph, args.monte_carlo, &result) {
+        write_result(generate_graph(Some(graph_path.clone()), Some(&result.0), &result.1, args, false), "The graph", args)?;
+        graph_written = has_graph_history(&result.1); // A skipped graph leaves no file for the report to link
+    }
+    if let (Some(format), Some((pool, result))) = (args.report, &result) {
+        let section = report::Section {
+            name: "",
+            pool,
+            result,
+            chart: Some(result).filter(|result| has_graph_history(result)).map(|result| render_graph_svg(Some(pool), result, args)),
+        };
+        let graph = Some(graph_path.as_str()).filter(|_| graph_written);
+        write_result(write_report(format, &[section], graph, args), "The report", args)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ada_calc::calculate_staked_pool;
+    use chrono::NaiveDate;
+
+    fn pool(json: &str) -> StakedCardanoPool {
+        let mut pool: StakedCardanoPool = serde_json::from_str(json).expect("test pool parses");
+        pool.start_date.get_or_insert(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        pool
+    }
+
+    const SMALL_POOL: &str = r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05, "epoch_in_days": 5, "years_holding": 1}"#;
+
+    // The options an ada_calc command line with these arguments would run with
+    fn options(args: &[&str]) -> CommandOptions {
+        let argv = std::iter::once("ada_calc").chain(args.iter().copied());
+        command_options(&build_command().try_get_matches_from(argv).expect("test arguments parse")).expect("test options build")
+    }
+
+    fn run_with_history(pool: &StakedCardanoPool) -> StakedCardanoPoolResult {
+        calculate_staked_pool(pool, &SimulationOptions { record_history: true, ..Default::default() })
+    }
+
+    #[test]
+    fn unparsable_numeric_flags_are_usage_errors() {
+        for args in [
+            ["--ada", "abc"],
+            ["--epoch-in-days", "2.5"],
+            ["--target-usd", "lots"],
+            ["--monte-carlo", "0"],
+            ["--skip-epochs", "x"],
+            ["--graph-max-points", "-"],
+            ["--csv-precision", "ten"],
+        ] {
+            let error = build_command().try_get_matches_from(["ada_calc", args[0], args[1]]).unwrap_err();
+            assert_eq!(error.kind(), clap::ErrorKind::ValueValidation, "{:?}", args);
+            assert!(error.to_string().contains(args[0]), "{}", error);
+        }
+        assert!(build_command().try_get_matches_from(["ada_calc", "--ada", "1500.5", "--skip-epochs", "3"]).is_ok());
+    }
+
+    #[test]
+    fn confidence_picks_the_outer_monte_carlo_percentiles() {
+        assert_eq!(monte_carlo_percentiles(DEFAULT_MONTE_CARLO_CONFIDENCE), vec![5.0, 25.0, 50.0, 75.0, 95.0]);
+        assert_eq!(monte_carlo_percentiles(80.0), vec![10.0, 25.0, 50.0, 75.0, 90.0]);
+        assert_eq!(monte_carlo_percentiles(50.0), vec![25.0, 50.0, 75.0]);
+        assert_eq!(monte_carlo_percentiles(40.0), vec![30.0, 50.0, 70.0]);
+        assert!(build_command().try_get_matches_from(["ada_calc", "--monte-carlo", "10", "--confidence", "100"]).is_err());
+        assert!(build_command().try_get_matches_from(["ada_calc", "--monte-carlo", "10", "--confidence", "0.5"]).is_err());
+    }
+
+    #[test]
+    fn date_format_rewrites_every_pool_date() {
+        let buffer = r#"{"start_date": "31/01/2025", "scenarios": [{"name": "a", "epoch_anchor_date": "13/02/2025"}], "ada": 1}"#;
+        let rewritten: serde_json::Value = serde_json::from_str(&apply_date_format(buffer, Some("%d/%m/%Y")).unwrap()).unwrap();
+        assert_eq!(rewritten["start_date"], "2025-01-31");
+        assert_eq!(rewritten["scenarios"][0]["epoch_anchor_date"], "2025-02-13");
+        assert_eq!(apply_date_format(buffer, None).unwrap(), buffer);
+        let error = apply_date_format(r#"{"start_date": "2025.31.01"}"#, Some("%d/%m/%Y")).unwrap_err();
+        assert!(error.contains("start_date 2025.31.01 is not a date the accepted formats are %d/%m/%Y, YYYY-MM-DD"), "{}", error);
+    }
+
+    #[test]
+    fn graph_renders_every_history_point() {
+        let pool = pool(SMALL_POOL);
+        let result = run_with_history(&pool);
+        assert_eq!(result.amount_historical.len(), 366);
+        for layout in ["indexed", "combined"] {
+            let svg = render_graph_svg(Some(&pool), &result, &options(&["-p", SMALL_POOL, "-G", "--graph-layout", layout]));
+            assert!(svg.contains("<svg") && svg.trim_end().ends_with("</svg>"), "{}", layout);
+        }
+    }
+
+    #[test]
+    fn empty_history_skips_the_graph_without_failing() {
+        let pool = pool(r#"{"ada": 1000, "initial_price": 1.0, "price_yield": 1.0, "annual_yield": 0.05, "years_holding": 0.001}"#);
+        let result = run_with_history(&pool);
+        assert!(!has_graph_history(&result));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("graph.svg").display().to_string();
+        assert!(generate_graph(Some(path.clone()), Some(&pool), &result, &options(&["-p", SMALL_POOL, "-G"]), false));
+        assert!(!std::path::Path::new(&path).exists());
+    }
+
+    #[test]
+    fn summary_only_refuses_full_run_flags() {
+        for flag in ["--sensitivity", "--tax-report", "--break-even", "--compare-tax-scenarios", "--warn-negative-yield", "--record"] {
+            let error = build_command().try_get_matches_from(["ada_calc", "--summary-only", flag]).unwrap_err();
+            assert_eq!(error.kind(), clap::ErrorKind::ArgumentConflict, "{}", flag);
+        }
+        for (flag, value) in [("--skip-epochs", "2"), ("--target-usd", "1000"), ("--report", "md"), ("--bundle", "b.json")] {
+            assert!(build_command().try_get_matches_from(["ada_calc", "--summary-only", flag, value]).is_err(), "{}", flag);
+        }
+        assert!(build_command().try_get_matches_from(["ada_calc", "--summary-only", "--compound-exact"]).is_ok());
+    }
+
+    #[test]
+    fn rounding_applies_to_ada_and_usd_figures() {
+        let usd = |rounding| UsdFormat { rounding, decimals: 2, currency: USD };
+        for (rounding, ada, cents) in [
+            (RoundingMode::HalfUp, ["0.000001", "0.000002"], ["$0.13", "$2.68"]),
+            (RoundingMode::HalfEven, ["0.000000", "0.000002"], ["$0.12", "$2.68"]),
+            (RoundingMode::Truncate, ["0.000000", "0.000001"], ["$0.12", "$2.67"]),
+        ] {
+            assert_eq!([format_ada(0.0000005, rounding), format_ada(0.0000015, rounding)], ada, "{:?}", rounding);
+            assert_eq!([format_money(0.125, usd(rounding)), format_money(2.675, usd(rounding))], cents, "{:?}", rounding);
+        }
+        let csv = CsvDialect { ada_precision: 2, value_precision: Some(1), rounding: RoundingMode::Truncate, ..XLSX_DIALECT };
+        assert_eq!((csv.ada(1.239), csv.value(0.29)), (String::from("1.23"), String::from("0.2")));
+    }
+
+    #[test]
+    fn streamed_csv_matches_the_buffered_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = pool(
+            r#"{"ada": 1000, "initial_price": 0.5, "daily_price_growth_pct": 0.1, "annual_yield": 0.05, "epoch_in_days": 5,
+                "years_holding": 3, "contribution": {"amount_usd": 25, "cadence_days": 30}}"#,
+        );
+        let csv_path = dir.path().join("data.csv");
+        let args = options(&["-g", "--csv-out", csv_path.to_str().unwrap()]);
+        run_simulation(&pool, &args, false);
+        // The whole file built in memory and written at the end the way it was before the rows were streamed
+        let mut buffer = args.csv_dialect.header(&data_header(&args, &pool)) + "\n";
+        let mut cumulative_rewards = 0.0;
+        calculate_staked_pool_with(&pool, &args.simulation_options(), |record| {
+            cumulative_rewards += record.credited;
+            buffer += &args.csv_dialect.row(&data_row(&pool, &args, record, args.csv_dialect, cumulative_rewards, 0.0));
+            buffer += "\n";
+        });
+        assert_eq!(std::fs::read(&csv_path).unwrap(), buffer.into_bytes());
+    }
+
+    #[test]
+    fn stdin_input_is_one_json_pool_on_its_own() {
+        let error = run_pools(&options(&["-i", "-", "-i", "pool.json"])).unwrap_err();
+        assert!(matches!(&error, CliError::Parse(text) if text.contains("--input -")), "{:?}", error);
+        assert!(matches!(run_pools(&options(&["-i", "pool.json", "-i", "-"])), Err(CliError::Parse(_))));
+        // Stdin has no extension so the text is taken as JSON as is
+        assert_eq!(pool_file_to_json(STDIN_PATH, String::from("ada = 1")), Ok(String::from("ada = 1")));
+    }
+
+    #[test]
+    fn holdings_show_six_ada_decimals_and_the_usd_precision() {
+        assert_eq!(format_holding(1000.0, 0.5, options(&[]).usd_format), "1000.000000 ADA @ $0.50 = $500.00");
+        let usd_format = options(&["--precision", "4"]).usd_format;
+        assert_eq!(format_holding(1000.0, 0.5, usd_format), "1000.000000 ADA @ $0.5000 = $500.0000");
+        assert_eq!(format_holding(1000.684462, 0.5, usd_format), "1000.684462 ADA @ $0.5000 = $500.3422");
+    }
+
+    #[test]
+    fn verbose_holding_is_written_without_building_strings() {
+        let mut line: Vec<u8> = Vec::with_capacity(128);
+        for args in [&[][..], &["--precision", "4"], &["--rounding", "truncate"], &["--currency", "jpy", "--fx-rate", "150"]] {
+            let format = options(args).usd_format;
+            for (ada, price) in [(1000.0, 0.5), (1000.684462, 0.50250501), (13026.964857, 2412.7995024)] {
+                line.clear();
+                write!(line, "{}", Holding { ada, price, format }).unwrap();
+                let expected = format!(
+                    "{} ADA @ {} = {}",
+                    format_ada(ada, format.rounding),
+                    format_money(price, format),
+                    format_money(ada * price, format)
+                );
+                assert_eq!(String::from_utf8_lossy(&line), expected, "{:?}", args);
+            }
+        }
+        assert_eq!(line.capacity(), 128); // The one buffer is reused for every line
+    }
+
+    #[test]
+    fn csv_rewards_reconcile_with_the_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = pool(
+            r#"{"ada": 1000, "initial_price": 0.5, "daily_price_growth_pct": 0.1, "annual_yield": 0.05, "epoch_in_days": 5,
+                "years_holding": 3, "contribution": {"amount_usd": 25, "cadence_days": 30},
+                "withdrawal_usd_per_interval": 10, "withdrawal_interval_days": 90}"#,
+        );
+        for granularity in ["day", "epoch", "month"] {
+            let csv_path = dir.path().join(format!("{}.csv", granularity));
+            let args = options(&["-g", "--csv-out", csv_path.to_str().unwrap(), "--csv-granularity", granularity]);
+            let result = run_simulation(&pool, &args, false);
+            let csv = std::fs::read_to_string(&csv_path).unwrap();
+            let rows: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+            assert_eq!(rows[0].len(), data_header(&args, &pool).split(',').count());
+            assert!(rows.iter().all(|row| row.len() == rows[0].len()), "{}", granularity);
+            let column = |name: &str| rows[0].iter().position(|header| *header == name).unwrap();
+            let cumulative = column("Cumulative Rewards");
+            // Coarser rows sum the rewards of the days left out
+            let paid = if granularity == "day" { column("Reward Paid") } else { column("Rewards Since Last Row") };
+            let last_cumulative = rows.last().unwrap()[cumulative];
+            assert_eq!(last_cumulative, format_ada(result.credited_rewards, args.usd_format.rounding), "{}", granularity);
+            let paid_sum: f64 = rows[1..].iter().map(|row| row[paid].parse::<f64>().unwrap()).sum();
+            assert!((paid_sum - result.credited_rewards).abs() < 1e-6, "{} {} vs {}", granularity, paid_sum, result.credited_rewards);
+        }
+        let basic = options(&["--csv-columns", "basic"]);
+        assert_eq!(data_header(&basic, &pool), "Day,ADA,Price,Total");
+    }
+
+    #[test]
+    fn real_total_in_the_csv_matches_the_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        // Half a year past a leap year is where years_holding and the actual day count part ways
+        let short = pool(
+            r#"{"ada": 1000, "initial_price": 0.5, "daily_price_growth_pct": 0.1, "annual_yield": 0.05, "epoch_in_days": 5,
+                "years_holding": 1.5, "annual_inflation": 0.03}"#,
+        );
+        let days = short.simulated_days();
+        // A row is the state at the start of its day so the row after the last day is where the short run ended
+        let long = StakedCardanoPool { years_holding: short.years_for_days(days + 1), ..short.clone() };
+        let csv_path = dir.path().join("real.csv");
+        let args = options(&["-g", "--csv-out", csv_path.to_str().unwrap()]);
+        run_simulation(&long, &args, false);
+        let csv = std::fs::read_to_string(&csv_path).unwrap();
+        let rows: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+        let real = rows[0].iter().position(|header| *header == "Real Total").unwrap();
+        let last = rows.last().unwrap();
+        assert_eq!(last[0], (days + 1).to_string());
+        let result = calculate_staked_pool(&short, &args.simulation_options());
+        let csv_real: f64 = last[real].parse().unwrap();
+        assert!((csv_real - result.real_total(&short)).abs() < 1e-9, "{} vs {}", csv_real, result.real_total(&short));
+    }
+
+    #[test]
+    fn pool_keys_are_checked_with_suggestions() {
+        let warnings = unknown_pool_keys(
+            r#"{"ada": 1000, "annual_yeild": 0.05, "colour": "red", "wallets": [{"name": "Cold", "ada": 5, "epoch_ofset_days": 2}]}"#,
+        );
+        assert_eq!(
+            warnings,
+            [
+                "Warning: unknown field annual_yeild is ignored (did you mean annual_yield?)",
+                "Warning: unknown field colour is ignored",
+                "Warning: wallet 1 unknown field epoch_ofset_days is ignored (did you mean epoch_offset_days?)",
+            ]
+        );
+        assert!(unknown_pool_keys(EXAMPLE_POOL_JSON).is_empty());
+        // A missing required field is named with the line to add from the example pool
+        let error = parse_pool(r#"{"initial_price": 1.0, "annual_yield": 0.05, "years_holding": 1}"#).unwrap_err();
+        assert!(error.contains("missing field `ada`") && error.ends_with("Add it to the pool for example: \"ada\": 10000"), "{}", error);
+        // Optional knobs can be left out
+        let pool = parse_pool(r#"{"ada": 1000, "initial_price": 1.0, "annual_yield": 0.05, "years_holding": 1}"#).unwrap();
+        assert_eq!((pool.epoch_in_days, pool.fetch_price_via_api, pool.compound), (5, false, true));
+    }
+
+    #[test]
+    fn pretty_numbers_group_round_and_sign() {
+        for (number, grouped) in [
+            ("0.50", "0.50"),
+            ("999", "999"),
+            ("1000", "1,000"),
+            ("-1234567.50", "-1,234,567.50"),
+            ("123456789.012345", "123,456,789.012345"),
+            ("inf", "inf"),
+            ("NaN", "NaN"),
+        ] {
+            assert_eq!(group_thousands(number), grouped);
+        }
+        assert_eq!(pretty_ada(1234567.8901235, RoundingMode::HalfEven), "1,234,567.890124");
+        assert_eq!(pretty_ada(1234567.8901235, RoundingMode::Truncate), "1,234,567.890123");
+        let usd = options(&[]).usd_format;
+        assert_eq!(pretty_money(1234567.125, usd), "$1,234,567.12");
+        assert_eq!(pretty_money(-1234.5, usd), "-$1,234.50");
+        assert_eq!(pretty_money(-0.004, usd), "-$0.00");
+    }
+
+    #[test]
+    fn color_only_on_request_or_a_terminal() {
+        assert!(!use_color(false, true));
+        assert!(use_color(true, false));
+        let plain = options(&["--no-color"]);
+        assert_eq!(paint_gain(String::from("+5.00%"), true, &plain), "+5.00%");
+        let colored = options(&["--color"]);
+        assert_ne!(paint_gain(String::from("-5.00%"), false, &colored), "-5.00%");
+    }
+
+    #[test]
+    fn bad_currency_and_denomination_options_are_parse_errors() {
+        let build = |args: &[&str]| {
+            let argv = std::iter::once("ada_calc").chain(args.iter().copied());
+            command_options(&build_command().try_get_matches_from(argv).unwrap())
+        };
+        for (args, message) in [
+            (&["--currency", "eur", "--fx-rate", "0"][..], "--fx-rate 0 needs to be a positive number of EUR per USD"),
+            (&["--currency", "gbp", "--fx-rate=-1.2"], "--fx-rate -1.2 needs to be a positive number of GBP per USD"),
+            (&["--denominate-in", "btc"], "--denominate-in btc needs a positive --reference-price in USD"),
+            (&["--denominate-in", "gold", "--reference-price", "0"], "--denominate-in gold needs a positive --reference-price in USD"),
+        ] {
+            match build(args) {
+                Err(CliError::Parse(text)) => assert_eq!(text, message),
+                other => panic!("{:?} gave {:?}", args, other.map(|_| ())),
+            }
+        }
+        let args = build(&["--currency", "eur", "--fx-rate", "0.9", "--denominate-in", "btc", "--reference-price", "60000"]).unwrap();
+        assert_eq!((args.usd_format.currency.code, args.usd_format.currency.per_usd), ("EUR", 0.9));
+        assert_eq!(args.denominate_in, Some((String::from("BTC"), 60000.0)));
     }
 }
@@ -0,0 +1,99 @@
+/*
+
+ADA Staking CLI Tool (⚠️ Not Offical or Investment Advice ⚠️)
+Copyright (C) 2022  zulrah93 <1s16slrse@mozmail.com>
+
+This program is free software; you can redistribute it and/or
+modify it under the terms of the GNU General Public License
+as published by the Free Software Foundation; either version 2
+of the License, or (at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program; if not, write to the Free Software
+Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301, USA.
+
+
+*/
+
+// Live ADA spot price for pools with fetch_price_via_api set
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const COINGECKO_SIMPLE_PRICE_URL: &str =
+    "https://api.coingecko.com/api/v3/simple/price?ids=cardano&vs_currencies=usd";
+
+// Long enough for a slow connection short enough that an offline run is not left hanging
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Name of the source shown next to a fetched price
+pub const SOURCE_NAME: &str = "CoinGecko";
+
+// ADA price in USD and in the fiat code (EUR, GBP ...) from one request the error is a readable reason so the caller can fall back to initial_price
+pub fn fetch_ada_price_in(code: &str) -> Result<(f64, f64), String> {
+    let currency = code.to_ascii_lowercase();
+    let url = if currency == "usd" {
+        String::from(COINGECKO_SIMPLE_PRICE_URL)
+    } else {
+        format!("{},{}", COINGECKO_SIMPLE_PRICE_URL, currency)
+    };
+    let body = ureq::get(&url)
+        .timeout(REQUEST_TIMEOUT)
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_string()
+        .map_err(|e| e.to_string())?;
+    // Response looks like {"cardano":{"usd":0.45,"eur":0.41}}
+    let json: serde_json::Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+    match (json["cardano"]["usd"].as_f64(), json["cardano"][currency.as_str()].as_f64()) {
+        (Some(usd), Some(fiat)) if usd > 0.0 && fiat > 0.0 => Ok((usd, fiat)),
+        _ => Err(format!("unexpected response: {}", body)),
+    }
+}
+
+// Units of the fiat code one USD buys taken from the ADA price in both
+pub fn fetch_usd_exchange_rate(code: &str) -> Result<f64, String> {
+    fetch_ada_price_in(code).map(|(usd, fiat)| fiat / usd)
+}
+
+// Last successful fetch kept on disk so repeated or offline runs can reuse it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedPrice {
+    pub currency: String, // Lowercase fiat code the price was fetched in next to USD
+    pub usd: f64,
+    pub fiat: f64,
+    pub fetched_at: i64, // Unix seconds
+}
+
+// ~/.cache/ada_calc/price_cache.json honoring XDG_CACHE_HOME and LOCALAPPDATA on Windows
+pub fn cache_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("ada_calc").join("price_cache.json"))
+}
+
+// None when there is no cache for this currency a corrupted file is treated as missing and replaced on the next fetch
+pub fn read_cache(code: &str) -> Option<CachedPrice> {
+    let text = std::fs::read_to_string(cache_path()?).ok()?;
+    let cached: CachedPrice = serde_json::from_str(&text).ok()?;
+    (cached.currency == code.to_ascii_lowercase() && cached.usd > 0.0 && cached.fiat > 0.0).then_some(cached)
+}
+
+// Failing to write the cache never fails the run
+pub fn write_cache(cached: &CachedPrice) {
+    if let Some(path) = cache_path() {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Ok(text) = serde_json::to_string_pretty(cached) {
+            let _ = std::fs::write(path, text);
+        }
+    }
+}
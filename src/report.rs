@@ -0,0 +1,211 @@
+/*
+
+ADA Staking CLI Tool (⚠️ Not Offical or Investment Advice ⚠️)
+Copyright (C) 2022  zulrah93 <1s16slrse@mozmail.com>
+
+This program is free software; you can redistribute it and/or
+modify it under the terms of the GNU General Public License
+as published by the Free Software Foundation; either version 2
+of the License, or (at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program; if not, write to the Free Software
+Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301, USA.
+
+
+*/
+
+// Run reports for pasting into notes only builds the text the caller decides where it is written
+use ada_calc::{StakedCardanoPool, StakedCardanoPoolResult};
+
+// ADA always with 6 decimals (whole lovelace) and USD always with 2 whatever --precision says
+fn ada(value: f64) -> String {
+    format!("{:.6}", value)
+}
+
+fn usd(value: f64) -> String {
+    if value < 0.0 {
+        format!("-${:.2}", -value)
+    } else {
+        format!("${:.2}", value)
+    }
+}
+
+// Label and value rows for the inputs the run used shared by every report format
+pub fn input_rows(pool: &StakedCardanoPool) -> Vec<(&'static str, String)> {
+    vec![
+        ("ADA", ada(pool.ada)),
+        ("Initial Price", usd(pool.initial_price)),
+        ("Annual Yield", format!("{:.2}%", pool.annual_yield * 100.0)),
+        (
+            "Price Model",
+            if pool.daily_prices.is_empty() {
+                format!("{} daily multiplier", pool.describe_price_yield())
+            } else {
+                format!("Backtest of {}", pool.describe_price_yield())
+            },
+        ),
+        ("Epoch Length", format!("{} days", pool.epoch_in_days)),
+        ("Years Holding", pool.years_holding.to_string()),
+        ("Start Date", pool.start().to_string()),
+    ]
+}
+
+// Label and value rows for the final summary
+pub fn result_rows(pool: &StakedCardanoPool, result: &StakedCardanoPoolResult) -> Vec<(&'static str, String)> {
+    let mut rows = vec![
+        ("Final ADA", ada(result.final_ada_amount)),
+        ("Final Price", usd(result.final_ada_price)),
+        ("Final Total", usd(result.total())),
+        ("Money Put In", usd(result.initial_investment + result.contributed_usd)),
+        ("Gainz (ROI)", format!("{:+.2}% ({:.2}x)", result.net_return_as_percentage(pool), result.value_multiple(pool))),
+        ("CAGR", format!("{:+.2}%", result.cagr(pool) * 100.0)),
+        ("ADA CAGR", format!("{:.2}%", result.ada_cagr(pool) * 100.0)),
+        ("Holding Instead Ends At", usd(result.hold_total(pool))),
+    ];
+    if pool.annual_inflation != 0.0 {
+        rows.push(("Real Total (Today's Dollars)", usd(result.real_total(pool))));
+        rows.push(("Real CAGR", format!("{:+.2}%", result.real_cagr(pool) * 100.0)));
+    }
+    rows
+}
+
+// Header and rows of the yearly breakdown
+pub fn yearly_rows(result: &StakedCardanoPoolResult) -> (Vec<&'static str>, Vec<Vec<String>>) {
+    let header = vec!["Year", "End Date", "ADA", "Price", "Total", "Rewards ADA", "Rewards Value", "Gainz"];
+    let rows = result
+        .yearly
+        .iter()
+        .map(|row| {
+            vec![
+                if row.partial { format!("{} (Partial)", row.year) } else { row.year.to_string() },
+                row.end_date.to_string(),
+                ada(row.ada),
+                usd(row.price),
+                usd(row.total),
+                ada(row.rewards_ada),
+                usd(row.rewards_usd),
+                format!("{:+.2}%", row.gain_pct),
+            ]
+        })
+        .collect();
+    (header, rows)
+}
+
+// One pool of the report a single run has one unnamed section and every scenario gets its own
+pub struct Section<'a> {
+    pub name: &'a str,
+    pub pool: &'a StakedCardanoPool,
+    pub result: &'a StakedCardanoPoolResult,
+    pub chart: Option<String>, // Inline SVG of the growth graph embedded by the HTML report
+}
+
+fn pairs(rows: Vec<(&'static str, String)>) -> Vec<Vec<String>> {
+    rows.into_iter().map(|(label, value)| vec![String::from(label), value]).collect()
+}
+
+fn markdown_table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut table = format!("| {} |\n|{}\n", header.join(" | "), " --- |".repeat(header.len()));
+    for row in rows {
+        table.push_str(&format!("| {} |\n", row.join(" | ")));
+    }
+    table
+}
+
+// The whole report graph is the image path relative to the report when a graph was written next to it
+pub fn markdown(sections: &[Section], generated: &str, graph: Option<&str>) -> String {
+    let mut report = format!(
+        "# ADA Staking Report\n\nGenerated {} by ada_calc. Projections are not investment advice.\n",
+        generated
+    );
+    for section in sections {
+        // Named sections push the headings one level down under the scenario name
+        let heading = if section.name.is_empty() {
+            "##"
+        } else {
+            report.push_str(&format!("\n## {}\n", section.name));
+            "###"
+        };
+        report.push_str(&format!("\n{} Inputs\n\n", heading));
+        report.push_str(&markdown_table(&["Parameter", "Value"], &pairs(input_rows(section.pool))));
+        report.push_str(&format!("\n{} Results\n\n", heading));
+        report.push_str(&markdown_table(&["", "Value"], &pairs(result_rows(section.pool, section.result))));
+        let (header, rows) = yearly_rows(section.result);
+        if !rows.is_empty() {
+            report.push_str(&format!("\n{} Yearly Breakdown\n\n", heading));
+            report.push_str(&markdown_table(&header, &rows));
+        }
+    }
+    if let Some(graph) = graph {
+        report.push_str(&format!("\n## Graph\n\n![ADA growth graph]({})\n", graph));
+    }
+    report
+}
+
+// Text going into the HTML report scenario names and the like can hold any character
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn html_table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let cells = |row: &[String], tag: &str| -> String {
+        row.iter().map(|cell| format!("<{tag}>{}</{tag}>", escape_html(cell), tag = tag)).collect()
+    };
+    let header: Vec<String> = header.iter().map(|cell| String::from(*cell)).collect();
+    let mut table = format!("<table>\n<tr>{}</tr>\n", cells(&header, "th"));
+    for row in rows {
+        table.push_str(&format!("<tr>{}</tr>\n", cells(row, "td")));
+    }
+    table.push_str("</table>\n");
+    table
+}
+
+// A few inline rules so the file looks tidy on its own
+const HTML_STYLE: &str = "body{font-family:sans-serif;max-width:960px;margin:2em auto;padding:0 1em;color:#222}\
+table{border-collapse:collapse;margin:0.5em 0 1.5em}\
+th,td{border:1px solid #ccc;padding:0.3em 0.7em;text-align:right}\
+th:first-child,td:first-child{text-align:left}\
+th{background:#f3f3f3}\
+.chart svg{width:100%;height:auto}\
+.note{color:#666}";
+
+// One self contained page every chart is inline SVG so nothing outside the file is referenced
+pub fn html(sections: &[Section], generated: &str) -> String {
+    let mut report = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>ADA Staking Report</title>\n<style>{}</style>\n</head>\n<body>\n\
+<h1>ADA Staking Report</h1>\n<p class=\"note\">Generated {} by ada_calc. Projections are not investment advice.</p>\n",
+        HTML_STYLE,
+        escape_html(generated)
+    );
+    for section in sections {
+        let heading = if section.name.is_empty() {
+            "h2"
+        } else {
+            report.push_str(&format!("<h2>{}</h2>\n", escape_html(section.name)));
+            "h3"
+        };
+        report.push_str(&format!("<{h}>Inputs</{h}>\n", h = heading));
+        report.push_str(&html_table(&["Parameter", "Value"], &pairs(input_rows(section.pool))));
+        report.push_str(&format!("<{h}>Results</{h}>\n", h = heading));
+        report.push_str(&html_table(&["", "Value"], &pairs(result_rows(section.pool, section.result))));
+        let (header, rows) = yearly_rows(section.result);
+        if !rows.is_empty() {
+            report.push_str(&format!("<{h}>Yearly Breakdown</{h}>\n", h = heading));
+            report.push_str(&html_table(&header, &rows));
+        }
+        if let Some(chart) = &section.chart {
+            report.push_str(&format!("<{h}>Graph</{h}>\n<div class=\"chart\">\n{}\n</div>\n", chart, h = heading));
+        }
+    }
+    report.push_str("</body>\n</html>\n");
+    report
+}
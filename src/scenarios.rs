@@ -0,0 +1,404 @@
+/*
+
+ADA Staking CLI Tool (⚠️ Not Offical or Investment Advice ⚠️)
+Copyright (C) 2022  zulrah93 <1s16slrse@mozmail.com>
+
+This program is free software; you can redistribute it and/or
+modify it under the terms of the GNU General Public License
+as published by the Free Software Foundation; either version 2
+of the License, or (at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program; if not, write to the Free Software
+Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301, USA.
+
+
+*/
+
+// Several pools from one file scenarios run side by side and are compared and wallets run on one price path and are added up
+use crate::config::{explain_missing_field, parse_pool};
+use crate::csv::{write_portfolio_csv, write_scenarios_csv};
+use crate::graph::{default_graph_path, has_graph_history, render_graph_svg, render_portfolio_svg, render_scenarios_svg, write_graph, NO_GRAPH_HISTORY_WARNING};
+use crate::{
+    clamp_annual_yield, format_ada, format_money, format_usd, print_diagnostic, print_result_line, print_table, report,
+    run_simulation, write_report, write_result, CliError, CommandOptions,
+};
+use ada_calc::analysis::{CompareMetric, Portfolio};
+use ada_calc::{StakedCardanoPool, StakedCardanoPoolResult};
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+// A pool JSON may also be an array of these or an object with a scenarios array to compare scenarios in one run
+#[derive(Debug, Deserialize)]
+pub struct NamedPool {
+    #[serde(default)]
+    pub name: String,
+    #[serde(flatten)]
+    pub pool: StakedCardanoPool,
+}
+
+// Always at least one entry a plain pool object is one unnamed scenario
+// With {"scenarios": [...]} the other top level fields are shared and each scenario only needs the fields it changes
+pub fn parse_scenarios(buffer: &str) -> Result<Vec<NamedPool>, String> {
+    if !buffer.trim_start().starts_with('[') {
+        if let Ok(serde_json::Value::Object(mut base)) = serde_json::from_str::<serde_json::Value>(buffer) {
+            if let Some(scenarios) = base.remove("scenarios") {
+                return parse_shared_scenarios(base, scenarios);
+            }
+        }
+        return parse_pool(buffer).map(|pool| vec![NamedPool { name: String::new(), pool }]);
+    }
+    let scenarios: Vec<NamedPool> =
+        serde_json::from_str(buffer).map_err(|e| explain_missing_field(format!("Invalid scenario JSON: {}", e)))?;
+    if scenarios.is_empty() {
+        return Err(String::from("Invalid scenario JSON: the array has no scenarios"));
+    }
+    Ok(scenarios)
+}
+
+fn parse_shared_scenarios(
+    base: serde_json::Map<String, serde_json::Value>,
+    scenarios: serde_json::Value,
+) -> Result<Vec<NamedPool>, String> {
+    let entries = match scenarios {
+        serde_json::Value::Array(entries) if !entries.is_empty() => entries,
+        serde_json::Value::Array(_) => return Err(String::from("Invalid scenario JSON: the scenarios array is empty")),
+        _ => return Err(String::from("Invalid scenario JSON: scenarios must be an array")),
+    };
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let mut merged = base.clone();
+            match entry {
+                serde_json::Value::Object(fields) => merged.extend(fields),
+                _ => return Err(format!("Invalid scenario JSON: scenario {} is not an object", index + 1)),
+            }
+            serde_json::from_value(serde_json::Value::Object(merged))
+                .map_err(|e| explain_missing_field(format!("Invalid scenario JSON: scenario {}: {}", index + 1, e)))
+        })
+        .collect()
+}
+
+// Runs every scenario quietly then prints one comparison table and with --generate_graph overlays their totals
+pub fn run_scenarios(scenarios: Vec<NamedPool>, args: &CommandOptions, output_to_stdout: bool) -> Result<(), CliError> {
+    let quiet_args = CommandOptions {
+        verbose: false,
+        generate_csv: false,
+        generate_xlsx: false,
+        include_history: args.include_history || args.generate_csv, // The shared CSV is written from the history afterwards
+        ..args.clone()
+    };
+    if args.generate_xlsx {
+        print_diagnostic("Note: --generate-xlsx only writes single pool runs the scenarios are not put in a workbook", output_to_stdout);
+    }
+    let mut results: Vec<(String, StakedCardanoPoolResult)> = Vec::new();
+    let mut pools: Vec<StakedCardanoPool> = Vec::new(); // For the report
+    let mut rows: Vec<(String, String, String, String, f64, f64)> = Vec::new(); // The last value is the --compare-metric one
+    let mut calendars: Vec<(u64, NaiveDate)> = Vec::new();
+    for (index, scenario) in scenarios.into_iter().enumerate() {
+        let name = if scenario.name.is_empty() {
+            format!("Scenario {}", index + 1)
+        } else {
+            scenario.name
+        };
+        let mut pool = scenario.pool;
+        args.pool_overrides.apply(&mut pool);
+        pool.validate().map_err(|e| CliError::invalid_pool(&name, &e))?;
+        if let Some(note) = pool.deprecation_note() {
+            print_diagnostic(&format!("Note: {}: {}", name, note), output_to_stdout);
+        }
+        clamp_annual_yield(&mut pool, args, &mut Vec::new());
+        let result = run_simulation(&pool, &quiet_args, output_to_stdout);
+        rows.push((
+            name.clone(),
+            format_ada(result.final_ada_amount, args.usd_format.rounding),
+            format_money(result.final_ada_price, args.usd_format),
+            format_usd(result.total(), args.usd_format),
+            result.net_return_as_percentage(&pool),
+            args.compare_metric.map(|metric| metric.value(&pool, &result)).unwrap_or_default(),
+        ));
+        calendars.push((pool.epoch_in_days, pool.start()));
+        results.push((name, result));
+        pools.push(pool);
+    }
+    let name_width = rows.iter().map(|(name, ..)| name.len()).max().unwrap_or(0);
+    let ada_width = rows.iter().map(|(_, ada, ..)| ada.len()).max().unwrap_or(0);
+    let price_width = rows.iter().map(|(_, _, price, ..)| price.chars().count()).max().unwrap_or(0);
+    let total_width = rows.iter().map(|(_, _, _, total, ..)| total.len()).max().unwrap_or(0);
+    if args.compare_metric.is_some() {
+        rows.sort_by(|a, b| b.5.total_cmp(&a.5));
+    }
+    if output_to_stdout {
+        println!("<div class='output'>");
+    }
+    let title = match args.compare_metric {
+        Some(metric) => format!("Scenario Comparison (Ranked By {}):", metric.name()),
+        None => String::from("Scenario Comparison:"),
+    };
+    print_result_line(&title, output_to_stdout);
+    for (name, ada, price, total, gainz, metric) in rows.iter() {
+        // Total and yield are already on the line CAGR is added so the order can be seen
+        let cagr = match args.compare_metric {
+            Some(CompareMetric::Cagr) => format!("  CAGR: {:+.2}%", metric),
+            _ => String::new(),
+        };
+        print_result_line(
+            &format!(
+                "  {:<name_width$}  {:>ada_width$} ADA @ {:<price_width$}  {}{:>total_width$}  Gainz: {:+.2}%{}",
+                name,
+                ada,
+                price,
+                args.usd_format.currency.symbol,
+                total,
+                gainz,
+                cagr,
+                name_width = name_width,
+                ada_width = ada_width,
+                price_width = price_width,
+                total_width = total_width
+            ),
+            output_to_stdout,
+        );
+    }
+    if output_to_stdout {
+        println!("</div>");
+    }
+    if args.generate_csv {
+        write_result(write_scenarios_csv(&results, &calendars, args), "The scenario CSV", args)?;
+    }
+    let graph_path = args.svg_out.clone().unwrap_or_else(|| default_graph_path(args));
+    let mut graph_written = false;
+    if args.generate_graph && !results.iter().any(|(_, result)| has_graph_history(result)) {
+        print_diagnostic(NO_GRAPH_HISTORY_WARNING, output_to_stdout);
+    } else if args.generate_graph {
+        graph_written = write_graph(Some(graph_path.clone()), &render_scenarios_svg(&results, args), args, output_to_stdout);
+        write_result(graph_written, "The scenario graph", args)?;
+    }
+    if let (Some(format), false) = (args.report, output_to_stdout) {
+        let sections: Vec<report::Section> = results
+            .iter()
+            .zip(pools.iter())
+            .map(|((name, result), pool)| report::Section {
+                name,
+                pool,
+                result,
+                chart: Some(result).filter(|result| has_graph_history(result)).map(|result| render_graph_svg(Some(pool), result, args)),
+            })
+            .collect();
+        let graph = Some(graph_path.as_str()).filter(|_| graph_written);
+        write_result(write_report(format, &sections, graph, args), "The report", args)?;
+    }
+    Ok(())
+}
+
+// Fields every wallet shares so the wallets always see the same price on the same day
+const WALLET_SHARED_FIELDS: [&str; 16] = [
+    "initial_price",
+    "fetch_price_via_api",
+    "price_api_currency",
+    "price_yield",
+    "daily_price_growth_pct",
+    "annual_price_growth_pct",
+    "price_yields",
+    "daily_prices",
+    "price_events",
+    "max_price",
+    "price_volatility",
+    "start_date",
+    "years_holding",
+    "day_count",
+    "annual_inflation",
+    "annual_inflation_rate",
+];
+
+// With {"wallets": [...]} the other top level fields are shared and each wallet lists its own ada, annual_yield and so on
+// epoch_offset_days moves a wallet's pay days that many days after the shared ones
+// None for a pool without wallets so it runs as before
+pub fn parse_wallets(buffer: &str) -> Result<Option<Vec<NamedPool>>, String> {
+    if buffer.trim_start().starts_with('[') {
+        return Ok(None);
+    }
+    let mut base = match serde_json::from_str::<serde_json::Value>(buffer) {
+        Ok(serde_json::Value::Object(base)) if base.contains_key("wallets") => base,
+        _ => return Ok(None),
+    };
+    if base.contains_key("scenarios") {
+        return Err(String::from("Invalid wallet JSON: wallets and scenarios cannot be used in the same file"));
+    }
+    let entries = match base.remove("wallets") {
+        Some(serde_json::Value::Array(entries)) if !entries.is_empty() => entries,
+        Some(serde_json::Value::Array(_)) => return Err(String::from("Invalid wallet JSON: the wallets array is empty")),
+        _ => return Err(String::from("Invalid wallet JSON: wallets must be an array")),
+    };
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let mut fields = match entry {
+                serde_json::Value::Object(fields) => fields,
+                _ => return Err(format!("Invalid wallet JSON: wallet {} is not an object", index + 1)),
+            };
+            if let Some(field) = WALLET_SHARED_FIELDS.iter().find(|field| fields.contains_key(**field)) {
+                return Err(format!(
+                    "Invalid wallet JSON: wallet {} sets {} the price path and horizon are shared so it goes at the top level",
+                    index + 1,
+                    field
+                ));
+            }
+            let offset = match fields.remove("epoch_offset_days") {
+                None => 0,
+                Some(value) => value
+                    .as_u64()
+                    .ok_or_else(|| format!("Invalid wallet JSON: wallet {} epoch_offset_days must be a whole number of days", index + 1))?,
+            };
+            let mut merged = base.clone();
+            merged.extend(fields);
+            let mut wallet: NamedPool = serde_json::from_value(serde_json::Value::Object(merged))
+                .map_err(|e| explain_missing_field(format!("Invalid wallet JSON: wallet {}: {}", index + 1, e)))?;
+            if wallet.name.is_empty() {
+                wallet.name = format!("Wallet {}", index + 1);
+            }
+            if offset >= wallet.pool.epoch_in_days.max(1) {
+                return Err(format!(
+                    "Invalid wallet JSON: wallet {} epoch_offset_days {} must be less than epoch_in_days {}",
+                    index + 1,
+                    offset,
+                    wallet.pool.epoch_in_days
+                ));
+            }
+            if offset > 0 {
+                // An anchor offset days later puts the pay days on day offset, offset + epoch_in_days and so on
+                let anchor = wallet.pool.epoch_anchor_date.unwrap_or_else(|| wallet.pool.start());
+                wallet.pool.epoch_anchor_date = Some(anchor + chrono::Duration::days(offset as i64));
+            }
+            Ok(wallet)
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map(Some)
+}
+
+// Runs every wallet quietly on the shared price path then prints each wallet's final and the combined portfolio
+pub fn run_portfolio(wallets: Vec<(String, StakedCardanoPool)>, args: &CommandOptions, output_to_stdout: bool) -> Result<(), CliError> {
+    if args.monte_carlo.is_some() {
+        return Err(CliError::Validation(String::from(
+            "--monte-carlo draws a separate price path per run so it cannot be used with wallets",
+        )));
+    }
+    let quiet_args = CommandOptions {
+        verbose: false,
+        generate_csv: false,
+        generate_xlsx: false,
+        include_history: args.include_history || args.generate_csv, // The portfolio CSV is written from the histories afterwards
+        ..args.clone()
+    };
+    if args.generate_xlsx {
+        print_diagnostic("Note: --generate-xlsx only writes single pool runs the wallets are not put in a workbook", output_to_stdout);
+    }
+    let mut results: Vec<(String, StakedCardanoPoolResult)> = Vec::new();
+    for (name, pool) in wallets.iter() {
+        let mut pool = pool.clone();
+        clamp_annual_yield(&mut pool, args, &mut Vec::new());
+        results.push((name.clone(), run_simulation(&pool, &quiet_args, output_to_stdout)));
+    }
+    let Portfolio {
+        put_in,
+        total,
+        final_ada,
+        multiple,
+        cagr,
+    } = Portfolio::new(&wallets, &results);
+
+    let show_cagr = args.compare_metric == Some(CompareMetric::Cagr);
+    let mut header = vec![
+        String::from("Wallet"),
+        String::from("ADA"),
+        String::from("Total"),
+        String::from("Share"),
+        String::from("Gainz"),
+    ];
+    if show_cagr {
+        header.push(String::from("CAGR"));
+    }
+    let share = |value: f64| if total > 0.0 { format!("{:.1}%", value / total * 100.0) } else { String::from("-") };
+    let mut wallet_rows: Vec<(f64, Vec<String>)> = Vec::new(); // --compare-metric value and the cells
+    for ((name, pool), (_, result)) in wallets.iter().zip(results.iter()) {
+        let mut row = vec![
+            name.clone(),
+            format_ada(result.final_ada_amount, args.usd_format.rounding),
+            format_money(result.total(), args.usd_format),
+            share(result.total()),
+            format!("{:+.2}%", result.net_return_as_percentage(pool)),
+        ];
+        if show_cagr {
+            row.push(format!("{:+.2}%", result.cagr(pool) * 100.0));
+        }
+        wallet_rows.push((args.compare_metric.map(|metric| metric.value(pool, result)).unwrap_or_default(), row));
+    }
+    if args.compare_metric.is_some() {
+        wallet_rows.sort_by(|a, b| b.0.total_cmp(&a.0));
+    }
+    let mut portfolio_row = vec![
+        String::from("Portfolio"),
+        format_ada(final_ada, args.usd_format.rounding),
+        format_money(total, args.usd_format),
+        share(total),
+        format!("{:+.2}%", (multiple - 1.0) * 100.0),
+    ];
+    if show_cagr {
+        portfolio_row.push(format!("{:+.2}%", cagr * 100.0));
+    }
+    let rows: Vec<Vec<String>> = std::iter::once(header)
+        .chain(wallet_rows.into_iter().map(|(_, row)| row))
+        .chain(std::iter::once(portfolio_row))
+        .collect();
+    let title = match args.compare_metric {
+        Some(metric) => format!("Portfolio ({} Wallets, Ranked By {}):", wallets.len(), metric.name()),
+        None => format!("Portfolio ({} Wallets):", wallets.len()),
+    };
+    print_table(&title, &rows, output_to_stdout);
+    print_result_line(
+        &format!(
+            "Portfolio Total: {} (Final Price {}) Money Put In: {} Gainz: {:+.2}% ({:.2}x) CAGR: {:+.2}%",
+            format_money(total, args.usd_format),
+            format_money(results[0].1.final_ada_price, args.usd_format),
+            format_money(put_in, args.usd_format),
+            (multiple - 1.0) * 100.0,
+            multiple,
+            cagr * 100.0
+        ),
+        output_to_stdout,
+    );
+
+    if args.generate_csv {
+        write_result(write_portfolio_csv(&results, &wallets[0].1, args), "The portfolio CSV", args)?;
+    }
+    let graph_path = args.svg_out.clone().unwrap_or_else(|| default_graph_path(args));
+    let mut graph_written = false;
+    if args.generate_graph && !results.iter().any(|(_, result)| has_graph_history(result)) {
+        print_diagnostic(NO_GRAPH_HISTORY_WARNING, output_to_stdout);
+    } else if args.generate_graph {
+        graph_written = write_graph(Some(graph_path.clone()), &render_portfolio_svg(&results, args), args, output_to_stdout);
+        write_result(graph_written, "The portfolio graph", args)?;
+    }
+    if let (Some(format), false) = (args.report, output_to_stdout) {
+        let sections: Vec<report::Section> = results
+            .iter()
+            .zip(wallets.iter())
+            .map(|((name, result), (_, pool))| report::Section {
+                name,
+                pool,
+                result,
+                chart: Some(result).filter(|result| has_graph_history(result)).map(|result| render_graph_svg(Some(pool), result, args)),
+            })
+            .collect();
+        let graph = Some(graph_path.as_str()).filter(|_| graph_written);
+        write_result(write_report(format, &sections, graph, args), "The report", args)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,146 @@
+/*
+
+ADA Staking CLI Tool (⚠️ Not Offical or Investment Advice ⚠️)
+Copyright (C) 2022  zulrah93 <1s16slrse@mozmail.com>
+
+This program is free software; you can redistribute it and/or
+modify it under the terms of the GNU General Public License
+as published by the Free Software Foundation; either version 2
+of the License, or (at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program; if not, write to the Free Software
+Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301, USA.
+
+
+*/
+
+// Excel workbook of a run the Daily sheet gets the same rows as the CSV and the Summary sheet the inputs, results and a chart
+use chrono::{Datelike, NaiveDate};
+use rust_xlsxwriter::{Chart, ChartType, ExcelDateTime, Format, Workbook, Worksheet};
+use std::fs::File;
+
+pub use rust_xlsxwriter::XlsxError;
+
+const SUMMARY_SHEET: &str = "Summary";
+const DAILY_SHEET: &str = "Daily";
+const DATE_FORMAT: &str = "yyyy-mm-dd";
+
+// Value of one labeled cell on the Summary sheet
+pub enum Cell {
+    Number(f64, String), // Value and the Excel number format it is shown with
+    Date(NaiveDate),
+    Text(String),
+}
+
+pub type SummaryRows = Vec<(&'static str, Cell)>;
+
+pub struct XlsxWriter {
+    workbook: Workbook,
+    file: File, // Opened before the run like the CSV so an existing file fails before anything is simulated
+    rows: u32,  // Day rows written under the header
+    date_format: Format,
+}
+
+fn excel_date(date: NaiveDate) -> Result<ExcelDateTime, XlsxError> {
+    ExcelDateTime::from_ymd(date.year() as u16, date.month() as u8, date.day() as u8)
+}
+
+fn daily_sheet(workbook: &mut Workbook) -> Result<&mut Worksheet, XlsxError> {
+    workbook.worksheet_from_index(1)
+}
+
+impl XlsxWriter {
+    // Header is the comma separated CSV header one column per name
+    pub fn new(file: File, header: &str) -> Result<XlsxWriter, XlsxError> {
+        let mut workbook = Workbook::new();
+        workbook.add_worksheet().set_name(SUMMARY_SHEET)?;
+        // Rows go out to a temporary file as they are written so a long run never holds every cell in memory
+        let daily = workbook.add_worksheet_with_constant_memory().set_name(DAILY_SHEET)?;
+        let bold = Format::new().set_bold();
+        for (column, name) in header.split(',').enumerate() {
+            daily.write_string_with_format(0, column as u16, name, &bold)?;
+        }
+        daily.set_freeze_panes(1, 0)?;
+        daily.set_column_width(1, 11)?;
+        Ok(XlsxWriter {
+            workbook,
+            file,
+            rows: 0,
+            date_format: Format::new().set_num_format(DATE_FORMAT),
+        })
+    }
+
+    // Cells that read as numbers or dates are written as such so they can be summed and plotted the labels stay text
+    pub fn write_row(&mut self, row: &[String]) -> Result<(), XlsxError> {
+        self.rows += 1;
+        let line = self.rows;
+        let date_format = &self.date_format;
+        let daily = daily_sheet(&mut self.workbook)?;
+        for (column, cell) in row.iter().enumerate() {
+            let column = column as u16;
+            if cell.is_empty() {
+                continue;
+            }
+            if let Some(number) = cell.parse::<f64>().ok().filter(|number| number.is_finite()) {
+                daily.write_number(line, column, number)?;
+            } else if let Ok(date) = NaiveDate::parse_from_str(cell, "%Y-%m-%d") {
+                daily.write_datetime_with_format(line, column, &excel_date(date)?, date_format)?;
+            } else {
+                daily.write_string(line, column, cell)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Fills the Summary sheet and saves the chart plots total_column of the Daily sheet against its Day column
+    pub fn finish(
+        mut self,
+        inputs: &[(&str, Cell)],
+        results: &[(&str, Cell)],
+        total_column: Option<usize>,
+    ) -> Result<(), XlsxError> {
+        let bold = Format::new().set_bold();
+        let rows = self.rows;
+        let summary = self.workbook.worksheet_from_index(0)?;
+        summary.set_column_width(0, 30)?;
+        summary.set_column_width(1, 18)?;
+        let mut line = 0;
+        for (heading, cells) in [("Inputs", inputs), ("Results", results)] {
+            summary.write_string_with_format(line, 0, heading, &bold)?;
+            line += 1;
+            for (label, cell) in cells {
+                summary.write_string(line, 0, *label)?;
+                match cell {
+                    Cell::Number(value, format) => {
+                        summary.write_number_with_format(line, 1, *value, &Format::new().set_num_format(format))?
+                    }
+                    Cell::Date(date) => summary.write_datetime_with_format(line, 1, &excel_date(*date)?, &self.date_format)?,
+                    Cell::Text(text) => summary.write_string(line, 1, text)?,
+                };
+                line += 1;
+            }
+            line += 1; // Blank row between the two blocks
+        }
+        if let (Some(column), true) = (total_column, rows > 0) {
+            let column = column as u16;
+            let mut chart = Chart::new(ChartType::Line);
+            chart
+                .add_series()
+                .set_name("Total")
+                .set_categories((DAILY_SHEET, 1, 0, rows, 0))
+                .set_values((DAILY_SHEET, 1, column, rows, column));
+            chart.title().set_name("ADA Growth");
+            chart.x_axis().set_name("Day");
+            chart.y_axis().set_name("Total");
+            chart.legend().set_hidden();
+            summary.insert_chart(0, 3, &chart)?;
+        }
+        self.workbook.save_to_writer(self.file)
+    }
+}
@@ -0,0 +1,471 @@
+// End to end runs of the binary against the fixture pools each test works in its own temporary directory
+use assert_cmd::Command;
+use std::path::{Path, PathBuf};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name)
+}
+
+// A temporary directory holding the fixture as pool.json so the default lookup finds it
+fn pool_dir(name: &str) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::copy(fixture(name), dir.path().join("pool.json")).unwrap();
+    dir
+}
+
+fn ada_calc(dir: &Path) -> Command {
+    let mut command = Command::cargo_bin("ada_calc").unwrap();
+    command.current_dir(dir).env("NO_COLOR", "1");
+    command
+}
+
+fn stdout(output: &std::process::Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn runs_the_pool_in_the_current_directory() {
+    let dir = pool_dir("pool.json");
+    let output = ada_calc(dir.path()).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(
+        stdout(&output).contains("Final Result: 1051.217114 ADA @ $0.72 = $757.77 Gainz: +51.55% (1.52x)"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn simulate_runs_the_pool_and_a_bare_run_notes_the_deprecation() {
+    let dir = pool_dir("pool.json");
+    let simulate = ada_calc(dir.path()).arg("simulate").output().unwrap();
+    let bare = ada_calc(dir.path()).output().unwrap();
+    assert_eq!(simulate.status.code(), Some(0));
+    assert_eq!(stdout(&simulate), stdout(&bare));
+    let note = "Note: running without a subcommand is deprecated use ada_calc simulate [OPTIONS]";
+    assert!(!String::from_utf8_lossy(&simulate.stderr).contains(note));
+    assert!(String::from_utf8_lossy(&bare.stderr).contains(note));
+
+    let help = ada_calc(dir.path()).args(["simulate", "--help"]).output().unwrap();
+    assert!(stdout(&help).contains("ada_calc simulate [OPTIONS]"), "{}", stdout(&help));
+    assert!(stdout(&help).contains("--svg-out <PATH>"), "{}", stdout(&help));
+
+    let before = ada_calc(dir.path()).args(["-v", "simulate"]).output().unwrap();
+    assert_eq!(before.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&before.stderr).contains("Error: Options of a run go after simulate"));
+}
+
+#[test]
+fn graph_subcommand_takes_the_graph_options_without_generate_graph() {
+    let dir = pool_dir("pool.json");
+    ada_calc(dir.path()).args(["simulate", "-g", "--csv-out", "data.csv"]).assert().success();
+    ada_calc(dir.path())
+        .args(["--graph-format", "svg", "--svg-out", "again.svg", "graph", "--from-csv", "data.csv"])
+        .assert()
+        .success();
+    assert!(dir.path().join("again.svg").exists());
+    let run = ada_calc(dir.path()).args(["simulate", "--svg-out", "run.svg"]).output().unwrap();
+    assert_eq!(run.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&run.stderr).contains("Error: --svg-out needs --generate_graph"));
+}
+
+#[test]
+fn generate_csv_writes_a_row_per_day() {
+    let dir = pool_dir("pool.json");
+    let output = ada_calc(dir.path()).args(["-g", "--csv-out", "data.csv"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let csv = std::fs::read_to_string(dir.path().join("data.csv")).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 367); // The header and the 366 days of 2024
+    assert_eq!(lines[0], "Day,Date,Epoch,ADA,Price,Total,Hold Total,Reward Paid,Cumulative Rewards");
+    assert!(lines[366].starts_with("366,2025-01-01,73,1051.217114,"), "{}", lines[366]);
+}
+
+#[test]
+fn csv_matches_the_golden_file() {
+    let dir = pool_dir("pool.json");
+    ada_calc(dir.path()).args(["-g", "--csv-out", "data.csv"]).assert().success();
+    let written = std::fs::read_to_string(dir.path().join("data.csv")).unwrap();
+    let golden = std::fs::read_to_string(fixture("pool.csv")).unwrap();
+    assert_eq!(written, golden);
+}
+
+#[test]
+fn existing_csv_is_not_replaced_without_force() {
+    let dir = pool_dir("pool.json");
+    std::fs::write(dir.path().join("data.csv"), "keep").unwrap();
+    let output = ada_calc(dir.path()).args(["-g", "--csv-out", "data.csv"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(std::fs::read_to_string(dir.path().join("data.csv")).unwrap(), "keep");
+    ada_calc(dir.path()).args(["-g", "--csv-out", "data.csv", "--force"]).assert().success();
+}
+
+#[test]
+fn missing_pool_file_exits_with_missing_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = ada_calc(dir.path()).args(["-i", "nope.json"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("nope.json"));
+}
+
+#[test]
+fn bad_numeric_flag_exits_with_parse_error() {
+    let dir = pool_dir("pool.json");
+    let output = ada_calc(dir.path()).args(["--years-holding", "ten"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--years-holding"));
+}
+
+#[test]
+fn confidence_sets_the_monte_carlo_band() {
+    let dir = pool_dir("pool.json");
+    let output =
+        ada_calc(dir.path()).args(["--monte-carlo", "20", "--seed", "7", "--confidence", "80"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let out = stdout(&output);
+    assert!(out.contains("10th Percentile Total") && out.contains("90th Percentile Total"), "{}", out);
+    assert!(!out.contains(" 5th Percentile Total"), "{}", out);
+}
+
+#[test]
+fn compare_metric_ranks_scenarios_by_yield() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("scenarios.json"),
+        r#"{"initial_price": 1.0, "daily_price_growth_pct": 0.0, "annual_yield": 0.05, "epoch_in_days": 5, "years_holding": 1,
+            "scenarios": [{"name": "Big", "ada": 100000, "annual_yield": 0.03}, {"name": "Small", "ada": 1000}]}"#,
+    )
+    .unwrap();
+    let rows = |args: &[&str]| -> Vec<String> {
+        let output = ada_calc(dir.path()).args(["-i", "scenarios.json"]).args(args).output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        stdout(&output).lines().filter(|line| line.contains("Gainz")).map(|line| line.trim().to_string()).collect()
+    };
+    let unranked = rows(&[]);
+    assert!(unranked[0].starts_with("Big") && unranked[1].starts_with("Small"), "{:?}", unranked);
+    assert_eq!(rows(&["--compare-metric", "total"]), unranked);
+    let by_yield = rows(&["--compare-metric", "yield"]);
+    assert!(by_yield[0].starts_with("Small") && by_yield[1].starts_with("Big"), "{:?}", by_yield);
+    let by_cagr = rows(&["--compare-metric", "cagr"]);
+    assert!(by_cagr[0].starts_with("Small") && by_cagr[0].contains("CAGR: +"), "{:?}", by_cagr);
+}
+
+#[test]
+fn empty_history_warns_and_skips_the_graph() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("pool.json"),
+        r#"{"ada": 1000, "initial_price": 1.0, "daily_price_growth_pct": 0.0, "annual_yield": 0.05, "years_holding": 0.001}"#,
+    )
+    .unwrap();
+    let output = ada_calc(dir.path()).args(["-G", "--svg-out", "graph.svg"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No History To Graph"));
+    assert!(!dir.path().join("graph.svg").exists());
+}
+
+#[test]
+fn same_seed_gives_identical_monte_carlo_percentiles() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("pool.json"),
+        r#"{"ada": 1000, "initial_price": 0.5, "daily_price_growth_pct": 0.1, "price_volatility": 0.03, "annual_yield": 0.05,
+            "years_holding": 2, "start_date": "2024-01-01"}"#,
+    )
+    .unwrap();
+    let percentiles = |seed: &str| -> Vec<String> {
+        let output = ada_calc(dir.path()).args(["--monte-carlo", "50", "--seed", seed]).output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        stdout(&output).lines().filter(|line| line.contains(" Total:")).map(String::from).collect()
+    };
+    let first = percentiles("42");
+    assert_eq!(first.len(), 6, "{:?}", first); // The mean and the 5th, 25th, 50th, 75th and 95th percentiles
+    assert_eq!(percentiles("42"), first);
+    assert_ne!(percentiles("43"), first);
+}
+
+#[test]
+fn reads_the_pool_from_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+    let pool = std::fs::read_to_string(fixture("pool.json")).unwrap();
+    let output = ada_calc(dir.path()).args(["--input", "-"]).write_stdin(pool).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(
+        stdout(&output).contains("Final Result: 1051.217114 ADA @ $0.72 = $757.77 Gainz: +51.55% (1.52x)"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn stdin_cannot_be_mixed_with_pool_files() {
+    let dir = pool_dir("pool.json");
+    let output = ada_calc(dir.path()).args(["-i", "-", "-i", "pool.json"]).write_stdin("{}").output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be combined with other --input files"));
+    assert!(!stdout(&output).contains("Final Result"));
+}
+
+#[test]
+fn day_zero_and_starting_lines_follow_precision() {
+    let dir = pool_dir("pool.json");
+    let output = ada_calc(dir.path()).args(["-v", "--precision", "4"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let trace = String::from_utf8_lossy(&output.stderr);
+    assert!(trace.contains("Day 0 (2024-01-01): 1000.000000 ADA @ $0.5000 = $500.0000\n"), "{}", trace);
+    assert!(trace.contains("Day 1 (2024-01-02): 1000.000000 ADA @ $0.5005 = $500.5000 [Pay Day: No]"), "{}", trace);
+    let output = ada_calc(dir.path()).args(["--precision", "4"]).output().unwrap();
+    assert!(stdout(&output).contains("Starting Result: 1000.000000 ADA @ $0.5000 = $500.0000\n"), "{}", stdout(&output));
+}
+
+#[test]
+fn declining_price_warns_of_a_net_loss() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("pool.json"),
+        r#"{"ada": 1000, "initial_price": 1.0, "daily_price_growth_pct": -0.2, "annual_yield": 0.05, "years_holding": 1,
+            "start_date": "2024-01-01"}"#,
+    )
+    .unwrap();
+    let output = ada_calc(dir.path()).arg("--warn-negative-yield").output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let out = stdout(&output);
+    assert!(out.contains("= $505.21 Gainz: -49.48% (0.51x)"), "{}", out);
+    assert!(out.contains("CAGR: -49.48% Break-Even: Never"), "{}", out);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Net loss of $494.79 (-49.48%) on $1000.00 put in"));
+}
+
+#[test]
+fn json_output_matches_the_library_run() {
+    let dir = pool_dir("pool.json");
+    let output = ada_calc(dir.path()).arg("--json").output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    // The binary is a front end over the library so calling it directly gives the same numbers
+    let pool: ada_calc::StakedCardanoPool =
+        serde_json::from_str(&std::fs::read_to_string(fixture("pool.json")).unwrap()).unwrap();
+    let result = ada_calc::calculate_staked_pool(&pool, &ada_calc::SimulationOptions::default());
+    assert_eq!(json["final_ada_amount"].as_f64(), Some(result.final_ada_amount));
+    assert_eq!(json["final_ada_price"].as_f64(), Some(result.final_ada_price));
+    assert_eq!(json["total"].as_f64(), Some(result.total()));
+}
+
+#[test]
+fn verbose_trace_has_one_line_per_day() {
+    let dir = pool_dir("pool.json");
+    let trace = |args: &[&str]| -> Vec<String> {
+        let output = ada_calc(dir.path()).arg("-v").args(args).output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        String::from_utf8_lossy(&output.stderr).lines().filter(|line| line.starts_with("Day ")).map(String::from).collect()
+    };
+    let days = trace(&[]);
+    assert_eq!(days.len(), 367); // Day 0 and the 366 days of 2024
+    for (day, line) in days.iter().enumerate().skip(1) {
+        assert!(line.starts_with(&format!("Day {} (", day)), "{}", line);
+        assert!(line.ends_with("[Pay Day: Yes]") || line.ends_with("[Pay Day: No]"), "{}", line);
+    }
+    assert_eq!(days.iter().filter(|line| line.ends_with("[Pay Day: Yes]")).count(), 73);
+    // Flushing every line gives the same trace
+    assert_eq!(trace(&["--unbuffered"]), days);
+}
+
+#[test]
+fn percentage_typed_into_price_yield_is_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let write_pool = |price_yield: &str| {
+        std::fs::write(
+            dir.path().join("pool.json"),
+            format!(r#"{{"ada": 1000, "initial_price": 1.0, "price_yield": {}, "annual_yield": 0.05, "years_holding": 1}}"#, price_yield),
+        )
+        .unwrap()
+    };
+    write_pool("0.01");
+    let output = ada_calc(dir.path()).output().unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("price_yield 0.01 is a daily multiplier"));
+    write_pool("1.001");
+    let output = ada_calc(dir.path()).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("price_yield is deprecated"));
+}
+
+#[test]
+fn anchored_pay_days_show_the_epoch_number() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("pool.json"),
+        r#"{"ada": 1000, "initial_price": 1.0, "daily_price_growth_pct": 0.0, "annual_yield": 0.05, "years_holding": 0.1,
+            "start_date": "2025-02-15", "epoch_anchor_date": "2025-02-13"}"#,
+    )
+    .unwrap();
+    let output = ada_calc(dir.path()).args(["-v", "-g", "--csv-out", "data.csv"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let trace = String::from_utf8_lossy(&output.stderr);
+    assert!(trace.contains("Day 3 (2025-02-18): 1000.410677 ADA @ $1.00 = $1000.41 [Pay Day: Yes] [Epoch 541]"), "{}", trace);
+    assert!(trace.contains("Day 2 (2025-02-17): 1000.000000 ADA @ $1.00 = $1000.00 [Pay Day: No]\n"), "{}", trace);
+    let csv = std::fs::read_to_string(dir.path().join("data.csv")).unwrap();
+    assert!(csv.starts_with("Day,Date,Epoch,"), "{}", csv);
+    assert!(csv.lines().any(|line| line.starts_with("3,2025-02-18,541,")), "{}", csv);
+}
+
+#[test]
+fn reserve_model_writes_the_decay_columns() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("pool.json"),
+        r#"{"ada": 100000, "initial_price": 1.0, "daily_price_growth_pct": 0.0, "annual_yield": 0.05, "years_holding": 2,
+            "start_date": "2024-01-01", "reserve_ada": 7500000000, "total_stake_ada": 22000000000}"#,
+    )
+    .unwrap();
+    ada_calc(dir.path()).args(["-g", "--csv-out", "data.csv"]).assert().success();
+    let csv = std::fs::read_to_string(dir.path().join("data.csv")).unwrap();
+    let lines: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+    let column = |name: &str| lines[0].iter().position(|header| *header == name).unwrap();
+    let (reserve, reserve_yield) = (column("Reserve"), column("Reserve Yield"));
+    let first: (f64, f64) = (lines[1][reserve].parse().unwrap(), lines[1][reserve_yield].parse().unwrap());
+    let last = lines.last().unwrap();
+    let last: (f64, f64) = (last[reserve].parse().unwrap(), last[reserve_yield].parse().unwrap());
+    assert_eq!(first.0, 7500000000.0);
+    assert!(last.0 < first.0 && last.1 < first.1, "{:?} then {:?}", first, last);
+}
+
+#[test]
+fn doubling_in_two_years_reports_the_multiple_return_and_cagr() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("pool.json"),
+        r#"{"ada": 1000, "initial_price": 1.0, "annual_price_growth_pct": 41.42135623730951, "annual_yield": 0, "years_holding": 2,
+            "day_count": "365", "start_date": "2024-01-01"}"#,
+    )
+    .unwrap();
+    let output = ada_calc(dir.path()).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let out = stdout(&output);
+    assert!(out.contains("= $2000.00 Gainz: +100.00% (2.00x)"), "{}", out);
+    assert!(out.contains("CAGR: +41.42%"), "{}", out);
+}
+
+#[test]
+fn day_count_365_runs_a_flat_year() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("pool.json"),
+        r#"{"ada": 1000, "initial_price": 1.0, "daily_price_growth_pct": 0.0, "annual_yield": 0.05, "years_holding": 4,
+            "start_date": "2024-01-01", "day_count": "365"}"#,
+    )
+    .unwrap();
+    ada_calc(dir.path()).args(["-g", "--csv-out", "data.csv"]).assert().success();
+    let csv = std::fs::read_to_string(dir.path().join("data.csv")).unwrap();
+    assert_eq!(csv.lines().count(), 1461); // The header and 4 x 365 days
+    assert!(csv.lines().last().unwrap().starts_with("1460,2027-12-31,"), "{}", csv.lines().last().unwrap());
+    let output = ada_calc(dir.path()).arg("-v").output().unwrap();
+    let trace = String::from_utf8_lossy(&output.stderr);
+    assert!(trace.contains("Day Count: 365 (4 years is 1460 days)"), "{}", trace);
+    assert!(trace.contains("Epochs Per Year: 73 (365 days / 5 day epochs with the 365 day count)"), "{}", trace);
+}
+
+#[test]
+fn basic_csv_columns_keep_the_original_four() {
+    let dir = pool_dir("pool.json");
+    ada_calc(dir.path()).args(["-g", "--csv-out", "data.csv", "--csv-columns", "basic"]).assert().success();
+    let csv = std::fs::read_to_string(dir.path().join("data.csv")).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "Day,ADA,Price,Total");
+    assert!(lines.iter().all(|line| line.split(',').count() == 4));
+    assert!(lines[366].starts_with("366,1051.217114,"), "{}", lines[366]);
+    // The full CSV's last Cumulative Rewards is the Rewards Paid of the summary
+    let output = ada_calc(dir.path()).args(["-g", "--csv-out", "full.csv"]).output().unwrap();
+    assert!(stdout(&output).contains("Rewards Paid: 51.217114 ADA"), "{}", stdout(&output));
+    let full = std::fs::read_to_string(dir.path().join("full.csv")).unwrap();
+    assert!(full.lines().last().unwrap().ends_with(",51.217114"), "{}", full.lines().last().unwrap());
+}
+
+#[test]
+fn misspelled_and_missing_pool_fields_are_explained() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("pool.json"),
+        r#"{"initial_price": 1.0, "daily_price_growth_pct": 0.0, "annual_yeild": 0.05, "years_holding": 1, "colour": 1}"#,
+    )
+    .unwrap();
+    let output = ada_calc(dir.path()).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Warning: unknown field annual_yeild is ignored (did you mean annual_yield?)"), "{}", stderr);
+    assert!(stderr.contains("Warning: unknown field colour is ignored\n"), "{}", stderr);
+    assert!(stderr.contains("missing field `ada`") && stderr.contains("Add it to the pool for example: \"ada\": 10000"), "{}", stderr);
+}
+
+#[test]
+fn colors_never_reach_piped_or_json_output() {
+    let dir = pool_dir("pool.json");
+    let mut piped = Command::cargo_bin("ada_calc").unwrap();
+    let output = piped.current_dir(dir.path()).env_remove("NO_COLOR").arg("--pretty").output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let out = stdout(&output);
+    assert!(out.contains("│ ADA            1,000.000000 │ Final ADA              1,051.217114 │"), "{}", out);
+    assert!(!out.contains('\u{1b}'), "{}", out);
+    let output = ada_calc(dir.path()).args(["--json", "--color"]).output().unwrap();
+    assert!(!stdout(&output).contains('\u{1b}'));
+    assert!(serde_json::from_slice::<serde_json::Value>(&output.stdout).is_ok());
+    // Only --color forces it into a pipe
+    let output = ada_calc(dir.path()).args(["--pretty", "--color"]).output().unwrap();
+    assert!(stdout(&output).contains('\u{1b}'));
+}
+
+#[test]
+fn pool_files_of_earlier_versions_still_load() {
+    // The pool.json shipped before the price growth fields set the old price_yield multiplier
+    let legacy = pool_dir("legacy_pool.json");
+    let output = ada_calc(legacy.path()).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("price_yield is deprecated"));
+    let final_line = |output: &std::process::Output| stdout(output).lines().find(|line| line.starts_with("Final Result:")).map(String::from);
+    let current = tempfile::tempdir().unwrap();
+    std::fs::copy(Path::new(env!("CARGO_MANIFEST_DIR")).join("pool.json"), current.path().join("pool.json")).unwrap();
+    let current = ada_calc(current.path()).output().unwrap();
+    assert!(final_line(&output).is_some());
+    assert_eq!(final_line(&output), final_line(&current));
+}
+
+#[test]
+fn non_positive_fx_rate_exits_with_parse_error() {
+    let dir = pool_dir("pool.json");
+    let output = ada_calc(dir.path()).args(["--currency", "eur", "--fx-rate", "0"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Error: --fx-rate 0 needs to be a positive number of EUR per USD"));
+    assert!(stdout(&output).is_empty());
+}
+
+#[test]
+fn no_reward_delay_keeps_the_baseline_results() {
+    // The version before the reward delay printed these for the pool.json it shipped with its years were 365.25 days and
+    // its Final Result read 6632.3744955191205 ADA @ $3.43 = $22716.80 where every payout is now floored to a whole lovelace
+    let legacy = std::fs::read_to_string(fixture("legacy_pool.json")).unwrap();
+    let pinned = legacy.replacen('{', r#"{"day_count": "365.25", "start_date": "2024-01-01","#, 1);
+    let explicit = pinned.replacen('{', r#"{"reward_lag_epochs": 0,"#, 1);
+    for pool in [pinned, explicit] {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("pool.json"), &pool).unwrap();
+        let output = ada_calc(dir.path()).output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        let out = stdout(&output);
+        assert!(out.contains("Initial ADA Per Year (Excluding Compounding Interest): 323.550000\n"), "{}", out);
+        assert!(out.contains("Starting Result: 6471.000000 ADA @ $0.56 = $3623.76\n"), "{}", out);
+        assert!(out.contains("Final Result: 6632.374475 ADA @ $3.43 = $22716.80 "), "{}", out);
+        assert!(!out.contains("In Flight"), "{}", out);
+    }
+}
+
+#[test]
+fn first_failing_pool_of_several_sets_the_exit_code() {
+    let dir = pool_dir("pool.json");
+    std::fs::write(dir.path().join("broken.json"), r#"{"ada": 1000, "initial_price": 1.0, "annual_yield": 0.05, "years_holding": 0}"#).unwrap();
+    let output = ada_calc(dir.path()).args(["-i", "broken.json", "-i", "pool.json", "-i", "missing.json"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    // The pool after the failure still ran
+    assert!(stdout(&output).contains("Final Result: 1051.217114 ADA"), "{}", stdout(&output));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Error: Failed to find missing.json"), "{}", stderr);
+    assert!(stderr.trim_end().ends_with("Error: 2 of 3 pools failed"), "{}", stderr);
+}